@@ -1,9 +1,16 @@
-use cbor_ld::{DecodeOptions, EncodeOptions};
+use cbor_ld::{
+    diagnostic::{Edn, InvalidCbor},
+    loader::{BundledLoader, ContextDigest, ContextPins},
+    tables::{GenerateOptions, Registry, RegistryEntry, ReservedRegistryEntry},
+    transform::ErrorLocation,
+    CompressionMode, DecodeOptions, EncodeOptions, Tables,
+};
 use clap::Parser;
 use iref::{Iri, IriBuf};
-use json_ld::{syntax::Parse, ChainLoader, FsLoader, Print, ReqwestLoader};
+use json_ld::{syntax::Parse, FsLoader, Print, ReqwestLoader};
 use serde::Deserialize;
 use std::{
+    borrow::Cow,
     collections::BTreeMap,
     fs,
     io::{self, stdout, Read, Write},
@@ -36,18 +43,50 @@ struct Configuration {
     #[serde(default, deserialize_with = "deserialize_mount_map")]
     mount: Vec<Mount>,
 
+    /// Pin a context to its digest.
+    ///
+    /// The value must be of the form `iri=digest`, where `digest` is the
+    /// multibase-encoded SHA-256 multihash of the canonical context document.
+    /// Encoding and decoding fail if the loaded context does not match.
+    #[clap(short, long, global = true)]
+    #[serde(default, deserialize_with = "deserialize_pin_map")]
+    pin: Vec<Pin>,
+
     /// Offline context loader.
     ///
     /// Enabling this option will disable remote context fetching.
     #[clap(short, long, global = true)]
     #[serde(default)]
     offline: bool,
+
+    /// Default compression tables file (TOML).
+    ///
+    /// Such a file can be generated with the `tables generate` command.
+    #[clap(long, global = true)]
+    #[serde(default)]
+    tables: Option<PathBuf>,
+
+    /// Bind the compression tables file to the given application registry
+    /// entry instead of using them as default tables.
+    ///
+    /// Required for tables with a string dictionary. Documents are then
+    /// encoded with this registry entry.
+    #[clap(long, global = true, requires = "tables")]
+    #[serde(default)]
+    registry_entry: Option<u64>,
 }
 
 impl Configuration {
     fn extend(&mut self, other: Self) {
         self.mount.extend(other.mount);
+        self.pin.extend(other.pin);
         self.offline |= other.offline;
+        if self.tables.is_none() {
+            self.tables = other.tables
+        }
+        if self.registry_entry.is_none() {
+            self.registry_entry = other.registry_entry
+        }
     }
 }
 
@@ -60,6 +99,42 @@ enum Command {
         /// Enable hexadecimal encoding.
         #[clap(short = 'x', long)]
         hexadecimal: bool,
+
+        /// Read JSON Lines input, one document per line, and output a CBOR
+        /// sequence.
+        #[clap(short, long)]
+        lines: bool,
+
+        /// Share term allocation and context IDs between the documents of
+        /// the sequence, recording the position of each document in the
+        /// given CBOR tag.
+        #[clap(short, long, value_name = "TAG", requires = "lines")]
+        share: Option<u64>,
+
+        /// Record the original entry order and compact IRI spelling, so that
+        /// decoding restores the exact input document, in a side channel
+        /// wrapped with the given CBOR tag.
+        #[clap(long, value_name = "TAG")]
+        lossless: Option<u64>,
+
+        /// Read an expanded JSON-LD document, compacted with the target
+        /// contexts before encoding.
+        #[clap(short, long, requires = "context", conflicts_with = "lines")]
+        expanded: bool,
+
+        /// Read an RDF dataset in N-Quads syntax, compacted with the target
+        /// contexts before encoding.
+        #[clap(short, long, requires = "context", conflicts_with_all = ["lines", "expanded"])]
+        nquads: bool,
+
+        /// Target context URL, for expanded JSON-LD or N-Quads input.
+        #[clap(short, long)]
+        context: Vec<IriBuf>,
+
+        /// Keep undefined terms uncompressed and leave values that no codec
+        /// can handle as-is, with a warning, instead of failing.
+        #[clap(long)]
+        lenient: bool,
     },
 
     Decode {
@@ -69,9 +144,132 @@ enum Command {
         /// Parse the input file has hexadecimal-encoded.
         #[clap(short = 'x', long)]
         hexadecimal: bool,
+
+        /// Read a CBOR sequence and output JSON Lines, one document per line.
+        #[clap(short, long)]
+        lines: bool,
+
+        /// Share term allocation and context IDs between the documents of
+        /// the sequence, recording the position of each document in the
+        /// given CBOR tag.
+        #[clap(short, long, value_name = "TAG", requires = "lines")]
+        share: Option<u64>,
+
+        /// Restore the exact input document from the lossless side channels
+        /// wrapped with the given CBOR tag.
+        #[clap(long, value_name = "TAG")]
+        lossless: Option<u64>,
+
+        /// Output the expanded JSON-LD document.
+        #[clap(short, long, conflicts_with = "lines")]
+        expanded: bool,
+
+        /// Output the RDF dataset of the document, in N-Quads syntax.
+        #[clap(short, long, conflicts_with_all = ["lines", "expanded"])]
+        nquads: bool,
+
+        /// Leave values that no codec can handle as-is, with a warning,
+        /// instead of failing.
+        #[clap(long)]
+        lenient: bool,
+    },
+
+    /// Encode and decode a JSON-LD document, then compare the RDF datasets
+    /// of the input and decoded documents.
+    Verify {
+        /// Input file.
+        input: Option<PathBuf>,
+    },
+
+    /// Encode a JSON-LD document and report its size, per entry, along with
+    /// the codec or table handling each value.
+    Stats {
+        /// Input file.
+        input: Option<PathBuf>,
+
+        /// Keep undefined terms uncompressed and leave values that no codec
+        /// can handle as-is, with a warning, instead of failing.
+        #[clap(long)]
+        lenient: bool,
+    },
+
+    /// Print a CBOR-LD document in diagnostic notation, annotated with the
+    /// term or context of each ID, the codec or table that handled each
+    /// value, and the byte offset and length of each entry.
+    Inspect {
+        /// Input file.
+        input: Option<PathBuf>,
+
+        /// Parse the input file has hexadecimal-encoded.
+        #[clap(short = 'x', long)]
+        hexadecimal: bool,
+    },
+
+    /// Compression tables.
+    Tables {
+        #[clap(subcommand)]
+        command: TablesCommand,
     },
 }
 
+#[derive(clap::Subcommand)]
+enum TablesCommand {
+    /// Propose compression tables for a corpus of JSON-LD documents, and
+    /// estimate the size savings.
+    ///
+    /// The tables are written in TOML, as loaded by the `--tables` option.
+    Generate {
+        /// Input files. The standard input is read as JSON Lines if none is
+        /// given.
+        inputs: Vec<PathBuf>,
+
+        /// Read the input files as JSON Lines, one document per line.
+        #[clap(short, long)]
+        lines: bool,
+
+        /// Minimum number of occurrences of a typed value for it to be added
+        /// to its type table.
+        #[clap(long, default_value_t = 2)]
+        min_occurrences: usize,
+    },
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("invalid pin value")]
+struct InvalidPinValue;
+
+#[derive(Debug, Clone)]
+struct Pin {
+    iri: IriBuf,
+    digest: ContextDigest,
+}
+
+impl FromStr for Pin {
+    type Err = InvalidPinValue;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (iri, digest) = s.split_once('=').ok_or(InvalidPinValue)?;
+        Ok(Self {
+            iri: IriBuf::new(iri.to_owned()).map_err(|_| InvalidPinValue)?,
+            digest: digest.parse().map_err(|_| InvalidPinValue)?,
+        })
+    }
+}
+
+fn deserialize_pin_map<'de, D: serde::Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Vec<Pin>, D::Error> {
+    BTreeMap::<IriBuf, String>::deserialize(deserializer)?
+        .into_iter()
+        .map(|(iri, digest)| {
+            Ok(Pin {
+                iri,
+                digest: digest.parse().map_err(serde::de::Error::custom)?,
+            })
+        })
+        .collect()
+}
+
 #[derive(Debug, thiserror::Error)]
 #[error("invalid mount value")]
 struct InvalidMountValue;
@@ -107,12 +305,20 @@ fn deserialize_mount_map<'de, D: serde::Deserializer<'de>>(
 #[tokio::main]
 async fn main() -> ExitCode {
     let args = Args::parse();
-    env_logger::init();
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("warn")).init();
 
     match run(args).await {
         Ok(()) => ExitCode::SUCCESS,
         Err(e) => {
             log::error!("{e}");
+
+            if let Some(location) = e.location() {
+                log::error!("  at: {}", location.path);
+                if let Some(term) = &location.term {
+                    log::error!("  term: {term}")
+                }
+            }
+
             ExitCode::FAILURE
         }
     }
@@ -144,23 +350,74 @@ async fn run(args: Args) -> Result<(), Error> {
         fs_loader.mount(m.iri, m.path);
     }
 
-    let loader = if config.offline {
-        Loader::Offline(fs_loader)
-    } else {
-        Loader::Online(ChainLoader::new(fs_loader, ReqwestLoader::new()))
+    let context_pins: ContextPins = config.pin.into_iter().map(|p| (p.iri, p.digest)).collect();
+
+    let mut default_tables: Cow<'static, Tables> = match config.tables {
+        Some(path) => Cow::Owned(toml::from_str(&fs::read_to_string(path)?)?),
+        None => Cow::Owned(Tables::default()),
+    };
+
+    let mut registry = Registry::new();
+    let mut compression_mode = CompressionMode::default();
+    if let Some(id) = config.registry_entry {
+        registry.insert(id, std::mem::take(&mut default_tables).into_owned())?;
+        compression_mode = CompressionMode::Compressed(RegistryEntry::Unknown(id))
+    }
+
+    let loader = Loader {
+        fs: fs_loader,
+        online: (!config.offline).then(ReqwestLoader::new),
     };
 
     match args.command {
-        Command::Encode { input, hexadecimal } => {
+        Command::Encode {
+            input,
+            hexadecimal,
+            lines,
+            share,
+            lossless,
+            expanded,
+            nquads,
+            context,
+            lenient,
+        } => {
             let input = read_input(input)?;
-            let json = cbor_ld::JsonValue::parse_slice(&input)?.0;
 
             let options = EncodeOptions {
                 // context_map,
+                compression_mode,
+                default_table: default_tables,
+                registry,
+                context_pins,
+                shared_sequence_tag: share,
+                lossless_tag: lossless,
+                lenient,
                 ..Default::default()
             };
 
-            let bytes = cbor_ld::encode_to_bytes_with(&json, loader, options).await?;
+            let bytes = if nquads {
+                let dataset: cbor_ld::RdfDataset = std::str::from_utf8(&input)?.parse()?;
+                let cbor_ld_document =
+                    cbor_ld::encode_rdf_with(&dataset, &context, loader, options).await?;
+                cbor_ld::cbor_into_bytes(cbor_ld_document)
+            } else if expanded {
+                let json = cbor_ld::JsonValue::parse_slice(&input)?.0;
+                let cbor_ld_document =
+                    cbor_ld::encode_expanded_with(&json, &context, loader, options).await?;
+                cbor_ld::cbor_into_bytes(cbor_ld_document)
+            } else if lines {
+                let documents = String::from_utf8(input)
+                    .map_err(|e| e.utf8_error())?
+                    .lines()
+                    .filter(|line| !line.trim().is_empty())
+                    .map(|line| cbor_ld::JsonValue::parse_str(line).map(|(json, _)| json))
+                    .collect::<Result<Vec<_>, _>>()?;
+
+                cbor_ld::encode_sequence_to_bytes_with(&documents, loader, options).await?
+            } else {
+                let json = cbor_ld::JsonValue::parse_slice(&input)?.0;
+                cbor_ld::encode_to_bytes_with(&json, loader, options).await?
+            };
 
             if hexadecimal {
                 let hex_bytes = hex::encode(&bytes).into_bytes();
@@ -169,7 +426,16 @@ async fn run(args: Args) -> Result<(), Error> {
                 stdout().write_all(&bytes)?
             }
         }
-        Command::Decode { input, hexadecimal } => {
+        Command::Decode {
+            input,
+            hexadecimal,
+            lines,
+            share,
+            lossless,
+            expanded,
+            nquads,
+            lenient,
+        } => {
             let bytes = if hexadecimal {
                 let hex_bytes = read_input(input)?;
                 hex::decode(hex_bytes)?
@@ -179,11 +445,166 @@ async fn run(args: Args) -> Result<(), Error> {
 
             let options = DecodeOptions {
                 // context_map,
+                default_tables,
+                registry,
+                context_pins,
+                shared_sequence_tag: share,
+                lossless_tag: lossless,
+                lenient,
+                ..Default::default()
+            };
+
+            if expanded || nquads {
+                let cbor_ld_document: cbor_ld::CborValue =
+                    ciborium::from_reader(bytes.as_slice()).map_err(cbor_ld::DecodeError::from)?;
+
+                if nquads {
+                    let dataset =
+                        cbor_ld::decode_to_rdf_with(&cbor_ld_document, loader, options).await?;
+                    print!("{dataset}")
+                } else {
+                    let json =
+                        cbor_ld::decode_expanded_with(&cbor_ld_document, loader, options).await?;
+                    println!("{}", json.pretty_print())
+                }
+            } else if lines {
+                let documents =
+                    cbor_ld::decode_sequence_from_bytes_with(&bytes, loader, options).await?;
+                let mut stdout = stdout().lock();
+                for json in documents {
+                    writeln!(stdout, "{}", json.compact_print())?
+                }
+            } else {
+                let json = cbor_ld::decode_from_bytes_with(&bytes, loader, options).await?;
+                eprintln!("{}", json.pretty_print())
+            }
+        }
+        Command::Verify { input } => {
+            let input = read_input(input)?;
+            let json = cbor_ld::JsonValue::parse_slice(&input)?.0;
+
+            let options = EncodeOptions {
+                compression_mode,
+                default_table: default_tables.clone(),
+                registry: registry.clone(),
+                context_pins: context_pins.clone(),
+                ..Default::default()
+            };
+
+            let decode_options = DecodeOptions {
+                default_tables,
+                registry,
+                context_pins,
+                ..Default::default()
+            };
+
+            let report =
+                cbor_ld::verify::verify_round_trip_with(&json, loader, options, decode_options)
+                    .await?;
+
+            print!("{report}");
+            let differences = report.missing.len() + report.unexpected.len();
+            if differences > 0 {
+                return Err(Error::NotEquivalent(differences));
+            }
+
+            eprintln!(
+                "{} quads preserved ({} bytes)",
+                report.quad_count, report.encoded_len
+            )
+        }
+        Command::Stats { input, lenient } => {
+            let input = read_input(input)?;
+            let json = cbor_ld::JsonValue::parse_slice(&input)?.0;
+
+            let options = EncodeOptions {
+                compression_mode,
+                default_table: default_tables,
+                registry,
+                context_pins,
+                lenient,
+                ..Default::default()
+            };
+
+            let stats = cbor_ld::stats::analyze_with(&json, loader, options).await?;
+            print!("{stats}")
+        }
+        Command::Inspect { input, hexadecimal } => {
+            let bytes = if hexadecimal {
+                let hex_bytes = read_input(input)?;
+                hex::decode(hex_bytes.trim_ascii())?
+            } else {
+                read_input(input)?
+            };
+
+            let edn = Edn::from_bytes(&bytes)?;
+            let cbor_ld_document: cbor_ld::CborValue =
+                ciborium::from_reader(bytes.as_slice()).map_err(cbor_ld::DecodeError::from)?;
+
+            let options = DecodeOptions {
+                default_tables,
+                registry,
+                context_pins,
+                ..Default::default()
+            };
+
+            let annotations =
+                cbor_ld::diagnostic::annotations(&cbor_ld_document, loader, options).await?;
+            println!("{}", edn.annotated(&annotations).with_offsets())
+        }
+        Command::Tables {
+            command:
+                TablesCommand::Generate {
+                    inputs,
+                    lines,
+                    min_occurrences,
+                },
+        } => {
+            let mut corpus = Vec::new();
+            let mut read_corpus = |input: Vec<u8>, lines: bool| -> Result<(), Error> {
+                if lines {
+                    for line in std::str::from_utf8(&input)?.lines() {
+                        if !line.trim().is_empty() {
+                            corpus.push(cbor_ld::JsonValue::parse_str(line)?.0)
+                        }
+                    }
+                } else {
+                    corpus.push(cbor_ld::JsonValue::parse_slice(&input)?.0)
+                }
+
+                Ok(())
+            };
+
+            if inputs.is_empty() {
+                read_corpus(read_input(None)?, true)?
+            } else {
+                for input in inputs {
+                    read_corpus(fs::read(input)?, lines)?
+                }
+            }
+
+            let options = GenerateOptions {
+                encode: EncodeOptions {
+                    compression_mode,
+                    default_table: default_tables,
+                    registry,
+                    context_pins,
+                    ..Default::default()
+                },
+                min_occurrences,
                 ..Default::default()
             };
 
-            let json = cbor_ld::decode_from_bytes_with(&bytes, loader, options).await?;
-            eprintln!("{}", json.pretty_print())
+            let generated = cbor_ld::tables::generate_tables(&corpus, loader, options).await?;
+            print!("{}", toml::to_string(&generated.tables)?);
+
+            eprintln!(
+                "{} documents: {} bytes with the current tables, {} bytes with the proposed tables ({} bytes saved)",
+                generated.documents,
+                generated.size_before,
+                generated.size_after,
+                generated.saved()
+            )
         }
     }
 
@@ -195,35 +616,75 @@ enum Error {
     #[error(transparent)]
     IO(#[from] io::Error),
 
-    #[error("unable to read TOML configuration file: {0}")]
+    #[error("unable to read TOML file: {0}")]
     Toml(#[from] toml::de::Error),
 
+    #[error("unable to write TOML tables file: {0}")]
+    TomlWrite(#[from] toml::ser::Error),
+
     #[error("invalid JSON: {0}")]
     Json(#[from] json_ld::syntax::parse::Error),
 
+    #[error(transparent)]
+    NQuads(#[from] cbor_ld::rdf::InvalidNQuads),
+
+    #[error(transparent)]
+    RegistryEntry(#[from] ReservedRegistryEntry),
+
     #[error(transparent)]
     Hex(#[from] hex::FromHexError),
 
-    #[error("encoding failed: {0}")]
+    #[error("invalid CBOR input: {0}")]
+    Cbor(#[from] InvalidCbor),
+
+    #[error("invalid UTF-8 input: {0}")]
+    Utf8(#[from] std::str::Utf8Error),
+
+    #[error("encoding failed: {}", .0.without_location())]
     Encode(#[from] cbor_ld::EncodeError),
 
-    #[error("decoding failed: {0}")]
+    #[error("decoding failed: {}", .0.without_location())]
     Decode(#[from] cbor_ld::DecodeError),
 
+    #[error("verification failed: {0}")]
+    Verify(#[from] cbor_ld::verify::VerifyError),
+
+    #[error("round trip is not semantically equivalent ({0} differences)")]
+    NotEquivalent(usize),
+
     #[error(transparent)]
     Write(#[from] ciborium::ser::Error<io::Error>),
 }
 
-enum Loader {
-    Offline(FsLoader),
-    Online(ChainLoader<FsLoader, ReqwestLoader>),
+impl Error {
+    /// Location of the encoding or decoding error in the input document.
+    fn location(&self) -> Option<&ErrorLocation> {
+        match self {
+            Self::Encode(e) => e.location(),
+            Self::Decode(e) => e.location(),
+            _ => None,
+        }
+    }
+}
+
+/// Context loader.
+///
+/// Contexts are loaded from the mount points first, then from the bundled
+/// contexts, and finally fetched online unless offline mode is enabled.
+struct Loader {
+    fs: FsLoader,
+    online: Option<ReqwestLoader>,
 }
 
 impl json_ld::Loader for Loader {
     async fn load(&self, url: &Iri) -> json_ld::LoadingResult<IriBuf> {
-        match self {
-            Self::Offline(l) => l.load(url).await,
-            Self::Online(l) => l.load(url).await,
+        if let Ok(document) = self.fs.load(url).await {
+            return Ok(document);
+        }
+
+        match (BundledLoader.load(url).await, &self.online) {
+            (Err(_), Some(l)) => l.load(url).await,
+            (result, _) => result,
         }
     }
 }
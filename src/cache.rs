@@ -0,0 +1,139 @@
+//! Processed JSON-LD context cache.
+use std::{
+    collections::{hash_map::DefaultHasher, HashMap},
+    fmt,
+    hash::{Hash, Hasher},
+    sync::{Arc, RwLock},
+};
+
+use json_ld::syntax::{context::ContextEntry, is_keyword};
+
+/// Fingerprint of the initial (empty) active context.
+pub const EMPTY_CONTEXT_FINGERPRINT: u64 = 0;
+
+/// Processed context cache key.
+///
+/// Identifies a local context processed on top of an active context.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ContextCacheKey {
+    /// Fingerprint of the active context.
+    pub active_context: u64,
+
+    /// Context IRI, or debug representation for inline contexts.
+    pub context: String,
+
+    /// Whether or not the context propagates.
+    pub propagate: bool,
+}
+
+impl ContextCacheKey {
+    pub fn new(active_context: u64, context: &json_ld::syntax::Context, propagate: bool) -> Self {
+        let context = match context {
+            json_ld::syntax::Context::One(ContextEntry::IriRef(iri_ref)) => {
+                iri_ref.as_str().to_owned()
+            }
+            other => format!("{other:?}"),
+        };
+
+        Self {
+            active_context,
+            context,
+            propagate,
+        }
+    }
+
+    /// Fingerprint of the resulting active context.
+    ///
+    /// Since contexts are only processed on top of the initial context or of
+    /// a previously processed one, the fingerprint identifies the whole
+    /// chain of processed contexts.
+    pub fn fingerprint(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        self.hash(&mut hasher);
+        hasher.finish()
+    }
+}
+
+/// Processed context.
+#[derive(Clone)]
+pub struct ProcessedContext {
+    /// Resulting active context.
+    pub context: json_ld::Context,
+
+    /// Fingerprint of the resulting active context.
+    pub fingerprint: u64,
+
+    /// Non-keyword terms defined by the resulting active context, sorted, as
+    /// used for term ID allocation.
+    pub terms: Vec<String>,
+}
+
+impl ProcessedContext {
+    pub fn new(context: json_ld::Context, fingerprint: u64) -> Self {
+        let mut terms: Vec<_> = context
+            .definitions()
+            .iter()
+            .map(|d| d.term().as_str())
+            .filter(|term| !is_keyword(term))
+            .map(ToOwned::to_owned)
+            .collect();
+        terms.sort_unstable();
+
+        Self {
+            context,
+            fingerprint,
+            terms,
+        }
+    }
+}
+
+/// Thread-safe processed context cache.
+///
+/// Can be shared between [`Encoder`](crate::Encoder) and
+/// [`Decoder`](crate::Decoder) instances using an [`Arc`]. The cache assumes
+/// that the context loader always returns the same context for a given URL
+/// (see [`PinnedLoader`](crate::loader::PinnedLoader)).
+#[derive(Default)]
+pub struct ContextCache {
+    entries: RwLock<HashMap<ContextCacheKey, Arc<ProcessedContext>>>,
+}
+
+impl ContextCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.read().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn get(&self, key: &ContextCacheKey) -> Option<Arc<ProcessedContext>> {
+        self.entries.read().unwrap().get(key).cloned()
+    }
+
+    pub fn insert(
+        &self,
+        key: ContextCacheKey,
+        processed: ProcessedContext,
+    ) -> Arc<ProcessedContext> {
+        let processed = Arc::new(processed);
+        self.entries.write().unwrap().insert(key, processed.clone());
+        processed
+    }
+
+    pub fn clear(&self) {
+        self.entries.write().unwrap().clear()
+    }
+}
+
+impl fmt::Debug for ContextCache {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ContextCache")
+            .field("len", &self.len())
+            .finish()
+    }
+}
@@ -0,0 +1,167 @@
+use json_syntax::Parse;
+
+use crate::{CborValue, DecodeError, EncodeError, JsonObject, JsonValue};
+
+/// Embedded JSON tag, holding the JSON text in a byte string.
+///
+/// See <https://www.iana.org/assignments/cbor-tags>.
+const EMBEDDED_JSON_TAG: u64 = 262;
+
+/// JSON literal (`@json` typed values) codec.
+///
+/// JSON literals are encoded as native CBOR values, without any term
+/// substitution: objects become maps with text keys, arrays become arrays,
+/// and numbers become integers or floats. Numbers that neither form
+/// represents exactly (e.g. `1.0` or `1e3`) are stored in their lexical form,
+/// as embedded JSON (tag 262).
+#[derive(Debug, Default, Clone, Copy)]
+pub struct JsonCodec {
+    /// Put JSON literals in canonical form (JCS, RFC 8785) before encoding
+    /// them.
+    pub canonicalize: bool,
+}
+
+impl JsonCodec {
+    pub fn new(canonicalize: bool) -> Self {
+        Self { canonicalize }
+    }
+
+    pub fn encode(&self, value: &JsonValue) -> Result<CborValue, EncodeError> {
+        if self.canonicalize {
+            let mut value = value.clone();
+            value.canonicalize();
+            json_to_cbor(&value)
+        } else {
+            json_to_cbor(value)
+        }
+    }
+
+    pub fn decode(&self, value: &CborValue) -> Result<JsonValue, DecodeError> {
+        cbor_to_json(value)
+    }
+}
+
+fn json_to_cbor(value: &JsonValue) -> Result<CborValue, EncodeError> {
+    match value {
+        JsonValue::Null => Ok(CborValue::Null),
+        JsonValue::Boolean(b) => Ok(CborValue::Bool(*b)),
+        JsonValue::Number(n) => {
+            if let Some(u) = n.as_u64() {
+                if u.to_string() == n.as_str() {
+                    return Ok(CborValue::Integer(u.into()));
+                }
+            }
+
+            if let Some(i) = n.as_i64() {
+                if i.to_string() == n.as_str() {
+                    return Ok(CborValue::Integer(i.into()));
+                }
+            }
+
+            let f = n.as_f64_lossy();
+            match json_ld::syntax::NumberBuf::try_from(f) {
+                Ok(m) if m.as_str() == n.as_str() => Ok(CborValue::Float(f)),
+                _ => Ok(CborValue::Tag(
+                    EMBEDDED_JSON_TAG,
+                    Box::new(CborValue::Bytes(n.as_str().as_bytes().to_vec())),
+                )),
+            }
+        }
+        JsonValue::String(s) => Ok(CborValue::Text(s.as_str().to_owned())),
+        JsonValue::Array(items) => items
+            .iter()
+            .map(json_to_cbor)
+            .collect::<Result<_, _>>()
+            .map(CborValue::Array),
+        JsonValue::Object(object) => object
+            .iter()
+            .map(|entry| {
+                Ok((
+                    CborValue::Text(entry.key.as_str().to_owned()),
+                    json_to_cbor(&entry.value)?,
+                ))
+            })
+            .collect::<Result<_, _>>()
+            .map(CborValue::Map),
+    }
+}
+
+fn cbor_to_json(value: &CborValue) -> Result<JsonValue, DecodeError> {
+    match value {
+        CborValue::Null => Ok(JsonValue::Null),
+        CborValue::Bool(b) => Ok(JsonValue::Boolean(*b)),
+        CborValue::Integer(n) => {
+            let n: i128 = (*n).into();
+            Ok(JsonValue::Number(n.to_string().parse().unwrap()))
+        }
+        CborValue::Float(f) => Ok(JsonValue::Number(
+            (*f).try_into().map_err(|_| DecodeError::NonFiniteFloat)?,
+        )),
+        CborValue::Text(s) => Ok(JsonValue::String(s.as_str().into())),
+        CborValue::Tag(EMBEDDED_JSON_TAG, value) => {
+            let bytes = value.as_bytes().ok_or_else(|| {
+                DecodeError::Codec("json", "expected embedded JSON bytes".to_owned())
+            })?;
+            JsonValue::parse_slice(bytes)
+                .map(|(value, _)| value)
+                .map_err(|e| DecodeError::Codec("json", format!("invalid embedded JSON: {e}")))
+        }
+        CborValue::Array(items) => items
+            .iter()
+            .map(cbor_to_json)
+            .collect::<Result<_, _>>()
+            .map(JsonValue::Array),
+        CborValue::Map(entries) => {
+            let mut object = JsonObject::new();
+
+            for (key, value) in entries {
+                let key = key
+                    .as_text()
+                    .ok_or_else(|| DecodeError::Codec("json", "expected text key".to_owned()))?;
+                object.push(key.into(), cbor_to_json(value)?);
+            }
+
+            Ok(JsonValue::Object(object))
+        }
+        _ => Err(DecodeError::Codec(
+            "json",
+            "unexpected CBOR value".to_owned(),
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::JsonCodec;
+    use crate::JsonValue;
+
+    #[test]
+    fn round_trip() {
+        let codec = JsonCodec::default();
+        let json: JsonValue = r#"{"b": [1, -2, 1.5, true, null], "a": {"@id": "not a term"}}"#
+            .parse()
+            .unwrap();
+        let cbor = codec.encode(&json).unwrap();
+        assert_eq!(codec.decode(&cbor).unwrap(), json)
+    }
+
+    #[test]
+    fn canonicalize() {
+        let codec = JsonCodec::new(true);
+        let json: JsonValue = r#"{"b": 1.50e1, "a": 1}"#.parse().unwrap();
+        let expected: JsonValue = r#"{"a": 1, "b": 15}"#.parse().unwrap();
+        let cbor = codec.encode(&json).unwrap();
+        assert_eq!(codec.decode(&cbor).unwrap(), expected)
+    }
+
+    #[test]
+    fn lexical_number() {
+        let codec = JsonCodec::default();
+        for input in ["1.0", "1.50", "1e3", "1E+3", "-0.0", "-0", "0.1e-7"] {
+            let json: JsonValue = input.parse().unwrap();
+            let cbor = codec.encode(&json).unwrap();
+            let decoded = codec.decode(&cbor).unwrap();
+            assert_eq!(decoded.as_number().unwrap().as_str(), input)
+        }
+    }
+}
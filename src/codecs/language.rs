@@ -0,0 +1,178 @@
+use crate::{CborValue, DecodeError, TypeTable};
+
+/// Common BCP 47 language tags and their compressed identifiers.
+///
+/// The most common tags come first so they are encoded on a single byte.
+/// Identifiers are part of the CBOR-LD output and must never be changed.
+pub const COMMON_LANGUAGE_TAGS: [(&str, u64); 72] = [
+    ("en", 0),
+    ("fr", 1),
+    ("de", 2),
+    ("es", 3),
+    ("it", 4),
+    ("pt", 5),
+    ("nl", 6),
+    ("ja", 7),
+    ("zh", 8),
+    ("ko", 9),
+    ("ar", 10),
+    ("ru", 11),
+    ("pl", 12),
+    ("sv", 13),
+    ("da", 14),
+    ("fi", 15),
+    ("no", 16),
+    ("nb", 17),
+    ("el", 18),
+    ("tr", 19),
+    ("he", 20),
+    ("hi", 21),
+    ("uk", 22),
+    ("cs", 23),
+    ("en-US", 24),
+    ("en-GB", 25),
+    ("en-CA", 26),
+    ("en-AU", 27),
+    ("en-IE", 28),
+    ("en-IN", 29),
+    ("en-NZ", 30),
+    ("fr-FR", 31),
+    ("fr-CA", 32),
+    ("fr-BE", 33),
+    ("fr-CH", 34),
+    ("de-DE", 35),
+    ("de-AT", 36),
+    ("de-CH", 37),
+    ("es-ES", 38),
+    ("es-MX", 39),
+    ("es-419", 40),
+    ("it-IT", 41),
+    ("pt-BR", 42),
+    ("pt-PT", 43),
+    ("nl-NL", 44),
+    ("nl-BE", 45),
+    ("zh-CN", 46),
+    ("zh-TW", 47),
+    ("zh-Hans", 48),
+    ("zh-Hant", 49),
+    ("bg", 50),
+    ("bn", 51),
+    ("ca", 52),
+    ("et", 53),
+    ("eu", 54),
+    ("fa", 55),
+    ("ga", 56),
+    ("gl", 57),
+    ("hr", 58),
+    ("hu", 59),
+    ("id", 60),
+    ("is", 61),
+    ("lt", 62),
+    ("lv", 63),
+    ("ms", 64),
+    ("mt", 65),
+    ("nn", 66),
+    ("ro", 67),
+    ("sk", 68),
+    ("sl", 69),
+    ("sr", 70),
+    ("th", 71),
+];
+
+/// BCP 47 language tag codec.
+///
+/// Used for `@language` values and language map keys. Known tags are encoded
+/// as integers, other tags are kept as text. Tags are matched exactly (and
+/// not case-insensitively) so that decoding gives back the original spelling.
+///
+/// The default codec knows no tag, so that other CBOR-LD implementations can
+/// decode its output. Use [`LanguageCodec::common`] on both ends to compress
+/// the [`COMMON_LANGUAGE_TAGS`].
+#[derive(Debug, Clone)]
+pub struct LanguageCodec {
+    table: TypeTable,
+}
+
+impl LanguageCodec {
+    /// Creates a new language tag codec with an empty table.
+    pub fn new() -> Self {
+        Self {
+            table: TypeTable::new(),
+        }
+    }
+
+    /// Creates a new language tag codec knowing the
+    /// [`COMMON_LANGUAGE_TAGS`].
+    pub fn common() -> Self {
+        Self {
+            table: COMMON_LANGUAGE_TAGS.into_iter().collect(),
+        }
+    }
+
+    pub fn insert(&mut self, tag: String, id: u64) {
+        self.table.insert(tag, id)
+    }
+
+    pub fn encode(&self, tag: &str) -> CborValue {
+        self.table.encode(tag)
+    }
+
+    pub fn decode(&self, value: &CborValue) -> Result<String, DecodeError> {
+        match value {
+            CborValue::Integer(id) => {
+                let id: u64 = (*id)
+                    .try_into()
+                    .map_err(|_| DecodeError::Codec("language", "invalid ID".to_owned()))?;
+
+                self.table
+                    .get_value(id)
+                    .map(ToOwned::to_owned)
+                    .ok_or_else(|| DecodeError::Codec("language", "unknown ID".to_owned()))
+            }
+            CborValue::Text(tag) => Ok(tag.clone()),
+            _ => Err(DecodeError::Codec(
+                "language",
+                "expected integer or text".to_owned(),
+            )),
+        }
+    }
+}
+
+impl Default for LanguageCodec {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{LanguageCodec, COMMON_LANGUAGE_TAGS};
+    use crate::CborValue;
+    use std::collections::HashSet;
+
+    #[test]
+    fn unique_ids() {
+        let ids: HashSet<_> = COMMON_LANGUAGE_TAGS.iter().map(|(_, id)| id).collect();
+        assert_eq!(ids.len(), COMMON_LANGUAGE_TAGS.len())
+    }
+
+    #[test]
+    fn round_trip() {
+        let codec = LanguageCodec::common();
+
+        for tag in ["en", "fr-CA", "EN", "sr-Latn-RS", "@none"] {
+            let encoded = codec.encode(tag);
+            assert_eq!(codec.decode(&encoded).unwrap(), tag)
+        }
+
+        assert_eq!(codec.encode("en"), CborValue::Integer(0.into()));
+        assert_eq!(codec.encode("EN"), CborValue::Text("EN".to_owned()));
+    }
+
+    #[test]
+    fn default_is_text() {
+        let codec = LanguageCodec::default();
+        assert_eq!(codec.encode("en"), CborValue::Text("en".to_owned()));
+        assert!(codec.decode(&CborValue::Integer(0.into())).is_err())
+    }
+}
@@ -23,6 +23,29 @@ pub use xsd_date::*;
 mod xsd_date_time;
 pub use xsd_date_time::*;
 
+mod xsd_time;
+pub use xsd_time::*;
+
+mod xsd_g_year;
+pub use xsd_g_year::*;
+
+mod xsd_g_year_month;
+pub use xsd_g_year_month::*;
+
+mod xsd_duration;
+pub use xsd_duration::*;
+
+mod xsd_lexical;
+
+mod string;
+pub use string::*;
+
+mod json;
+pub use json::*;
+
+mod language;
+pub use language::*;
+
 pub trait TypeCodec: Send + Sync {
     fn encode(
         &self,
@@ -57,6 +80,34 @@ impl TypeCodecs {
     pub fn insert(&mut self, type_: json_ld::Type<IriBuf>, encoder: impl 'static + TypeCodec) {
         self.map.insert(type_, Box::new(encoder));
     }
+
+    /// Creates the default type codecs, along with the [`XsdTimeCodec`],
+    /// [`XsdGYearCodec`], [`XsdGYearMonthCodec`] and [`XsdDurationCodec`].
+    ///
+    /// Other CBOR-LD implementations do not know these codecs: they must be
+    /// used on both ends.
+    pub fn temporal() -> Self {
+        let mut result = Self::default();
+
+        result.insert(
+            json_ld::Type::Iri(xsd_types::XSD_TIME.to_owned()),
+            XsdTimeCodec,
+        );
+        result.insert(
+            json_ld::Type::Iri(xsd_types::XSD_G_YEAR.to_owned()),
+            XsdGYearCodec,
+        );
+        result.insert(
+            json_ld::Type::Iri(xsd_types::XSD_G_YEAR_MONTH.to_owned()),
+            XsdGYearMonthCodec,
+        );
+        result.insert(
+            json_ld::Type::Iri(xsd_types::XSD_DURATION.to_owned()),
+            XsdDurationCodec,
+        );
+
+        result
+    }
 }
 
 impl fmt::Debug for TypeCodecs {
@@ -80,15 +131,44 @@ impl Default for TypeCodecs {
         );
         result.insert(
             json_ld::Type::Iri(xsd_types::XSD_DATE_TIME.to_owned()),
-            XsdDateTimeCodec,
+            XsdDateTimeCodec::default(),
         );
 
         result
     }
 }
 
-#[derive(Debug, Default)]
+/// Codecs used to compress values.
+#[derive(Debug)]
 pub struct Codecs {
     pub iri: IriCodecs,
     pub type_: TypeCodecs,
+    pub json: JsonCodec,
+    pub language: LanguageCodec,
+}
+
+impl Default for Codecs {
+    /// Default codecs, producing output that other CBOR-LD implementations
+    /// can decode.
+    ///
+    /// For this reason, the [`XsdDateTimeCodec`] only uses the standard
+    /// seconds and milliseconds forms: `xsd:dateTime` values with a timezone
+    /// offset other than `Z`, without timezone, or with a precision other
+    /// than seconds or milliseconds are stored as text. They are still
+    /// reconstructed exactly. Register an [`XsdDateTimeCodec`] with
+    /// [`extended_forms`](XsdDateTimeCodec::extended_forms) enabled to
+    /// compress them as well, at the cost of interoperability.
+    ///
+    /// Likewise, language tags are stored as text. Use
+    /// [`LanguageCodec::common`] to compress common tags. `xsd:time`,
+    /// `xsd:gYear`, `xsd:gYearMonth` and `xsd:duration` values are stored as
+    /// text too, unless the [`TypeCodecs::temporal`] codecs are used.
+    fn default() -> Self {
+        Self {
+            iri: IriCodecs::default(),
+            type_: TypeCodecs::default(),
+            json: JsonCodec::default(),
+            language: LanguageCodec::default(),
+        }
+    }
 }
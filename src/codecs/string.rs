@@ -0,0 +1,238 @@
+use std::collections::HashMap;
+
+use crate::{tables::StringDictionary, CborValue, DecodeError};
+
+/// Minimum length of strings that can be referenced again later in the same
+/// document.
+///
+/// Shorter strings are never cheaper to encode as a back-reference.
+pub const MIN_BACK_REFERENCE_LEN: usize = 6;
+
+/// Minimum length of a dictionary match inside a string.
+const MIN_MATCH_LEN: usize = 3;
+
+const CODEC: &str = "string";
+
+/// Dictionary-based string literal codec.
+///
+/// Compressed strings are tagged with the dictionary
+/// [tag](StringDictionary::tag). The tagged value is either:
+///   - a non-negative integer `i`, the `i`-th dictionary entry;
+///   - a negative integer `-1 - n`, a back-reference to the `n`-th distinct
+///     string (of at least [`MIN_BACK_REFERENCE_LEN`] bytes) previously seen
+///     in the document;
+///   - an array of text and dictionary entry indexes to concatenate.
+///
+/// Strings are only compressed when it makes them smaller, otherwise they are
+/// kept as text. Back-references are scoped to a single document, which is
+/// why the codec must be reset between documents.
+#[derive(Debug, Default, Clone)]
+pub struct StringCodec {
+    references: Vec<String>,
+    ids: HashMap<String, u64>,
+}
+
+impl StringCodec {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Forgets every string seen so far.
+    pub fn reset(&mut self) {
+        self.references.clear();
+        self.ids.clear()
+    }
+
+    pub fn encode(&mut self, dictionary: &StringDictionary, value: &str) -> CborValue {
+        if let Some(n) = self.ids.get(value) {
+            return tagged(dictionary, CborValue::Integer((-1 - *n as i64).into()));
+        }
+
+        self.remember(value);
+
+        let text = CborValue::Text(value.to_owned());
+        match compress(dictionary, value) {
+            Some(compressed) if encoded_len(&compressed) < encoded_len(&text) => compressed,
+            // No compression.
+            _ => text,
+        }
+    }
+
+    pub fn decode(
+        &mut self,
+        dictionary: &StringDictionary,
+        value: &CborValue,
+    ) -> Result<String, DecodeError> {
+        let value = match value {
+            CborValue::Text(text) => text.clone(),
+            CborValue::Tag(tag, value) if *tag == dictionary.tag() => match value.as_ref() {
+                CborValue::Integer(i) => {
+                    let i: i128 = (*i).into();
+                    if i < 0 {
+                        return usize::try_from(-1 - i)
+                            .ok()
+                            .and_then(|n| self.references.get(n))
+                            .cloned()
+                            .ok_or_else(|| {
+                                DecodeError::Codec(CODEC, "unknown back-reference".to_owned())
+                            });
+                    }
+
+                    entry(dictionary, i)?.to_owned()
+                }
+                CborValue::Array(parts) => {
+                    let mut result = String::new();
+
+                    for part in parts {
+                        match part {
+                            CborValue::Text(text) => result.push_str(text),
+                            CborValue::Integer(i) => {
+                                result.push_str(entry(dictionary, (*i).into())?)
+                            }
+                            _ => {
+                                return Err(DecodeError::Codec(
+                                    CODEC,
+                                    "expected text or integer part".to_owned(),
+                                ))
+                            }
+                        }
+                    }
+
+                    result
+                }
+                _ => {
+                    return Err(DecodeError::Codec(
+                        CODEC,
+                        "expected integer or array".to_owned(),
+                    ))
+                }
+            },
+            _ => return Err(DecodeError::InvalidValue),
+        };
+
+        self.remember(&value);
+        Ok(value)
+    }
+
+    fn remember(&mut self, value: &str) {
+        if value.len() >= MIN_BACK_REFERENCE_LEN && !self.ids.contains_key(value) {
+            self.ids
+                .insert(value.to_owned(), self.references.len() as u64);
+            self.references.push(value.to_owned())
+        }
+    }
+}
+
+fn tagged(dictionary: &StringDictionary, value: CborValue) -> CborValue {
+    CborValue::Tag(dictionary.tag(), Box::new(value))
+}
+
+fn entry(dictionary: &StringDictionary, i: i128) -> Result<&str, DecodeError> {
+    u64::try_from(i)
+        .ok()
+        .and_then(|i| dictionary.get(i))
+        .ok_or_else(|| DecodeError::Codec(CODEC, "unknown dictionary entry".to_owned()))
+}
+
+/// Greedily replaces the longest dictionary matches of `value`.
+fn compress(dictionary: &StringDictionary, value: &str) -> Option<CborValue> {
+    let mut parts = Vec::new();
+    let mut literal_start = 0;
+    let mut i = 0;
+
+    while i < value.len() {
+        match dictionary.longest_prefix_of(&value[i..]) {
+            Some((index, len)) if len >= MIN_MATCH_LEN => {
+                if literal_start < i {
+                    parts.push(CborValue::Text(value[literal_start..i].to_owned()))
+                }
+
+                parts.push(CborValue::Integer(index.into()));
+                i += len;
+                literal_start = i
+            }
+            _ => i += value[i..].chars().next().unwrap().len_utf8(),
+        }
+    }
+
+    if literal_start == 0 {
+        return None;
+    }
+
+    if literal_start < value.len() {
+        parts.push(CborValue::Text(value[literal_start..].to_owned()))
+    }
+
+    if parts.len() == 1 {
+        Some(tagged(dictionary, parts.pop().unwrap()))
+    } else {
+        Some(tagged(dictionary, CborValue::Array(parts)))
+    }
+}
+
+fn encoded_len(value: &CborValue) -> usize {
+    let mut bytes = Vec::new();
+    ciborium::into_writer(value, &mut bytes).unwrap();
+    bytes.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::StringCodec;
+    use crate::{tables::StringDictionary, CborValue};
+    use proptest::prelude::*;
+
+    const TAG: u64 = 0xa000;
+
+    proptest! {
+        #[test]
+        fn round_trip(values in prop::collection::vec("(Permanent |did:example:|[a-z ]{0,8}|é)*", 0..8)) {
+            let dictionary = StringDictionary::credentials(TAG);
+            let mut encoder = StringCodec::new();
+            let mut decoder = StringCodec::new();
+
+            for value in values {
+                let encoded = encoder.encode(&dictionary, &value);
+                prop_assert_eq!(decoder.decode(&dictionary, &encoded).unwrap(), value);
+            }
+        }
+    }
+
+    #[test]
+    fn compress_string() {
+        let dictionary = StringDictionary::credentials(TAG);
+        let mut codec = StringCodec::new();
+
+        assert_eq!(
+            codec.encode(&dictionary, "Male"),
+            CborValue::Tag(TAG, Box::new(CborValue::Integer(14.into())))
+        );
+        assert_eq!(
+            codec.encode(
+                &dictionary,
+                "Government of Example Permanent Resident Card."
+            ),
+            CborValue::Tag(
+                TAG,
+                Box::new(CborValue::Array(vec![
+                    CborValue::Integer(7.into()),
+                    CborValue::Integer(17.into()),
+                    CborValue::Text(" ".to_owned()),
+                    CborValue::Integer(5.into()),
+                    CborValue::Text(".".to_owned()),
+                ]))
+            )
+        );
+        assert_eq!(
+            codec.encode(&dictionary, "JOHN"),
+            CborValue::Text("JOHN".to_owned())
+        );
+        assert_eq!(
+            codec.encode(
+                &dictionary,
+                "Government of Example Permanent Resident Card."
+            ),
+            CborValue::Tag(TAG, Box::new(CborValue::Integer((-1).into())))
+        );
+    }
+}
@@ -1,9 +1,60 @@
-use super::TypeCodec;
+use super::{
+    xsd_lexical::{
+        decode_integer, decode_optional_fraction, encode_optional_fraction, first_exact,
+        format_year, split_fraction, Timezone,
+    },
+    TypeCodec,
+};
 use crate::{transform::TransformerState, CborValue, DecodeError, EncodeError};
-use chrono::{TimeZone, Utc};
-use std::str::FromStr;
+use chrono::{DateTime, Datelike, NaiveDateTime, Timelike};
 
-pub struct XsdDateTimeCodec;
+/// `xsd:dateTime` codec.
+///
+/// Values are compressed into one of the following forms:
+/// - an integer, the number of seconds since the UNIX epoch, for values
+///   without fractional seconds using the `Z` timezone;
+/// - a `[seconds, milliseconds]` array for values with exactly three
+///   fractional digits using the `Z` timezone;
+/// - a `[seconds, fraction, timezone]` array for any other value, if
+///   [`Self::extended_forms`] is enabled.
+///
+/// In the extended form, `fraction` is either `null` or a decimal fraction
+/// (tag 4) preserving the number of fractional digits, and `timezone` is
+/// either `null` (no timezone), an offset in minutes (`0` meaning `Z`) or the
+/// original timezone text.
+///
+/// A value is only compressed if it can be decoded back to the exact same
+/// lexical form, otherwise it is stored as text.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct XsdDateTimeCodec {
+    /// Enables the extended `[seconds, fraction, timezone]` form.
+    ///
+    /// This form is not understood by other CBOR-LD implementations and is
+    /// hence disabled by default. It is always accepted by the decoder.
+    pub extended_forms: bool,
+}
+
+impl XsdDateTimeCodec {
+    pub fn new(extended_forms: bool) -> Self {
+        Self { extended_forms }
+    }
+
+    fn compress(&self, value: &str) -> Option<CborValue> {
+        let date_time = LexicalDateTime::parse(value)?;
+
+        let candidates = [
+            date_time.seconds_form(),
+            date_time.milliseconds_form(),
+            if self.extended_forms {
+                date_time.extended_form()
+            } else {
+                None
+            },
+        ];
+
+        first_exact(value, candidates, decode_date_time)
+    }
+}
 
 impl TypeCodec for XsdDateTimeCodec {
     fn encode(
@@ -12,88 +63,243 @@ impl TypeCodec for XsdDateTimeCodec {
         _active_context: &json_ld::Context,
         value: &str,
     ) -> Result<CborValue, EncodeError> {
-        let date_time = xsd_types::DateTime::from_str(value)
-            .map_err(|e| EncodeError::Codec("xsd-date-time", e.to_string()))?;
+        match self.compress(value) {
+            Some(cbor_value) => Ok(cbor_value),
+            // No compression.
+            None => Ok(CborValue::Text(value.to_owned())),
+        }
+    }
+
+    fn decode(
+        &self,
+        _state: &TransformerState,
+        _active_context: &json_ld::Context,
+        value: &CborValue,
+    ) -> Result<String, DecodeError> {
+        decode_date_time(value)
+    }
+}
 
-        let exact_date_time = date_time.earliest();
+/// Lexical `xsd:dateTime` value split into its components.
+struct LexicalDateTime<'a> {
+    /// Local date and time, without fractional seconds.
+    local: NaiveDateTime,
 
-        if exact_date_time == date_time.latest() {
-            let timestamp = exact_date_time.timestamp_micros();
-            let seconds = timestamp / 1_000_000;
-            let milliseconds = (timestamp / 1000) % 1000;
+    /// Fractional seconds digits.
+    fraction: Option<&'a str>,
 
-            if seconds * 1_000_000 == timestamp {
-                // second precision.
-                return Ok(CborValue::Integer(seconds.into()));
-            }
+    /// Timezone.
+    timezone: Option<Timezone<'a>>,
+}
+
+impl<'a> LexicalDateTime<'a> {
+    fn parse(value: &'a str) -> Option<Self> {
+        let (value, timezone) = Timezone::split(value)?;
+        let (value, fraction) = split_fraction(value)?;
+        let local = NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%M:%S").ok()?;
+
+        Some(Self {
+            local,
+            fraction,
+            timezone,
+        })
+    }
 
-            if seconds * 1_000_000 + milliseconds * 1000 == timestamp {
-                // millisecond precision.
-                return Ok(CborValue::Array(vec![
-                    CborValue::Integer(seconds.into()),
+    /// Number of seconds since the UNIX epoch.
+    fn seconds(&self) -> i64 {
+        self.local.and_utc().timestamp() - Timezone::offset(self.timezone) * 60
+    }
+
+    fn seconds_form(&self) -> Option<CborValue> {
+        if self.timezone == Some(Timezone::Utc) && self.fraction.is_none() {
+            Some(CborValue::Integer(self.seconds().into()))
+        } else {
+            None
+        }
+    }
+
+    fn milliseconds_form(&self) -> Option<CborValue> {
+        match self.fraction {
+            Some(fraction) if self.timezone == Some(Timezone::Utc) && fraction.len() == 3 => {
+                let milliseconds: u32 = fraction.parse().ok()?;
+                Some(CborValue::Array(vec![
+                    CborValue::Integer(self.seconds().into()),
                     CborValue::Integer(milliseconds.into()),
-                ]));
+                ]))
             }
+            _ => None,
         }
+    }
 
-        // No compression.
-        Ok(CborValue::Text(value.to_owned()))
+    fn extended_form(&self) -> Option<CborValue> {
+        Some(CborValue::Array(vec![
+            CborValue::Integer(self.seconds().into()),
+            encode_optional_fraction(self.fraction)?,
+            Timezone::encode(self.timezone),
+        ]))
     }
+}
 
-    fn decode(
-        &self,
-        _state: &TransformerState,
-        _active_context: &json_ld::Context,
-        value: &CborValue,
-    ) -> Result<String, crate::DecodeError> {
-        match value {
-            CborValue::Text(text) => Ok(text.clone()),
-            CborValue::Integer(i) => {
-                let seconds: i128 = (*i).into();
-                match i64::try_from(seconds) {
-                    Ok(seconds) => match Utc.timestamp_opt(seconds, 0).single() {
-                        Some(date_time) => Ok(xsd_types::DateTime::from(date_time).into_string()),
-                        None => {
-                            todo!()
-                        }
-                    },
-                    Err(_) => {
-                        todo!()
-                    }
+const CODEC: &str = "xsd-date-time";
+
+fn decode_date_time(value: &CborValue) -> Result<String, DecodeError> {
+    match value {
+        CborValue::Text(text) => Ok(text.clone()),
+        CborValue::Integer(_) => {
+            let seconds = decode_integer(CODEC, value)?;
+            Ok(format!("{}Z", format_local(seconds, 0)?))
+        }
+        CborValue::Array(items) => match items.as_slice() {
+            [seconds, milliseconds] => {
+                let seconds = decode_integer(CODEC, seconds)?;
+                let milliseconds: u32 = decode_integer(CODEC, milliseconds)?;
+
+                if milliseconds >= 1000 {
+                    return Err(DecodeError::Codec(CODEC, "invalid milliseconds".to_owned()));
                 }
+
+                Ok(format!("{}.{milliseconds:03}Z", format_local(seconds, 0)?))
             }
-            CborValue::Array(items) => {
-                if items.len() != 2 {
-                    todo!()
-                }
+            [seconds, fraction, timezone] => {
+                let seconds = decode_integer(CODEC, seconds)?;
+                let (offset, timezone) = Timezone::decode(CODEC, timezone)?;
+                let fraction = decode_optional_fraction(CODEC, fraction)?;
 
-                let seconds: i64 = items[0]
-                    .as_integer()
-                    .ok_or_else(|| {
-                        DecodeError::Codec("xsd-date-time", "expected integer".to_string())
-                    })?
-                    .try_into()
-                    .map_err(|_| DecodeError::Codec("xsd-date-time", "overflow".to_string()))?;
-
-                let milliseconds: u32 = items[1]
-                    .as_integer()
-                    .ok_or_else(|| {
-                        DecodeError::Codec("xsd-date-time", "expected integer".to_string())
-                    })?
-                    .try_into()
-                    .map_err(|_| DecodeError::Codec("xsd-date-time", "overflow".to_string()))?;
-
-                match Utc
-                    .timestamp_opt(seconds, milliseconds * 1_000_000)
-                    .single()
-                {
-                    Some(date_time) => Ok(xsd_types::DateTime::from(date_time).into_string()),
-                    None => {
-                        todo!()
-                    }
-                }
+                Ok(format!(
+                    "{}{fraction}{timezone}",
+                    format_local(seconds, offset)?
+                ))
             }
-            _ => todo!(),
+            _ => Err(DecodeError::Codec(CODEC, "invalid array length".to_owned())),
+        },
+        _ => Err(DecodeError::Codec(
+            CODEC,
+            "expected text, integer or array".to_owned(),
+        )),
+    }
+}
+
+/// Formats the local date and time (without fractional seconds) of the given
+/// UNIX timestamp, shifted by the given offset in minutes.
+fn format_local(seconds: i64, offset: i64) -> Result<String, DecodeError> {
+    let local = seconds
+        .checked_add(offset * 60)
+        .and_then(|s| DateTime::from_timestamp(s, 0))
+        .ok_or_else(|| DecodeError::Codec(CODEC, "overflow".to_owned()))?
+        .naive_utc();
+
+    Ok(format!(
+        "{}-{:02}-{:02}T{:02}:{:02}:{:02}",
+        format_year(local.year().into()),
+        local.month(),
+        local.day(),
+        local.hour(),
+        local.minute(),
+        local.second()
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{decode_date_time, XsdDateTimeCodec};
+    use crate::CborValue;
+    use proptest::prelude::*;
+
+    fn date_time() -> impl Strategy<Value = String> {
+        let year = prop_oneof![1900i32..2100, -9999i32..=9999];
+        let fraction = prop_oneof![
+            Just(String::new()),
+            "\\.[0-9]{1,12}".prop_map(|s| s.to_owned())
+        ];
+        let timezone = prop_oneof![
+            Just(String::new()),
+            Just("Z".to_owned()),
+            Just("+00:00".to_owned()),
+            Just("-00:00".to_owned()),
+            (prop::bool::ANY, 0u32..14, 0u32..60).prop_map(|(negative, h, m)| format!(
+                "{}{h:02}:{m:02}",
+                if negative { '-' } else { '+' }
+            ))
+        ];
+
+        (
+            year,
+            1u32..=12,
+            1u32..=28,
+            0u32..24,
+            0u32..60,
+            0u32..60,
+            fraction,
+            timezone,
+        )
+            .prop_map(|(y, mo, d, h, mi, s, fraction, timezone)| {
+                let sign = if y < 0 { "-" } else { "" };
+                format!(
+                    "{sign}{:04}-{mo:02}-{d:02}T{h:02}:{mi:02}:{s:02}{fraction}{timezone}",
+                    y.unsigned_abs()
+                )
+            })
+    }
+
+    proptest! {
+        #[test]
+        fn round_trip(value in date_time(), extended_forms in prop::bool::ANY) {
+            let codec = XsdDateTimeCodec::new(extended_forms);
+            let cbor_value = codec
+                .compress(&value)
+                .unwrap_or_else(|| CborValue::Text(value.clone()));
+            prop_assert_eq!(decode_date_time(&cbor_value).unwrap(), value);
+        }
+
+        #[test]
+        fn extended_round_trip_is_compressed(value in date_time()) {
+            let codec = XsdDateTimeCodec::new(true);
+            prop_assert!(codec.compress(&value).is_some());
         }
     }
+
+    #[test]
+    fn compatible_forms() {
+        let codec = XsdDateTimeCodec::default();
+
+        assert_eq!(
+            codec.compress("2021-03-24T20:03:03Z"),
+            Some(CborValue::Integer(1616616183.into()))
+        );
+        assert_eq!(
+            codec.compress("1958-07-17T00:00:00.120Z"),
+            Some(CborValue::Array(vec![
+                CborValue::Integer((-361670400).into()),
+                CborValue::Integer(120.into())
+            ]))
+        );
+        assert_eq!(codec.compress("2025-01-01T00:00:00+02:00"), None);
+        assert_eq!(codec.compress("2021-03-24T20:03:03.12Z"), None);
+        assert_eq!(codec.compress("1958-07-17T00:00:00"), None);
+    }
+
+    #[test]
+    fn preserves_offset() {
+        let codec = XsdDateTimeCodec::new(true);
+        let value = "2025-01-01T00:00:00+02:00";
+        let cbor_value = codec.compress(value).unwrap();
+        assert_eq!(
+            cbor_value,
+            CborValue::Array(vec![
+                CborValue::Integer(1735682400.into()),
+                CborValue::Null,
+                CborValue::Integer(120.into())
+            ])
+        );
+        assert_eq!(decode_date_time(&cbor_value).unwrap(), value)
+    }
+
+    #[test]
+    fn rejects_invalid() {
+        let codec = XsdDateTimeCodec::new(true);
+        assert_eq!(codec.compress("2025-01-01T24:00:00Z"), None);
+        assert_eq!(codec.compress("2025-1-01T00:00:00Z"), None);
+        assert_eq!(codec.compress("2025-01-01T00:00:00.Z"), None);
+        assert_eq!(codec.compress("not a date"), None);
+    }
 }
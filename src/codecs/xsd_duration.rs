@@ -0,0 +1,222 @@
+use super::{
+    xsd_lexical::{
+        decode_decimal, decode_integer, encode_decimal, first_exact, is_digits,
+        DECIMAL_FRACTION_TAG,
+    },
+    TypeCodec,
+};
+use crate::{transform::TransformerState, CborValue, DecodeError, EncodeError};
+
+/// `xsd:duration` codec.
+///
+/// Values are compressed into a `[flags, components...]` array, where the
+/// bits of `flags` tell which components are present (years, months, days,
+/// hours, minutes then seconds from the least significant bit) and whether
+/// the duration is negative (bit 6). Components are integers, except for
+/// fractional seconds encoded as a decimal fraction.
+///
+/// Components are never normalized so that `P1D` and `PT24H` are preserved.
+pub struct XsdDurationCodec;
+
+const CODEC: &str = "xsd-duration";
+
+/// Component designators, in lexical order.
+const DESIGNATORS: [char; 6] = ['Y', 'M', 'D', 'H', 'M', 'S'];
+
+/// Index of the first time component.
+const FIRST_TIME_COMPONENT: usize = 3;
+
+/// Index of the seconds component.
+const SECONDS: usize = 5;
+
+/// Negative duration flag.
+const NEGATIVE: u64 = 1 << 6;
+
+impl TypeCodec for XsdDurationCodec {
+    fn encode(
+        &self,
+        _state: &TransformerState,
+        _active_context: &json_ld::Context,
+        value: &str,
+    ) -> Result<CborValue, EncodeError> {
+        match compress(value) {
+            Some(cbor_value) => Ok(cbor_value),
+            // No compression.
+            None => Ok(CborValue::Text(value.to_owned())),
+        }
+    }
+
+    fn decode(
+        &self,
+        _state: &TransformerState,
+        _active_context: &json_ld::Context,
+        value: &CborValue,
+    ) -> Result<String, DecodeError> {
+        decode_duration(value)
+    }
+}
+
+fn compress(value: &str) -> Option<CborValue> {
+    let (negative, rest) = match value.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, value),
+    };
+
+    let rest = rest.strip_prefix('P')?;
+    let (date, time) = match rest.split_once('T') {
+        Some((date, time)) => (date, Some(time)),
+        None => (rest, None),
+    };
+
+    let mut flags = if negative { NEGATIVE } else { 0 };
+    let mut components = vec![CborValue::Null];
+
+    let mut parse = |mut part: &str, range: std::ops::Range<usize>| -> Option<()> {
+        for i in range {
+            let Some(end) = part.find(DESIGNATORS[i]) else {
+                continue;
+            };
+
+            let number = &part[..end];
+            let component = match number.split_once('.') {
+                Some((integer, fraction)) if i == SECONDS && is_digits(integer) => {
+                    encode_decimal(integer, fraction)?
+                }
+                Some(_) => return None,
+                None => CborValue::Integer(parse_number(number)?.into()),
+            };
+
+            flags |= 1 << i;
+            components.push(component);
+            part = &part[(end + 1)..];
+        }
+
+        part.is_empty().then_some(())
+    };
+
+    parse(date, 0..FIRST_TIME_COMPONENT)?;
+    if let Some(time) = time {
+        parse(time, FIRST_TIME_COMPONENT..DESIGNATORS.len())?;
+    }
+
+    if flags & !NEGATIVE == 0 {
+        return None;
+    }
+
+    components[0] = CborValue::Integer(flags.into());
+    first_exact(value, [Some(CborValue::Array(components))], decode_duration)
+}
+
+fn parse_number(value: &str) -> Option<u64> {
+    if is_digits(value) {
+        value.parse().ok()
+    } else {
+        None
+    }
+}
+
+fn decode_duration(value: &CborValue) -> Result<String, DecodeError> {
+    match value {
+        CborValue::Text(text) => Ok(text.clone()),
+        CborValue::Array(items) if !items.is_empty() => {
+            let flags: u64 = decode_integer(CODEC, &items[0])?;
+            let mut components = items[1..].iter();
+            let mut result = String::new();
+
+            if flags & NEGATIVE != 0 {
+                result.push('-')
+            }
+
+            result.push('P');
+
+            for (i, designator) in DESIGNATORS.into_iter().enumerate() {
+                if flags & (1 << i) == 0 {
+                    continue;
+                }
+
+                if i >= FIRST_TIME_COMPONENT && !result.contains('T') {
+                    result.push('T')
+                }
+
+                let component = components
+                    .next()
+                    .ok_or_else(|| DecodeError::Codec(CODEC, "missing component".to_owned()))?;
+
+                match component {
+                    CborValue::Tag(DECIMAL_FRACTION_TAG, _) if i == SECONDS => {
+                        let (integer, fraction) = decode_decimal(CODEC, component)?;
+                        result.push_str(&format!("{integer}.{fraction}"))
+                    }
+                    _ => {
+                        let n: u64 = decode_integer(CODEC, component)?;
+                        result.push_str(&n.to_string())
+                    }
+                }
+
+                result.push(designator)
+            }
+
+            if components.next().is_some() || flags >> 7 != 0 {
+                return Err(DecodeError::Codec(CODEC, "invalid components".to_owned()));
+            }
+
+            Ok(result)
+        }
+        _ => Err(DecodeError::Codec(
+            CODEC,
+            "expected text or array".to_owned(),
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{compress, decode_duration};
+    use crate::CborValue;
+    use proptest::prelude::*;
+
+    proptest! {
+        #[test]
+        fn round_trip(
+            value in "-?P([1-9][0-9]{0,3}Y)?([0-9]M)?([0-9]{1,3}D)?(T([0-9]{1,2}H)?([0-9]{1,2}M)?([0-9]{1,2}(\\.[0-9]{1,6})?S)?)?"
+        ) {
+            let cbor_value = compress(&value).unwrap_or_else(|| CborValue::Text(value.clone()));
+            prop_assert_eq!(decode_duration(&cbor_value).unwrap(), value);
+        }
+    }
+
+    #[test]
+    fn compress_duration() {
+        assert_eq!(
+            compress("P1D"),
+            Some(CborValue::Array(vec![
+                CborValue::Integer(4.into()),
+                CborValue::Integer(1.into())
+            ]))
+        );
+        assert_eq!(
+            compress("PT24H"),
+            Some(CborValue::Array(vec![
+                CborValue::Integer(8.into()),
+                CborValue::Integer(24.into())
+            ]))
+        );
+        assert_eq!(
+            compress("-PT1.50S"),
+            Some(CborValue::Array(vec![
+                CborValue::Integer(96.into()),
+                CborValue::Tag(
+                    4,
+                    Box::new(CborValue::Array(vec![
+                        CborValue::Integer((-2).into()),
+                        CborValue::Integer(150.into())
+                    ]))
+                )
+            ]))
+        );
+        assert_eq!(compress("P"), None);
+        assert_eq!(compress("P1DT"), None);
+        assert_eq!(compress("PT1D"), None);
+        assert_eq!(compress("P01D"), None);
+    }
+}
@@ -0,0 +1,116 @@
+use super::{
+    xsd_lexical::{decode_integer, first_exact, format_year, parse_year, Timezone},
+    TypeCodec,
+};
+use crate::{transform::TransformerState, CborValue, DecodeError, EncodeError};
+
+/// `xsd:gYear` codec.
+///
+/// Values without timezone are compressed into the year number. Other values
+/// are compressed into a `[year, timezone]` array.
+pub struct XsdGYearCodec;
+
+const CODEC: &str = "xsd-g-year";
+
+impl TypeCodec for XsdGYearCodec {
+    fn encode(
+        &self,
+        _state: &TransformerState,
+        _active_context: &json_ld::Context,
+        value: &str,
+    ) -> Result<CborValue, EncodeError> {
+        match compress(value) {
+            Some(cbor_value) => Ok(cbor_value),
+            // No compression.
+            None => Ok(CborValue::Text(value.to_owned())),
+        }
+    }
+
+    fn decode(
+        &self,
+        _state: &TransformerState,
+        _active_context: &json_ld::Context,
+        value: &CborValue,
+    ) -> Result<String, DecodeError> {
+        decode_g_year(value)
+    }
+}
+
+fn compress(value: &str) -> Option<CborValue> {
+    let (rest, timezone) = Timezone::split(value)?;
+    let year = parse_year(rest)?;
+
+    let cbor_value = match timezone {
+        Some(timezone) => CborValue::Array(vec![
+            CborValue::Integer(year.into()),
+            Timezone::encode(Some(timezone)),
+        ]),
+        None => CborValue::Integer(year.into()),
+    };
+
+    first_exact(value, [Some(cbor_value)], decode_g_year)
+}
+
+fn decode_g_year(value: &CborValue) -> Result<String, DecodeError> {
+    match value {
+        CborValue::Text(text) => Ok(text.clone()),
+        CborValue::Integer(_) => Ok(format_year(decode_integer(CODEC, value)?)),
+        CborValue::Array(items) => match items.as_slice() {
+            [year, timezone] => {
+                let year = format_year(decode_integer(CODEC, year)?);
+                let (_, timezone) = Timezone::decode(CODEC, timezone)?;
+                Ok(format!("{year}{timezone}"))
+            }
+            _ => Err(DecodeError::Codec(CODEC, "invalid array length".to_owned())),
+        },
+        _ => Err(DecodeError::Codec(
+            CODEC,
+            "expected text, integer or array".to_owned(),
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{compress, decode_g_year};
+    use crate::{codecs::xsd_lexical::format_year, CborValue};
+    use proptest::prelude::*;
+
+    proptest! {
+        #[test]
+        fn round_trip(value in "-?[1-9]?[0-9]{4}(Z|[+-](0[0-9]|1[0-3]):[0-5][0-9])?") {
+            let cbor_value = compress(&value).unwrap_or_else(|| CborValue::Text(value.clone()));
+            prop_assert_eq!(decode_g_year(&cbor_value).unwrap(), value);
+        }
+
+        #[test]
+        fn compressed_round_trip(
+            year in -99999i64..=99999,
+            timezone in "(Z|[+-](0[0-9]|1[0-3]):[0-5][0-9])?"
+        ) {
+            let value = format!("{}{timezone}", format_year(year));
+            let cbor_value = compress(&value);
+            prop_assert!(cbor_value.is_some(), "{} is not compressed", value);
+            prop_assert_eq!(decode_g_year(&cbor_value.unwrap()).unwrap(), value);
+        }
+    }
+
+    #[test]
+    fn compress_g_year() {
+        assert_eq!(compress("2003"), Some(CborValue::Integer(2003.into())));
+        assert_eq!(compress("-0044"), Some(CborValue::Integer((-44).into())));
+        assert_eq!(
+            compress("-12345"),
+            Some(CborValue::Integer((-12345).into()))
+        );
+        assert_eq!(
+            compress("-12345-12:45"),
+            Some(CborValue::Array(vec![
+                CborValue::Integer((-12345).into()),
+                CborValue::Integer((-765).into())
+            ]))
+        );
+        assert_eq!(compress("02003"), None);
+        assert_eq!(compress("203"), None);
+    }
+}
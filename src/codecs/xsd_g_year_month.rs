@@ -0,0 +1,118 @@
+use super::{
+    xsd_lexical::{decode_integer, first_exact, format_year, is_digits, parse_year, Timezone},
+    TypeCodec,
+};
+use crate::{transform::TransformerState, CborValue, DecodeError, EncodeError};
+
+/// `xsd:gYearMonth` codec.
+///
+/// Values without timezone are compressed into the number of months since
+/// January 1970. Other values are compressed into a `[months, timezone]`
+/// array.
+pub struct XsdGYearMonthCodec;
+
+const CODEC: &str = "xsd-g-year-month";
+
+impl TypeCodec for XsdGYearMonthCodec {
+    fn encode(
+        &self,
+        _state: &TransformerState,
+        _active_context: &json_ld::Context,
+        value: &str,
+    ) -> Result<CborValue, EncodeError> {
+        match compress(value) {
+            Some(cbor_value) => Ok(cbor_value),
+            // No compression.
+            None => Ok(CborValue::Text(value.to_owned())),
+        }
+    }
+
+    fn decode(
+        &self,
+        _state: &TransformerState,
+        _active_context: &json_ld::Context,
+        value: &CborValue,
+    ) -> Result<String, DecodeError> {
+        decode_g_year_month(value)
+    }
+}
+
+fn compress(value: &str) -> Option<CborValue> {
+    let (rest, timezone) = Timezone::split(value)?;
+    let (year, month) = rest.rsplit_once('-')?;
+    let year = parse_year(year)?;
+
+    if month.len() != 2 || !is_digits(month) {
+        return None;
+    }
+
+    let month: i64 = month.parse().ok()?;
+    let months = (year.checked_sub(1970)?)
+        .checked_mul(12)?
+        .checked_add(month - 1)?;
+
+    let cbor_value = match timezone {
+        Some(timezone) => CborValue::Array(vec![
+            CborValue::Integer(months.into()),
+            Timezone::encode(Some(timezone)),
+        ]),
+        None => CborValue::Integer(months.into()),
+    };
+
+    first_exact(value, [Some(cbor_value)], decode_g_year_month)
+}
+
+fn format_months(months: i64) -> Result<String, DecodeError> {
+    let year = months
+        .div_euclid(12)
+        .checked_add(1970)
+        .ok_or_else(|| DecodeError::Codec(CODEC, "overflow".to_owned()))?;
+    let month = months.rem_euclid(12) + 1;
+    Ok(format!("{}-{month:02}", format_year(year)))
+}
+
+fn decode_g_year_month(value: &CborValue) -> Result<String, DecodeError> {
+    match value {
+        CborValue::Text(text) => Ok(text.clone()),
+        CborValue::Integer(_) => format_months(decode_integer(CODEC, value)?),
+        CborValue::Array(items) => match items.as_slice() {
+            [months, timezone] => {
+                let date = format_months(decode_integer(CODEC, months)?)?;
+                let (_, timezone) = Timezone::decode(CODEC, timezone)?;
+                Ok(format!("{date}{timezone}"))
+            }
+            _ => Err(DecodeError::Codec(CODEC, "invalid array length".to_owned())),
+        },
+        _ => Err(DecodeError::Codec(
+            CODEC,
+            "expected text, integer or array".to_owned(),
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{compress, decode_g_year_month};
+    use crate::CborValue;
+    use proptest::prelude::*;
+
+    proptest! {
+        #[test]
+        fn round_trip(
+            // `-0000` is not a valid year.
+            value in "(0000|-?(000[1-9]|00[1-9][0-9]|0[1-9][0-9]{2}|[1-9][0-9]{3}))-(0[1-9]|1[0-2])(Z|[+-](0[0-9]|1[0-3]):[0-5][0-9])?"
+        ) {
+            let cbor_value = compress(&value);
+            prop_assert!(cbor_value.is_some());
+            prop_assert_eq!(decode_g_year_month(&cbor_value.unwrap()).unwrap(), value);
+        }
+    }
+
+    #[test]
+    fn compress_g_year_month() {
+        assert_eq!(compress("1970-01"), Some(CborValue::Integer(0.into())));
+        assert_eq!(compress("2024-05"), Some(CborValue::Integer(652.into())));
+        assert_eq!(compress("1969-12"), Some(CborValue::Integer((-1).into())));
+        assert_eq!(compress("2024-13"), None);
+    }
+}
@@ -0,0 +1,226 @@
+//! Lexical helpers shared by the XSD temporal codecs.
+//!
+//! These codecs only compress a value if decoding the compressed form gives
+//! back the exact same lexical form. Parsing can hence be lenient, the
+//! round-trip check rejecting anything that would not be reconstructed
+//! exactly.
+use crate::{CborValue, DecodeError};
+
+/// CBOR decimal fraction tag.
+///
+/// See: <https://www.rfc-editor.org/rfc/rfc8949.html#name-decimal-fractions-and-bigfl>
+pub const DECIMAL_FRACTION_TAG: u64 = 4;
+
+/// Timezone of a lexical XSD temporal value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Timezone<'a> {
+    /// `Z` timezone.
+    Utc,
+
+    /// Numeric offset, in minutes, with its original text.
+    Offset(i64, &'a str),
+}
+
+impl<'a> Timezone<'a> {
+    /// Splits the optional timezone suffix from the given lexical value.
+    pub fn split(value: &'a str) -> Option<(&'a str, Option<Self>)> {
+        match value.strip_suffix('Z') {
+            Some(rest) => Some((rest, Some(Self::Utc))),
+            None => match value.len().checked_sub(6) {
+                // A timezone follows the value, and has a colon: `-12345`
+                // is a year, not a year `-` with a `12:45` offset.
+                Some(i)
+                    if i > 0
+                        && matches!(value.as_bytes()[i], b'+' | b'-')
+                        && value.as_bytes()[i + 3] == b':' =>
+                {
+                    let text = &value[i..];
+                    let hours: i64 = text.get(1..3)?.parse().ok()?;
+                    let minutes: i64 = text.get(4..6)?.parse().ok()?;
+                    let offset = hours * 60 + minutes;
+                    let offset = if text.starts_with('-') {
+                        -offset
+                    } else {
+                        offset
+                    };
+                    Some((&value[..i], Some(Self::Offset(offset, text))))
+                }
+                _ => Some((value, None)),
+            },
+        }
+    }
+
+    /// Offset in minutes.
+    pub fn offset(timezone: Option<Self>) -> i64 {
+        match timezone {
+            Some(Self::Offset(minutes, _)) => minutes,
+            _ => 0,
+        }
+    }
+
+    /// Encodes an optional timezone.
+    ///
+    /// No timezone is encoded as `null`, `Z` as `0` and other offsets as a
+    /// number of minutes. Zero offsets not written `Z` are kept as text.
+    pub fn encode(timezone: Option<Self>) -> CborValue {
+        match timezone {
+            Some(Self::Utc) => CborValue::Integer(0.into()),
+            Some(Self::Offset(0, text)) => CborValue::Text(text.to_owned()),
+            Some(Self::Offset(minutes, _)) => CborValue::Integer(minutes.into()),
+            None => CborValue::Null,
+        }
+    }
+
+    /// Decodes an optional timezone, returning its offset in minutes and its
+    /// lexical form.
+    pub fn decode(codec: &'static str, value: &CborValue) -> Result<(i64, String), DecodeError> {
+        match value {
+            CborValue::Null => Ok((0, String::new())),
+            CborValue::Integer(_) => match decode_integer::<i64>(codec, value)? {
+                0 => Ok((0, "Z".to_owned())),
+                minutes => {
+                    let sign = if minutes < 0 { '-' } else { '+' };
+                    let abs = minutes.unsigned_abs();
+                    Ok((minutes, format!("{sign}{:02}:{:02}", abs / 60, abs % 60)))
+                }
+            },
+            CborValue::Text(text) => Ok((0, text.clone())),
+            _ => Err(DecodeError::Codec(codec, "invalid timezone".to_owned())),
+        }
+    }
+}
+
+/// Checks that the given string is a non-empty sequence of ASCII digits.
+pub fn is_digits(value: &str) -> bool {
+    !value.is_empty() && value.bytes().all(|b| b.is_ascii_digit())
+}
+
+/// Splits the optional fractional part (without the dot) from the given
+/// lexical value.
+pub fn split_fraction(value: &str) -> Option<(&str, Option<&str>)> {
+    match value.split_once('.') {
+        Some((rest, fraction)) => {
+            if is_digits(fraction) {
+                Some((rest, Some(fraction)))
+            } else {
+                None
+            }
+        }
+        None => Some((value, None)),
+    }
+}
+
+/// Encodes a decimal number given its integer and fractional digits as a
+/// decimal fraction, preserving the number of fractional digits.
+pub fn encode_decimal(integer: &str, fraction: &str) -> Option<CborValue> {
+    let exponent = -i64::try_from(fraction.len()).ok()?;
+    let mantissa: u64 = format!("{integer}{fraction}").parse().ok()?;
+    Some(CborValue::Tag(
+        DECIMAL_FRACTION_TAG,
+        Box::new(CborValue::Array(vec![
+            CborValue::Integer(exponent.into()),
+            CborValue::Integer(mantissa.into()),
+        ])),
+    ))
+}
+
+/// Decodes a decimal fraction into its integer and fractional digits.
+pub fn decode_decimal(
+    codec: &'static str,
+    value: &CborValue,
+) -> Result<(String, String), DecodeError> {
+    let invalid = || DecodeError::Codec(codec, "invalid decimal fraction".to_owned());
+    match value {
+        CborValue::Tag(DECIMAL_FRACTION_TAG, value) => match value.as_array() {
+            Some(items) if items.len() == 2 => {
+                let exponent: i64 = decode_integer(codec, &items[0])?;
+                let mantissa: u64 = decode_integer(codec, &items[1])?;
+                let digits = usize::try_from(-exponent).map_err(|_| invalid())?;
+
+                if digits == 0 {
+                    return Err(invalid());
+                }
+
+                let mantissa = format!("{mantissa:0width$}", width = digits + 1);
+                let (integer, fraction) = mantissa.split_at(mantissa.len() - digits);
+                Ok((integer.to_owned(), fraction.to_owned()))
+            }
+            _ => Err(invalid()),
+        },
+        _ => Err(invalid()),
+    }
+}
+
+/// Encodes fractional digits as a decimal fraction, preserving the number of
+/// digits.
+pub fn encode_fraction(fraction: &str) -> Option<CborValue> {
+    encode_decimal("", fraction)
+}
+
+/// Decodes a decimal fraction back into its fractional digits.
+pub fn decode_fraction(codec: &'static str, value: &CborValue) -> Result<String, DecodeError> {
+    match decode_decimal(codec, value)? {
+        (integer, fraction) if integer == "0" => Ok(fraction),
+        _ => Err(DecodeError::Codec(codec, "invalid fraction".to_owned())),
+    }
+}
+
+/// Encodes optional fractional digits, `null` meaning no fractional part.
+pub fn encode_optional_fraction(fraction: Option<&str>) -> Option<CborValue> {
+    match fraction {
+        Some(fraction) => encode_fraction(fraction),
+        None => Some(CborValue::Null),
+    }
+}
+
+/// Decodes optional fractional digits, including the leading dot.
+pub fn decode_optional_fraction(
+    codec: &'static str,
+    value: &CborValue,
+) -> Result<String, DecodeError> {
+    match value {
+        CborValue::Null => Ok(String::new()),
+        value => decode_fraction(codec, value).map(|f| format!(".{f}")),
+    }
+}
+
+/// Decodes an integer.
+pub fn decode_integer<T: TryFrom<ciborium::value::Integer>>(
+    codec: &'static str,
+    value: &CborValue,
+) -> Result<T, DecodeError> {
+    value
+        .as_integer()
+        .ok_or_else(|| DecodeError::Codec(codec, "expected integer".to_owned()))?
+        .try_into()
+        .map_err(|_| DecodeError::Codec(codec, "overflow".to_owned()))
+}
+
+/// Formats a year using at least four digits.
+pub fn format_year(year: i64) -> String {
+    let sign = if year < 0 { "-" } else { "" };
+    format!("{sign}{:04}", year.unsigned_abs())
+}
+
+/// Parses a year of at least four digits.
+pub fn parse_year(value: &str) -> Option<i64> {
+    let digits = value.strip_prefix('-').unwrap_or(value);
+    if digits.len() >= 4 && is_digits(digits) {
+        value.parse().ok()
+    } else {
+        None
+    }
+}
+
+/// Returns the first candidate compressed form decoding back to the exact
+/// given lexical value.
+pub fn first_exact(
+    value: &str,
+    candidates: impl IntoIterator<Item = Option<CborValue>>,
+    decode: impl Fn(&CborValue) -> Result<String, DecodeError>,
+) -> Option<CborValue> {
+    candidates
+        .into_iter()
+        .flatten()
+        .find(|candidate| decode(candidate).is_ok_and(|decoded| decoded == value))
+}
@@ -0,0 +1,135 @@
+use super::{
+    xsd_lexical::{
+        decode_integer, decode_optional_fraction, encode_optional_fraction, first_exact,
+        split_fraction, Timezone,
+    },
+    TypeCodec,
+};
+use crate::{transform::TransformerState, CborValue, DecodeError, EncodeError};
+use chrono::{NaiveTime, Timelike};
+
+/// `xsd:time` codec.
+///
+/// Values without fractional seconds and timezone are compressed into the
+/// number of seconds since midnight. Other values are compressed into a
+/// `[seconds, fraction, timezone]` array, using the same `fraction` and
+/// `timezone` encoding as the extended [`XsdDateTimeCodec`] form.
+///
+/// [`XsdDateTimeCodec`]: super::XsdDateTimeCodec
+pub struct XsdTimeCodec;
+
+const CODEC: &str = "xsd-time";
+
+impl TypeCodec for XsdTimeCodec {
+    fn encode(
+        &self,
+        _state: &TransformerState,
+        _active_context: &json_ld::Context,
+        value: &str,
+    ) -> Result<CborValue, EncodeError> {
+        match compress(value) {
+            Some(cbor_value) => Ok(cbor_value),
+            // No compression.
+            None => Ok(CborValue::Text(value.to_owned())),
+        }
+    }
+
+    fn decode(
+        &self,
+        _state: &TransformerState,
+        _active_context: &json_ld::Context,
+        value: &CborValue,
+    ) -> Result<String, DecodeError> {
+        decode_time(value)
+    }
+}
+
+fn compress(value: &str) -> Option<CborValue> {
+    let (rest, timezone) = Timezone::split(value)?;
+    let (rest, fraction) = split_fraction(rest)?;
+    let seconds = NaiveTime::parse_from_str(rest, "%H:%M:%S")
+        .ok()?
+        .num_seconds_from_midnight();
+
+    let candidates = [
+        if timezone.is_none() && fraction.is_none() {
+            Some(CborValue::Integer(seconds.into()))
+        } else {
+            None
+        },
+        encode_optional_fraction(fraction).map(|fraction| {
+            CborValue::Array(vec![
+                CborValue::Integer(seconds.into()),
+                fraction,
+                Timezone::encode(timezone),
+            ])
+        }),
+    ];
+
+    first_exact(value, candidates, decode_time)
+}
+
+fn decode_time(value: &CborValue) -> Result<String, DecodeError> {
+    match value {
+        CborValue::Text(text) => Ok(text.clone()),
+        CborValue::Integer(_) => format_seconds(decode_integer(CODEC, value)?),
+        CborValue::Array(items) => match items.as_slice() {
+            [seconds, fraction, timezone] => {
+                let seconds = format_seconds(decode_integer(CODEC, seconds)?)?;
+                let fraction = decode_optional_fraction(CODEC, fraction)?;
+                let (_, timezone) = Timezone::decode(CODEC, timezone)?;
+                Ok(format!("{seconds}{fraction}{timezone}"))
+            }
+            _ => Err(DecodeError::Codec(CODEC, "invalid array length".to_owned())),
+        },
+        _ => Err(DecodeError::Codec(
+            CODEC,
+            "expected text, integer or array".to_owned(),
+        )),
+    }
+}
+
+fn format_seconds(seconds: u32) -> Result<String, DecodeError> {
+    if seconds >= 86400 {
+        return Err(DecodeError::Codec(CODEC, "overflow".to_owned()));
+    }
+
+    Ok(format!(
+        "{:02}:{:02}:{:02}",
+        seconds / 3600,
+        (seconds / 60) % 60,
+        seconds % 60
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{compress, decode_time};
+    use crate::CborValue;
+    use proptest::prelude::*;
+
+    proptest! {
+        #[test]
+        fn round_trip(
+            value in "([01][0-9]|2[0-3]):[0-5][0-9]:[0-5][0-9](\\.[0-9]{1,9})?(Z|[+-](0[0-9]|1[0-3]):[0-5][0-9])?"
+        ) {
+            let cbor_value = compress(&value);
+            prop_assert!(cbor_value.is_some());
+            prop_assert_eq!(decode_time(&cbor_value.unwrap()).unwrap(), value);
+        }
+    }
+
+    #[test]
+    fn compress_time() {
+        assert_eq!(compress("14:30:00"), Some(CborValue::Integer(52200.into())));
+        assert_eq!(
+            compress("14:30:00Z"),
+            Some(CborValue::Array(vec![
+                CborValue::Integer(52200.into()),
+                CborValue::Null,
+                CborValue::Integer(0.into())
+            ]))
+        );
+        assert_eq!(compress("24:00:00"), None);
+    }
+}
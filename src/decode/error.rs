@@ -1,6 +1,9 @@
 use crate::{
     tables::UnknownCompressionTable,
-    transform::{DuplicateKey, ExpectedObject, InvalidTypeKind, MissingKeyTerm, UndefinedTerm},
+    transform::{
+        DuplicateKey, ErrorLocation, ExpectedObject, InvalidTypeKind, LocatedError, MissingKeyTerm,
+        PathSegment, UndefinedTerm,
+    },
     CborValue,
 };
 
@@ -9,18 +12,39 @@ pub enum DecodeError {
     #[error(transparent)]
     Cbor(#[from] ciborium::de::Error<std::io::Error>),
 
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+
+    #[error("deserialization failed: {0}")]
+    Deserialization(String),
+
     #[error("not CBOR-LD")]
     NotCborLd,
 
     #[error("unknown compression table {0}")]
     UnknownCompressionTable(u64),
 
+    #[error("unsupported compression mode {0}")]
+    UnsupportedCompressionMode(u64),
+
+    #[error("string dictionary not bound to an application registry entry")]
+    UnregisteredStringDictionary,
+
     #[error("expected node object")]
     ExpectedNodeObject,
 
+    #[error("invalid JSON-LD context: {0}")]
+    InvalidContext(#[from] json_ld::syntax::context::InvalidContext),
+
     #[error("JSON-LD context processing failed: {0}")]
     ContextProcessing(#[from] json_ld::context_processing::Error),
 
+    #[error("JSON-LD expansion failed: {0}")]
+    Expansion(#[from] json_ld::ExpandError),
+
+    #[error("invalid value object")]
+    InvalidValueObject,
+
     #[error("duplicate entry")]
     DuplicateEntry(CborValue),
 
@@ -36,6 +60,9 @@ pub enum DecodeError {
     #[error("non finite float")]
     NonFiniteFloat,
 
+    #[error("invalid lossless side channel")]
+    InvalidSideChannel,
+
     #[error("invalid value")]
     InvalidValue,
 
@@ -56,6 +83,51 @@ pub enum DecodeError {
 
     #[error("`{0}` codec error: {1}")]
     Codec(&'static str, String),
+
+    #[error("invalid value reference: {0}")]
+    InvalidReference(String),
+
+    #[error("CBOR sequence documents with shared state must use the same compression mode")]
+    SequenceCompressionMismatch,
+
+    #[error("CBOR sequence state sharing does not match the encoded documents")]
+    SequenceStateMismatch,
+
+    #[error("{error} ({location})")]
+    Located {
+        error: Box<Self>,
+        location: ErrorLocation,
+    },
+}
+
+impl DecodeError {
+    /// Returns the location of the error in the input document, if known.
+    pub fn location(&self) -> Option<&ErrorLocation> {
+        match self {
+            Self::Located { location, .. } => Some(location),
+            _ => None,
+        }
+    }
+
+    /// Returns the error without its location.
+    pub fn without_location(&self) -> &Self {
+        match self {
+            Self::Located { error, .. } => error,
+            other => other,
+        }
+    }
+}
+
+impl LocatedError for DecodeError {
+    fn at(self, path: &[PathSegment]) -> Self {
+        match self {
+            Self::Located { .. } => self,
+            error => Self::Located {
+                error: Box::new(error),
+                location: ErrorLocation::cbor_path(path),
+            },
+        }
+    }
 }
 
 impl From<UnknownCompressionTable> for DecodeError {
@@ -82,6 +154,12 @@ impl From<UndefinedTerm> for DecodeError {
     }
 }
 
+impl From<json_ld::expansion::Error> for DecodeError {
+    fn from(value: json_ld::expansion::Error) -> Self {
+        Self::Expansion(json_ld::ExpandError::Expansion(value))
+    }
+}
+
 impl From<ExpectedObject> for DecodeError {
     fn from(_value: ExpectedObject) -> Self {
         Self::ExpectedNodeObject
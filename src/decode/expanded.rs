@@ -0,0 +1,824 @@
+//! Expanded JSON-LD and RDF output.
+//!
+//! The expanded document is built while decoding, from the active context of
+//! each decoded entry, following the JSON-LD expansion algorithm: no context
+//! is loaded or processed again after decoding. The RDF dataset is then
+//! derived from the expanded document.
+//!
+//! Expansion follows the decoding walk, which only tracks what CBOR-LD
+//! compression needs: the scope of type-scoped contexts is not reverted for
+//! nested node objects, and index, `@id` and `@type` maps are not supported.
+use std::collections::VecDeque;
+
+use json_ld::{
+    context::TermDefinitionRef,
+    context_processing::algorithm::{expand_iri_simple, Action, MalformedIri},
+    expansion::Error as ExpansionError,
+    object::{value::Literal, List},
+    rdf_types::generator,
+    syntax::{ContainerKind, Direction, IntoJsonWithContext, Keyword, LenientLangTagBuf, Nullable},
+    BlankIdBuf, Indexed, IriBuf, LangString, Object, RdfQuads, ValidId, Value,
+};
+
+use crate::{decode::DecodePipeline, CborValue, DecodeError, DecodeOptions, JsonValue, RdfDataset};
+
+type ExpandedDocument = json_ld::ExpandedDocument<IriBuf, BlankIdBuf>;
+type IndexedObject = json_ld::IndexedObject<IriBuf, BlankIdBuf>;
+type Node = json_ld::Node<IriBuf, BlankIdBuf>;
+type Id = json_ld::Id<IriBuf, BlankIdBuf>;
+type Term = json_ld::Term<IriBuf, BlankIdBuf>;
+
+/// Decodes a CBOR-LD document into an expanded JSON-LD document using the
+/// given JSON-LD context loader and the default options.
+pub async fn decode_expanded(
+    cbor_ld_document: &CborValue,
+    loader: impl json_ld::Loader,
+) -> Result<JsonValue, DecodeError> {
+    decode_expanded_with(cbor_ld_document, loader, Default::default()).await
+}
+
+/// Decodes a CBOR-LD document into an expanded JSON-LD document using the
+/// given JSON-LD context loader and the given options.
+///
+/// The document is expanded while decoding, from the decoder's active
+/// contexts.
+pub async fn decode_expanded_with(
+    cbor_ld_document: &CborValue,
+    loader: impl json_ld::Loader,
+    options: DecodeOptions,
+) -> Result<JsonValue, DecodeError> {
+    let pipeline = DecodePipeline::from(options);
+    let expanded = pipeline
+        .decode_expanded(pipeline.loader(loader), cbor_ld_document)
+        .await?;
+
+    let mut json = JsonValue::Array(
+        expanded
+            .into_iter()
+            .map(|object| object.into_json_with(&()))
+            .collect(),
+    );
+    json.canonicalize();
+    Ok(json)
+}
+
+/// Decodes a CBOR-LD document into an RDF dataset using the given JSON-LD
+/// context loader and the default options.
+pub async fn decode_to_rdf(
+    cbor_ld_document: &CborValue,
+    loader: impl json_ld::Loader,
+) -> Result<RdfDataset, DecodeError> {
+    decode_to_rdf_with(cbor_ld_document, loader, Default::default()).await
+}
+
+/// Decodes a CBOR-LD document into an RDF dataset using the given JSON-LD
+/// context loader and the given options.
+///
+/// The dataset is derived from the document expanded while decoding. Blank
+/// nodes are labeled `_:0`, `_:1`, etc.
+pub async fn decode_to_rdf_with(
+    cbor_ld_document: &CborValue,
+    loader: impl json_ld::Loader,
+    options: DecodeOptions,
+) -> Result<RdfDataset, DecodeError> {
+    let pipeline = DecodePipeline::from(options);
+    let mut expanded = pipeline
+        .decode_expanded(pipeline.loader(loader), cbor_ld_document)
+        .await?;
+
+    let mut generator = generator::Blank::new();
+    expanded.relabel_and_canonicalize(&mut generator);
+    let quads = expanded.rdf_quads(&mut generator, None).cloned().collect();
+    Ok(RdfDataset(quads))
+}
+
+/// Expanded document built while decoding.
+///
+/// The decoder reports the node objects it decodes and their entries, along
+/// with the active context of each entry. Node objects nested in an entry
+/// are completed before the entry is reported.
+#[derive(Default)]
+pub(crate) struct Expansion {
+    /// Node objects being expanded, from the root.
+    frames: Vec<Frame>,
+
+    /// Expanded root node object.
+    root: Option<Frame>,
+}
+
+/// Where the values of an entry are expanded.
+enum Target {
+    Graph,
+    Included,
+    Reverse,
+    Nest,
+    Property {
+        prop: Id,
+        list: bool,
+        graph: bool,
+        reverse: bool,
+    },
+}
+
+/// Plural entry being expanded, item by item.
+struct OpenEntry {
+    term: String,
+    target: Target,
+    items: Option<Vec<IndexedObject>>,
+}
+
+/// Node or value object being expanded.
+#[derive(Default)]
+struct Frame {
+    id: Option<Id>,
+    types: Vec<Term>,
+    value: Option<JsonValue>,
+    language: Option<JsonValue>,
+    direction: Option<JsonValue>,
+    index: Option<String>,
+    graph: Option<Vec<IndexedObject>>,
+    included: Option<Vec<IndexedObject>>,
+    properties: Vec<(Id, Vec<IndexedObject>)>,
+    reverse_properties: Vec<(Id, Vec<IndexedObject>)>,
+
+    /// Plural entry being expanded, if any.
+    open: Option<OpenEntry>,
+
+    /// Node objects nested in the entry being expanded, in document order.
+    nested: VecDeque<Frame>,
+}
+
+/// Origin of an expanded value.
+#[derive(Clone, Copy)]
+enum Source {
+    /// Decoded entry: its node objects are already expanded.
+    Decoded,
+
+    /// JSON value left as-is by the decoder, in lenient mode.
+    Json,
+}
+
+impl Expansion {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Starts a node object.
+    pub fn begin_node(&mut self) {
+        self.frames.push(Frame::default())
+    }
+
+    /// Expands an entry of the current node object, or an item of its
+    /// current plural entry.
+    ///
+    /// The value is `None` when a plural entry starts.
+    pub fn entry(
+        &mut self,
+        active_context: &json_ld::Context,
+        term: &str,
+        def: Option<TermDefinitionRef>,
+        value: Option<&JsonValue>,
+    ) -> Result<(), DecodeError> {
+        // Only the entries of undefined terms are left as-is.
+        let source = if def.is_none() && Keyword::try_from(term).is_err() {
+            Source::Json
+        } else {
+            Source::Decoded
+        };
+
+        let result = self.expand_entry(active_context, term, def, value, source);
+        if let Some(frame) = self.frames.last_mut() {
+            frame.nested.clear()
+        }
+        result
+    }
+
+    /// Ends the current node object.
+    pub fn end_node(&mut self) -> Result<(), DecodeError> {
+        self.close_entry()?;
+        let frame = self.frames.pop().ok_or(DecodeError::ExpectedNodeObject)?;
+        match self.frames.last_mut() {
+            Some(parent) => parent.nested.push_back(frame),
+            None => self.root = Some(frame),
+        }
+        Ok(())
+    }
+
+    /// Returns the expanded document.
+    pub fn finish(self) -> Result<ExpandedDocument, DecodeError> {
+        let root = self.root.ok_or(DecodeError::ExpectedNodeObject)?;
+        let mut document = ExpandedDocument::new();
+        if let Some(object) = root.finish(true)? {
+            match object.into_unnamed_graph() {
+                Ok(graph) => document = ExpandedDocument::from(graph),
+                Err(object) => {
+                    if !matches!(object.inner(), Object::Value(_)) {
+                        document.insert(object);
+                    }
+                }
+            }
+        }
+
+        Ok(document)
+    }
+
+    fn frame(&mut self) -> &mut Frame {
+        self.frames
+            .last_mut()
+            .expect("no node object being expanded")
+    }
+
+    fn expand_entry(
+        &mut self,
+        active_context: &json_ld::Context,
+        term: &str,
+        def: Option<TermDefinitionRef>,
+        value: Option<&JsonValue>,
+        source: Source,
+    ) -> Result<(), DecodeError> {
+        // Item of the current plural entry.
+        if let Some(value) = value {
+            if self.frame().open.as_ref().is_some_and(|e| e.term == term) {
+                let items = self.expand_items(active_context, term, value, source, false)?;
+                let open = self.frame().open.as_mut().unwrap();
+                open.items
+                    .get_or_insert_with(Vec::new)
+                    .extend(items.into_iter().flatten());
+                return Ok(());
+            }
+        }
+
+        self.close_entry()?;
+
+        let key = match def {
+            Some(def) => def.value().cloned().unwrap_or(Term::Null),
+            None => match expand_iri(active_context, term, false, true) {
+                Some(key) => key,
+                None => return Ok(()),
+            },
+        };
+
+        let target = match key {
+            Term::Keyword(Keyword::Graph) => Target::Graph,
+            Term::Keyword(Keyword::Included) => Target::Included,
+            Term::Keyword(Keyword::Reverse) => Target::Reverse,
+            Term::Keyword(Keyword::Nest) => Target::Nest,
+            Term::Keyword(keyword) => {
+                if let Some(value) = value {
+                    self.frame().keyword(active_context, keyword, value)?;
+                }
+                return Ok(());
+            }
+            Term::Id(prop) if prop.as_str().contains(':') => {
+                let container = def.map(|d| d.container()).unwrap_or_default();
+                Target::Property {
+                    prop,
+                    list: container.contains(ContainerKind::List),
+                    graph: container.contains(ContainerKind::Graph)
+                        && !container.contains(ContainerKind::Id)
+                        && !container.contains(ContainerKind::Index),
+                    reverse: def.is_some_and(|d| d.reverse_property()),
+                }
+            }
+            _ => return Ok(()),
+        };
+
+        match value {
+            Some(value) => {
+                if let Target::Reverse | Target::Nest = target {
+                    return self.merge_nested(active_context, &target, value, source);
+                }
+
+                let items = match (&target, def) {
+                    (Target::Property { .. }, Some(def))
+                        if def.typ() == Some(&json_ld::Type::Json) =>
+                    {
+                        Some(vec![Object::Value(Value::Json(value.clone())).into()])
+                    }
+                    (Target::Property { .. }, Some(def))
+                        if def.container().contains(ContainerKind::Language)
+                            && value.is_object() =>
+                    {
+                        Some(expand_language_map(active_context, def, value)?)
+                    }
+                    _ => {
+                        let free_floating = matches!(target, Target::Graph);
+                        self.expand_items(active_context, term, value, source, free_floating)?
+                    }
+                };
+
+                self.frame().open = Some(OpenEntry {
+                    term: term.to_owned(),
+                    target,
+                    items,
+                });
+                self.close_entry()
+            }
+            None => {
+                self.frame().open = Some(OpenEntry {
+                    term: term.to_owned(),
+                    target,
+                    items: Some(Vec::new()),
+                });
+                Ok(())
+            }
+        }
+    }
+
+    /// Adds the expanded values of the current plural entry to its node
+    /// object, if any.
+    fn close_entry(&mut self) -> Result<(), DecodeError> {
+        let frame = self.frame();
+        let Some(OpenEntry { target, items, .. }) = frame.open.take() else {
+            return Ok(());
+        };
+
+        match target {
+            Target::Graph => {
+                let items = items.into_iter().flatten();
+                frame.graph = Some(
+                    items
+                        .filter(|item| !matches!(item.inner(), Object::Value(_)))
+                        .collect(),
+                );
+            }
+            Target::Included => {
+                let included = frame.included.get_or_insert_with(Vec::new);
+                for item in items.into_iter().flatten() {
+                    if !item.is_node() {
+                        return Err(ExpansionError::InvalidIncludedValue.into());
+                    }
+
+                    included.push(item)
+                }
+            }
+            Target::Property {
+                prop,
+                list,
+                graph,
+                reverse,
+            } => {
+                let mut items = match items {
+                    Some(items) => items,
+                    None if list => Vec::new(),
+                    None => return Ok(()),
+                };
+
+                if list {
+                    items = vec![Object::List(List::new(items)).into()];
+                }
+
+                if graph {
+                    items = items.into_iter().map(graph_object).collect();
+                }
+
+                if reverse {
+                    if items.iter().any(|item| !item.is_node()) {
+                        return Err(ExpansionError::InvalidReversePropertyValue.into());
+                    }
+
+                    frame.reverse_properties.push((prop, items))
+                } else {
+                    frame.properties.push((prop, items))
+                }
+            }
+            Target::Reverse | Target::Nest => (),
+        }
+
+        Ok(())
+    }
+
+    /// Merges the node objects of a `@reverse` or `@nest` entry into the
+    /// current node object.
+    fn merge_nested(
+        &mut self,
+        active_context: &json_ld::Context,
+        target: &Target,
+        value: &JsonValue,
+        source: Source,
+    ) -> Result<(), DecodeError> {
+        for value in value.force_as_array() {
+            let Some(object) = value.as_object() else {
+                return Err(match target {
+                    Target::Reverse => ExpansionError::InvalidReverseValue.into(),
+                    _ => ExpansionError::InvalidNestValue.into(),
+                });
+            };
+
+            let nested = self.nested_frame(active_context, object, source)?;
+            let frame = self.frame();
+            match target {
+                Target::Reverse => {
+                    if nested.is_keyword_free() {
+                        for (prop, items) in nested.properties {
+                            if items.iter().any(|item| !item.is_node()) {
+                                return Err(ExpansionError::InvalidReversePropertyValue.into());
+                            }
+
+                            frame.reverse_properties.push((prop, items))
+                        }
+                        frame.properties.extend(nested.reverse_properties)
+                    } else {
+                        return Err(ExpansionError::InvalidReversePropertyMap.into());
+                    }
+                }
+                _ => frame.merge(nested),
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Expands the value of an entry, or one of its items.
+    ///
+    /// Returns `None` if the value is `null`.
+    fn expand_items(
+        &mut self,
+        active_context: &json_ld::Context,
+        term: &str,
+        value: &JsonValue,
+        source: Source,
+        free_floating: bool,
+    ) -> Result<Option<Vec<IndexedObject>>, DecodeError> {
+        match value {
+            JsonValue::Null => Ok(None),
+            JsonValue::Array(values) => {
+                let mut items = Vec::with_capacity(values.len());
+                for value in values {
+                    let expanded =
+                        self.expand_items(active_context, term, value, source, free_floating)?;
+                    items.extend(expanded.into_iter().flatten())
+                }
+
+                Ok(Some(items))
+            }
+            JsonValue::Object(object) => {
+                let nested = self.nested_frame(active_context, object, source)?;
+                Ok(nested.finish(free_floating)?.map(|item| vec![item]))
+            }
+            _ if free_floating => Ok(None),
+            literal => {
+                let def = active_context.get(term);
+                expand_literal(active_context, def, literal).map(|item| Some(vec![item]))
+            }
+        }
+    }
+
+    /// Returns the expanded node object of a value.
+    fn nested_frame(
+        &mut self,
+        active_context: &json_ld::Context,
+        object: &json_ld::syntax::Object,
+        source: Source,
+    ) -> Result<Frame, DecodeError> {
+        match source {
+            Source::Decoded => self
+                .frame()
+                .nested
+                .pop_front()
+                .ok_or(DecodeError::ExpectedNodeObject),
+            Source::Json => {
+                self.begin_node();
+                let result = object.iter().try_for_each(|entry| {
+                    let key = entry.key.as_str();
+                    let def = active_context.get(key);
+                    self.expand_entry(active_context, key, def, Some(&entry.value), Source::Json)
+                });
+                let result = result.and_then(|()| self.close_entry());
+                let frame = self.frames.pop().unwrap_or_default();
+                result.map(|()| frame)
+            }
+        }
+    }
+}
+
+impl Frame {
+    /// Sets a keyword entry.
+    fn keyword(
+        &mut self,
+        active_context: &json_ld::Context,
+        keyword: Keyword,
+        value: &JsonValue,
+    ) -> Result<(), DecodeError> {
+        match keyword {
+            Keyword::Id => {
+                let id = value.as_str().ok_or(ExpansionError::InvalidIdValue)?;
+                self.id = expand_iri(active_context, id, true, false).and_then(node_id_of_term);
+            }
+            Keyword::Type => {
+                for ty in value.force_as_array() {
+                    let ty = ty.as_str().ok_or(ExpansionError::InvalidTypeValue)?;
+                    self.types
+                        .extend(expand_iri(active_context, ty, true, true))
+                }
+            }
+            Keyword::Value => self.value = Some(value.clone()),
+            Keyword::Language => self.language = Some(value.clone()),
+            Keyword::Direction => self.direction = Some(value.clone()),
+            Keyword::Index => {
+                let index = value.as_str().ok_or(ExpansionError::InvalidIndexValue)?;
+                self.index = Some(index.to_owned())
+            }
+            _ => (),
+        }
+
+        Ok(())
+    }
+
+    /// Checks that the node object only has properties, as the value of a
+    /// `@reverse` entry.
+    fn is_keyword_free(&self) -> bool {
+        self.id.is_none()
+            && self.types.is_empty()
+            && self.value.is_none()
+            && self.language.is_none()
+            && self.direction.is_none()
+            && self.index.is_none()
+            && self.graph.is_none()
+            && self.included.is_none()
+    }
+
+    /// Merges the entries of a `@nest` entry value.
+    fn merge(&mut self, nested: Frame) {
+        self.id = nested.id.or(self.id.take());
+        self.types.extend(nested.types);
+        self.value = nested.value.or(self.value.take());
+        self.language = nested.language.or(self.language.take());
+        self.direction = nested.direction.or(self.direction.take());
+        self.index = nested.index.or(self.index.take());
+        if let Some(graph) = nested.graph {
+            self.graph.get_or_insert_with(Vec::new).extend(graph)
+        }
+        if let Some(included) = nested.included {
+            self.included.get_or_insert_with(Vec::new).extend(included)
+        }
+        self.properties.extend(nested.properties);
+        self.reverse_properties.extend(nested.reverse_properties);
+    }
+
+    /// Returns the expanded object.
+    ///
+    /// Free-floating objects, at the top level or in a `@graph` entry, are
+    /// dropped when empty.
+    fn finish(self, free_floating: bool) -> Result<Option<IndexedObject>, DecodeError> {
+        if let Some(value) = self.value {
+            let has_node_entries = self.id.is_some()
+                || self.graph.is_some()
+                || self.included.is_some()
+                || !self.properties.is_empty()
+                || !self.reverse_properties.is_empty();
+            if has_node_entries {
+                return Err(DecodeError::InvalidValueObject);
+            }
+
+            return expand_value(self.types, self.language, self.direction, self.index, value);
+        }
+
+        let has_value_object_entries = self.language.is_some() || self.direction.is_some();
+
+        let mut node = Node::new();
+        node.id = self.id;
+        for ty in self.types {
+            let ty = Id::try_from(ty).map_err(|_| ExpansionError::InvalidTypeValue)?;
+            node.types_mut_or_default().push(ty)
+        }
+        if let Some(graph) = self.graph {
+            node.set_graph_entry(Some(graph.into_iter().collect()))
+        }
+        if let Some(included) = self.included {
+            let included = included.into_iter().filter_map(|item| item.try_cast().ok());
+            node.set_included(Some(included.collect()))
+        }
+        for (prop, items) in self.properties {
+            node.insert_all(prop, items.into_iter())
+        }
+        for (prop, items) in self.reverse_properties {
+            let nodes = items.into_iter().filter_map(|item| item.try_cast().ok());
+            node.reverse_properties_or_default().insert_all(prop, nodes)
+        }
+
+        if has_value_object_entries && node.is_empty() && node.id.is_none() {
+            return Ok(None);
+        }
+
+        if free_floating && node.is_empty() && self.index.is_none() {
+            return Ok(None);
+        }
+
+        Ok(Some(Indexed::new(Object::node(node), self.index)))
+    }
+}
+
+/// Expands a value object.
+fn expand_value(
+    types: Vec<Term>,
+    language: Option<JsonValue>,
+    direction: Option<JsonValue>,
+    index: Option<String>,
+    value: JsonValue,
+) -> Result<Option<IndexedObject>, DecodeError> {
+    let mut is_json = false;
+    let mut ty = None;
+    for t in types {
+        match t {
+            Term::Keyword(Keyword::Json) => is_json = true,
+            Term::Id(Id::Valid(ValidId::Iri(iri))) => {
+                is_json = false;
+                ty = Some(iri)
+            }
+            _ => return Err(DecodeError::InvalidValueObject),
+        }
+    }
+
+    let language = match language {
+        Some(language) => match language.as_str() {
+            Some("@none") => None,
+            Some(language) => Some(LenientLangTagBuf::new(language.to_owned()).0),
+            None => return Err(DecodeError::InvalidValueObject),
+        },
+        None => None,
+    };
+
+    let direction = match direction {
+        Some(direction) => match direction.as_str().map(Direction::try_from) {
+            Some(Ok(direction)) => Some(direction),
+            _ => return Err(DecodeError::InvalidValueObject),
+        },
+        None => None,
+    };
+
+    if is_json {
+        if language.is_some() || direction.is_some() {
+            return Err(DecodeError::InvalidValueObject);
+        }
+
+        return Ok(Some(Indexed::new(Object::Value(Value::Json(value)), index)));
+    }
+
+    let literal = match value {
+        JsonValue::Null => return Ok(None),
+        JsonValue::Boolean(b) => Literal::Boolean(b),
+        JsonValue::Number(n) => Literal::Number(n),
+        JsonValue::String(s) => Literal::String(s),
+        _ => return Err(DecodeError::InvalidValueObject),
+    };
+
+    if language.is_some() || direction.is_some() {
+        if ty.is_some() {
+            return Err(DecodeError::InvalidValueObject);
+        }
+
+        let Literal::String(s) = literal else {
+            return Err(DecodeError::InvalidValueObject);
+        };
+
+        return match LangString::new(s, language, direction) {
+            Ok(s) => Ok(Some(Indexed::new(
+                Object::Value(Value::LangString(s)),
+                index,
+            ))),
+            Err(_) => Err(DecodeError::InvalidValueObject),
+        };
+    }
+
+    Ok(Some(Indexed::new(
+        Object::Value(Value::Literal(literal, ty)),
+        index,
+    )))
+}
+
+/// Expands a literal value of the given term.
+fn expand_literal(
+    active_context: &json_ld::Context,
+    def: Option<TermDefinitionRef>,
+    value: &JsonValue,
+) -> Result<IndexedObject, DecodeError> {
+    let typ = def.and_then(|d| d.typ()).cloned();
+
+    if let Some(s) = value.as_str() {
+        let id = match typ {
+            Some(json_ld::Type::Id) => Some(expand_iri(active_context, s, true, false)),
+            Some(json_ld::Type::Vocab) => Some(expand_iri(active_context, s, true, true)),
+            _ => None,
+        };
+
+        if let Some(id) = id {
+            let mut node = Node::new();
+            node.id = id.and_then(node_id_of_term);
+            return Ok(Object::node(node).into());
+        }
+    }
+
+    let literal = match value {
+        JsonValue::Boolean(b) => Literal::Boolean(*b),
+        JsonValue::Number(n) => Literal::Number(n.clone()),
+        JsonValue::String(s) => Literal::String(s.clone()),
+        _ => return Err(DecodeError::InvalidValueObject),
+    };
+
+    let ty = match typ {
+        None | Some(json_ld::Type::Id | json_ld::Type::Vocab | json_ld::Type::None) => {
+            if let Literal::String(s) = literal {
+                let language = match def.and_then(|d| d.language()) {
+                    Some(language) => language.cloned().option(),
+                    None => active_context.default_language().map(ToOwned::to_owned),
+                };
+
+                let direction = match def.and_then(|d| d.direction()) {
+                    Some(direction) => direction.option(),
+                    None => active_context.default_base_direction(),
+                };
+
+                let value = match LangString::new(s, language, direction) {
+                    Ok(s) => Value::LangString(s),
+                    Err(s) => Value::Literal(Literal::String(s), None),
+                };
+                return Ok(Object::Value(value).into());
+            }
+
+            None
+        }
+        Some(typ) => Some(
+            typ.into_iri()
+                .map_err(|_| ExpansionError::InvalidTypeValue)?,
+        ),
+    };
+
+    Ok(Object::Value(Value::Literal(literal, ty)).into())
+}
+
+/// Expands the value of a language map.
+fn expand_language_map(
+    active_context: &json_ld::Context,
+    def: TermDefinitionRef,
+    value: &JsonValue,
+) -> Result<Vec<IndexedObject>, DecodeError> {
+    let direction = match def.direction() {
+        Some(direction) => direction.option(),
+        None => active_context.default_base_direction(),
+    };
+
+    let mut items = Vec::new();
+    for entry in value.as_object().into_iter().flat_map(|o| o.iter()) {
+        let language = match expand_iri(active_context, &entry.key, false, true) {
+            Some(Term::Keyword(Keyword::None)) => None,
+            _ => Some(LenientLangTagBuf::new(entry.key.to_string()).0),
+        };
+
+        for item in entry.value.force_as_array() {
+            match item {
+                JsonValue::Null => (),
+                JsonValue::String(s) => {
+                    let value = match LangString::new(s.clone(), language.clone(), direction) {
+                        Ok(s) => Value::LangString(s),
+                        Err(s) => Value::Literal(Literal::String(s), None),
+                    };
+                    items.push(Object::Value(value).into())
+                }
+                _ => return Err(ExpansionError::InvalidLanguageMapValue.into()),
+            }
+        }
+    }
+
+    Ok(items)
+}
+
+/// Wraps an expanded value into a graph object.
+fn graph_object(item: IndexedObject) -> IndexedObject {
+    let mut node = Node::new();
+    node.set_graph_entry(Some([item].into_iter().collect()));
+    Object::node(node).into()
+}
+
+/// Expands an IRI using the given active context.
+fn expand_iri(
+    active_context: &json_ld::Context,
+    value: &str,
+    document_relative: bool,
+    vocab: bool,
+) -> Option<Term> {
+    let mut env = json_ld::Environment {
+        vocabulary: &mut (),
+        loader: &json_ld::NoLoader,
+        warnings: &mut (),
+    };
+
+    expand_iri_simple::<MalformedIri, _, _, _>(
+        &mut env,
+        active_context,
+        Nullable::Some(value.into()),
+        document_relative,
+        vocab.then_some(Action::Keep),
+    )
+    .ok()
+    .flatten()
+}
+
+/// Converts a term to a node identifier, `None` if the term is `null`.
+fn node_id_of_term(term: Term) -> Option<Id> {
+    match term {
+        Term::Null => None,
+        Term::Id(id) => Some(id),
+        Term::Keyword(keyword) => Some(Id::Invalid(keyword.into_str().to_owned())),
+    }
+}
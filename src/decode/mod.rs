@@ -1,14 +1,30 @@
-use std::borrow::Cow;
+use std::{borrow::Cow, io, marker::PhantomData, sync::Arc};
 
 use crate::{
-    transform::{TransformedValue, Transformer, TransformerState},
-    CborObject, CborValue, Codecs, CompressionMode, JsonObject, JsonValue, Tables,
-    CBOR_LD_TAG_HIGH,
+    cache::ContextCache,
+    diagnostic::Diagnostic,
+    json::{JsonKey, JsonLike},
+    lenient::{Warning, WarningKind, Warnings},
+    loader::{block_on, Blocking, ContextPins, PinnedLoader, SyncLoader},
+    lossless,
+    references::{resolve_references, STRINGREF_NAMESPACE_TAG},
+    stats::Handler,
+    stream::{Cbor, Json, StreamFormat, StreamWriter},
+    tables::{Registry, RegistryEntry},
+    transform::{
+        DeferredEntries, ErrorLocation, NoDeferredEntries, PathSegment, TransformedValue,
+        Transformer, TransformerState,
+    },
+    CborObject, CborValue, Codecs, CompressionMode, JsonValue, Tables, CBOR_LD_TAG_HIGH,
 };
 
 mod error;
 pub use error::*;
+mod expanded;
+use expanded::Expansion;
+pub use expanded::*;
 use iref::{IriBuf, IriRef, IriRefBuf};
+use json_ld::{context::TermDefinitionRef, syntax::TryFromJson};
 
 /// Decoding options.
 #[derive(Debug, Default)]
@@ -20,6 +36,38 @@ pub struct DecodeOptions {
 
     /// Tables.
     pub default_tables: Cow<'static, Tables>,
+
+    /// Application compression tables registry, used to resolve the
+    /// compression mode registry entries unknown to this library.
+    pub registry: Registry,
+
+    /// Pinned context digests.
+    ///
+    /// Decoding fails if a pinned context does not match its digest.
+    pub context_pins: ContextPins,
+
+    /// Processed context cache, shared between calls.
+    pub context_cache: Option<Arc<ContextCache>>,
+
+    /// Share term allocation and context IDs between the documents of a CBOR
+    /// sequence, whose position is recorded in the given tag.
+    ///
+    /// Must match the option used for encoding. See [`decode_sequence_with`].
+    pub shared_sequence_tag: Option<u64>,
+
+    /// Tag wrapping the lossless side channels, if any.
+    ///
+    /// Must match the option used for encoding. See the
+    /// [`lossless`](crate::lossless) module.
+    pub lossless_tag: Option<u64>,
+
+    /// Leave values that no codec can handle as-is, instead of failing.
+    ///
+    /// See the [`lenient`](crate::lenient) module.
+    pub lenient: bool,
+
+    /// Lenient mode warnings collector. Warnings are logged if none is given.
+    pub warnings: Option<Arc<Warnings>>,
 }
 
 /// Decodes a CBOR-LD document using the given JSON-LD context loader and the
@@ -38,28 +86,242 @@ pub async fn decode_with(
     loader: impl json_ld::Loader,
     options: DecodeOptions,
 ) -> Result<JsonValue, DecodeError> {
+    decode_document_with(cbor_ld_document, loader, options).await
+}
+
+/// Decodes a CBOR-LD document into a [`serde_json::Value`] using the given
+/// JSON-LD context loader and the default options.
+#[cfg(feature = "serde_json")]
+pub async fn decode_serde_json(
+    cbor_ld_document: &CborValue,
+    loader: impl json_ld::Loader,
+) -> Result<serde_json::Value, DecodeError> {
+    decode_serde_json_with(cbor_ld_document, loader, Default::default()).await
+}
+
+/// Decodes a CBOR-LD document into a [`serde_json::Value`] using the given
+/// JSON-LD context loader and the given options.
+///
+/// The document is built directly, without going through a [`JsonValue`].
+#[cfg(feature = "serde_json")]
+pub async fn decode_serde_json_with(
+    cbor_ld_document: &CborValue,
+    loader: impl json_ld::Loader,
+    options: DecodeOptions,
+) -> Result<serde_json::Value, DecodeError> {
+    decode_document_with(cbor_ld_document, loader, options).await
+}
+
+async fn decode_document_with<V: JsonLike>(
+    cbor_ld_document: &CborValue,
+    loader: impl json_ld::Loader,
+    options: DecodeOptions,
+) -> Result<V, DecodeError> {
+    let pipeline = DecodePipeline::from(options);
+    let loader = pipeline.loader(loader);
+    pipeline.decode(&loader, cbor_ld_document).await
+}
+
+/// Decoding pipeline configured by [`DecodeOptions`].
+///
+/// Shared by the decoding functions and [`CborLd`](crate::CborLd) sessions.
+pub(crate) struct DecodePipeline {
+    pub default_tables: Arc<Tables>,
+    pub registry: Registry,
+    pub codecs: Arc<Codecs>,
+    pub context_pins: ContextPins,
+    pub context_cache: Option<Arc<ContextCache>>,
+    pub shared_sequence_tag: Option<u64>,
+    pub lossless_tag: Option<u64>,
+    pub lenient: bool,
+    pub warnings: Option<Arc<Warnings>>,
+}
+
+impl From<DecodeOptions> for DecodePipeline {
+    fn from(options: DecodeOptions) -> Self {
+        Self {
+            default_tables: Arc::new(options.default_tables.into_owned()),
+            registry: options.registry,
+            codecs: Arc::new(options.codecs),
+            context_pins: options.context_pins,
+            context_cache: options.context_cache,
+            shared_sequence_tag: options.shared_sequence_tag,
+            lossless_tag: options.lossless_tag,
+            lenient: options.lenient,
+            warnings: options.warnings,
+        }
+    }
+}
+
+impl DecodePipeline {
+    /// Wraps the given loader to check the pinned contexts.
+    pub fn loader<L>(&self, loader: L) -> PinnedLoader<L> {
+        PinnedLoader::new(loader, self.context_pins.clone())
+    }
+
+    /// Returns the compression tables of the given compression mode.
+    pub fn tables(&self, compression_mode: CompressionMode) -> Result<Arc<Tables>, DecodeError> {
+        match compression_mode {
+            CompressionMode::Uncompressed => Err(DecodeError::UnsupportedCompressionMode(
+                compression_mode.id(),
+            )),
+            CompressionMode::Compressed(entry) => {
+                let tables = self.registry.resolve(entry, &self.default_tables)?;
+                if entry == RegistryEntry::Default && tables.strings.is_some() {
+                    return Err(DecodeError::UnregisteredStringDictionary);
+                }
+
+                Ok(tables)
+            }
+        }
+    }
+
+    /// Splits the position in its sequence off a document body encoded with
+    /// shared sequence state.
+    pub fn split_sequence_position<'a>(
+        &self,
+        body: &'a CborValue,
+    ) -> Result<(Option<u64>, &'a CborValue), DecodeError> {
+        match (body, self.shared_sequence_tag) {
+            (CborValue::Tag(tag, value), Some(shared_sequence_tag))
+                if *tag == shared_sequence_tag =>
+            {
+                match value.as_array().map(Vec::as_slice) {
+                    Some([CborValue::Integer(position), body]) => {
+                        let position = u64::try_from(*position)
+                            .map_err(|_| DecodeError::SequenceStateMismatch)?;
+                        Ok((Some(position), body))
+                    }
+                    _ => Err(DecodeError::InvalidValue),
+                }
+            }
+            (body, _) => Ok((None, body)),
+        }
+    }
+
+    /// Creates a new decoder.
+    pub fn decoder<L, V>(&self, loader: L, tables: Arc<Tables>) -> Decoder<L, V> {
+        let mut decoder = Decoder::new(loader, self.codecs.clone(), tables);
+        decoder.state.context_cache = self.context_cache.clone();
+        decoder.lossless_tag = self.lossless_tag;
+        decoder.state.lenient = self.lenient;
+        decoder.state.warnings = self.warnings.clone();
+        decoder
+    }
+
+    /// Decodes a CBOR-LD document.
+    pub async fn decode<L, V>(
+        &self,
+        loader: L,
+        cbor_ld_document: &CborValue,
+    ) -> Result<V, DecodeError>
+    where
+        L: json_ld::Loader,
+        V: JsonLike,
+    {
+        let (compression_mode, body) = untag_cbor_ld(cbor_ld_document)?;
+        let (None, body) = self.split_sequence_position(body)? else {
+            return Err(DecodeError::SequenceStateMismatch);
+        };
+        let mut decoder = self.decoder(loader, self.tables(compression_mode)?);
+        decoder.decode_body(body).await
+    }
+
+    /// Decodes a CBOR-LD document into an expanded JSON-LD document.
+    pub async fn decode_expanded<L>(
+        &self,
+        loader: L,
+        cbor_ld_document: &CborValue,
+    ) -> Result<json_ld::ExpandedDocument, DecodeError>
+    where
+        L: json_ld::Loader,
+    {
+        let (compression_mode, body) = untag_cbor_ld(cbor_ld_document)?;
+        let (None, body) = self.split_sequence_position(body)? else {
+            return Err(DecodeError::SequenceStateMismatch);
+        };
+        let mut decoder: Decoder<L> = self.decoder(loader, self.tables(compression_mode)?);
+        decoder.decode_expanded_body(body).await
+    }
+
+    /// Reads a CBOR-LD document from `reader`, writing the JSON-LD output
+    /// incrementally.
+    ///
+    /// See the [`stream`](crate::stream) module.
+    pub async fn decode_stream<L>(
+        &self,
+        loader: L,
+        mut reader: impl io::Read,
+        writer: impl io::Write,
+    ) -> Result<(), DecodeError>
+    where
+        L: json_ld::Loader,
+    {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes)?;
+        match Cbor::read_document(bytes) {
+            Ok((cbor_ld_document, deferred)) => {
+                self.decode_to_writer(loader, &cbor_ld_document, &deferred, writer)
+                    .await
+            }
+            Err(bytes) => {
+                let cbor_ld_document = ciborium::from_reader(bytes.as_slice())?;
+                drop(bytes);
+                self.decode_to_writer(loader, &cbor_ld_document, &NoDeferredEntries, writer)
+                    .await
+            }
+        }
+    }
+
+    /// Decodes a CBOR-LD document whose root node entries may be deferred,
+    /// writing the JSON-LD output incrementally.
+    async fn decode_to_writer<L>(
+        &self,
+        loader: L,
+        cbor_ld_document: &CborValue,
+        deferred: &impl DeferredEntries<CborValue, CborValue, DecodeError>,
+        writer: impl io::Write,
+    ) -> Result<(), DecodeError>
+    where
+        L: json_ld::Loader,
+    {
+        let (compression_mode, body) = untag_cbor_ld(cbor_ld_document)?;
+        let (None, body) = self.split_sequence_position(body)? else {
+            return Err(DecodeError::SequenceStateMismatch);
+        };
+        let mut decoder: Decoder<L> = self.decoder(loader, self.tables(compression_mode)?);
+        decoder.decode_body_to_writer(body, deferred, writer).await
+    }
+}
+
+/// Resolves the value references of a CBOR-LD document body, if any.
+fn resolved_body(body: &CborValue) -> Result<Cow<'_, CborValue>, DecodeError> {
+    if let CborValue::Tag(STRINGREF_NAMESPACE_TAG, _) = body {
+        resolve_references(body).map(Cow::Owned)
+    } else {
+        Ok(Cow::Borrowed(body))
+    }
+}
+
+/// Splits a CBOR-LD document into its compression mode and body.
+pub(crate) fn untag_cbor_ld(
+    cbor_ld_document: &CborValue,
+) -> Result<(CompressionMode, &CborValue), DecodeError> {
     match cbor_ld_document {
         CborValue::Tag(tag, value) => {
             if tag >> 8 != CBOR_LD_TAG_HIGH as u64 {
                 return Err(DecodeError::NotCborLd);
             }
 
-            let varint_high = (tag & 0xff) as u8;
+            let varint_high = tag & 0xff;
 
-            let compression_mode = if varint_high >= 128 {
-                todo!()
-            } else {
-                CompressionMode::from_id(varint_high as u64)
-            };
-
-            match compression_mode {
-                CompressionMode::Uncompressed => todo!("uncompressed"),
-                CompressionMode::Compressed(registry_entry) => {
-                    let tables = registry_entry.tables(options.default_tables)?;
-                    let mut decoder = Decoder::new(loader, options.codecs, tables);
-                    decoder.decode(value).await
-                }
+            if varint_high >= 128 {
+                return Err(DecodeError::UnsupportedCompressionMode(varint_high));
             }
+
+            let compression_mode = CompressionMode::from_id(varint_high);
+
+            Ok((compression_mode, value))
         }
         _ => Err(DecodeError::NotCborLd),
     }
@@ -85,38 +347,284 @@ pub async fn decode_from_bytes_with(
     decode_with(&cbor_ld_document, loader, options).await
 }
 
+/// Decodes CBOR-LD bytes and deserializes the resulting JSON-LD document
+/// using the given JSON-LD context loader and the default options.
+pub async fn from_slice<T: serde::de::DeserializeOwned>(
+    bytes: &[u8],
+    loader: impl json_ld::Loader,
+) -> Result<T, DecodeError> {
+    from_slice_with(bytes, loader, Default::default()).await
+}
+
+/// Decodes CBOR-LD bytes and deserializes the resulting JSON-LD document
+/// using the given JSON-LD context loader and the given options.
+///
+/// The value is deserialized from the decoded JSON tree, without going
+/// through JSON text.
+pub async fn from_slice_with<T: serde::de::DeserializeOwned>(
+    bytes: &[u8],
+    loader: impl json_ld::Loader,
+    options: DecodeOptions,
+) -> Result<T, DecodeError> {
+    let json_ld_document = decode_from_bytes_with(bytes, loader, options).await?;
+    json_syntax::from_value(json_ld_document)
+        .map_err(|e| DecodeError::Deserialization(e.to_string()))
+}
+
+/// Decodes a CBOR sequence (RFC 8742) of CBOR-LD documents using the given
+/// JSON-LD context loader and the default options.
+pub async fn decode_sequence<'a>(
+    cbor_ld_documents: impl IntoIterator<Item = &'a CborValue>,
+    loader: impl json_ld::Loader,
+) -> Result<Vec<JsonValue>, DecodeError> {
+    decode_sequence_with(cbor_ld_documents, loader, Default::default()).await
+}
+
+/// Decodes a CBOR sequence (RFC 8742) of CBOR-LD documents using the given
+/// JSON-LD context loader and the given options.
+///
+/// With [`DecodeOptions::shared_sequence_tag`], documents are decoded in
+/// order, each one reusing the term IDs and context IDs of the previous
+/// ones. All the documents must then use the same compression mode.
+///
+/// Documents encoded with shared state record their position in the sequence
+/// in this tag (see [`encode_sequence_with`](crate::encode_sequence_with)).
+/// Decoding fails if the documents are out of order, or if they do not
+/// record their position although the option is set.
+pub async fn decode_sequence_with<'a>(
+    cbor_ld_documents: impl IntoIterator<Item = &'a CborValue>,
+    loader: impl json_ld::Loader,
+    options: DecodeOptions,
+) -> Result<Vec<JsonValue>, DecodeError> {
+    let pipeline = DecodePipeline::from(options);
+    let loader = pipeline.loader(loader);
+    let share_sequence_state = pipeline.shared_sequence_tag.is_some();
+    let mut shared_decoder = None;
+    let mut result = Vec::new();
+
+    for (position, cbor_ld_document) in cbor_ld_documents.into_iter().enumerate() {
+        let (compression_mode, body) = untag_cbor_ld(cbor_ld_document)?;
+        let (sequence_position, body) = pipeline.split_sequence_position(body)?;
+        let expected_position = share_sequence_state.then_some(position as u64);
+        if sequence_position != expected_position {
+            return Err(DecodeError::SequenceStateMismatch);
+        }
+
+        let json = if share_sequence_state {
+            if shared_decoder.is_none() {
+                let decoder = pipeline
+                    .decoder(&loader, pipeline.tables(compression_mode)?)
+                    .with_shared_sequence_state();
+                shared_decoder = Some((compression_mode, decoder))
+            }
+
+            let (shared_compression_mode, decoder) = shared_decoder.as_mut().unwrap();
+            if *shared_compression_mode != compression_mode {
+                return Err(DecodeError::SequenceCompressionMismatch);
+            }
+
+            decoder.decode_body(body).await?
+        } else {
+            let mut decoder = pipeline.decoder(&loader, pipeline.tables(compression_mode)?);
+            decoder.decode_body(body).await?
+        };
+
+        result.push(json)
+    }
+
+    Ok(result)
+}
+
+/// Decodes CBOR sequence (RFC 8742) bytes of CBOR-LD documents using the
+/// given JSON-LD context loader and the default options.
+pub async fn decode_sequence_from_bytes(
+    bytes: &[u8],
+    loader: impl json_ld::Loader,
+) -> Result<Vec<JsonValue>, DecodeError> {
+    decode_sequence_from_bytes_with(bytes, loader, Default::default()).await
+}
+
+/// Decodes CBOR sequence (RFC 8742) bytes of CBOR-LD documents using the
+/// given JSON-LD context loader and the given options.
+pub async fn decode_sequence_from_bytes_with(
+    bytes: &[u8],
+    loader: impl json_ld::Loader,
+    options: DecodeOptions,
+) -> Result<Vec<JsonValue>, DecodeError> {
+    let cbor_ld_documents = cbor_sequence_from_bytes(bytes)?;
+    decode_sequence_with(&cbor_ld_documents, loader, options).await
+}
+
+/// Parses a CBOR sequence (RFC 8742).
+pub fn cbor_sequence_from_bytes(mut bytes: &[u8]) -> Result<Vec<CborValue>, DecodeError> {
+    let mut sequence = Vec::new();
+    while !bytes.is_empty() {
+        sequence.push(ciborium::from_reader(&mut bytes)?)
+    }
+
+    Ok(sequence)
+}
+
+/// Reads a CBOR-LD document from `reader` and writes the decoded JSON-LD
+/// document to `writer` using the given JSON-LD context loader and the
+/// default options.
+///
+/// The output is written incrementally, without building the CBOR or JSON
+/// tree of the whole document. See the [`stream`](crate::stream) module.
+pub async fn decode_stream(
+    reader: impl io::Read,
+    writer: impl io::Write,
+    loader: impl json_ld::Loader,
+) -> Result<(), DecodeError> {
+    decode_stream_with(reader, writer, loader, Default::default()).await
+}
+
+/// Reads a CBOR-LD document from `reader` and writes the decoded JSON-LD
+/// document to `writer` using the given JSON-LD context loader and the given
+/// options.
+pub async fn decode_stream_with(
+    reader: impl io::Read,
+    writer: impl io::Write,
+    loader: impl json_ld::Loader,
+    options: DecodeOptions,
+) -> Result<(), DecodeError> {
+    let pipeline = DecodePipeline::from(options);
+    let loader = pipeline.loader(loader);
+    pipeline.decode_stream(&loader, reader, writer).await
+}
+
+/// Decodes a CBOR-LD document using the given synchronous JSON-LD context
+/// loader and the default options, blocking the current thread.
+pub fn decode_sync(
+    cbor_ld_document: &CborValue,
+    loader: impl SyncLoader,
+) -> Result<JsonValue, DecodeError> {
+    decode_sync_with(cbor_ld_document, loader, Default::default())
+}
+
+/// Decodes a CBOR-LD document using the given synchronous JSON-LD context
+/// loader and the given options, blocking the current thread.
+pub fn decode_sync_with(
+    cbor_ld_document: &CborValue,
+    loader: impl SyncLoader,
+    options: DecodeOptions,
+) -> Result<JsonValue, DecodeError> {
+    block_on(decode_with(cbor_ld_document, Blocking(loader), options))
+}
+
+/// Decodes a CBOR-LD document bytes using the given synchronous JSON-LD
+/// context loader and the default options, blocking the current thread.
+pub fn decode_from_bytes_sync(
+    bytes: &[u8],
+    loader: impl SyncLoader,
+) -> Result<JsonValue, DecodeError> {
+    decode_from_bytes_sync_with(bytes, loader, Default::default())
+}
+
+/// Decodes a CBOR-LD document bytes using the given synchronous JSON-LD
+/// context loader and the given options, blocking the current thread.
+pub fn decode_from_bytes_sync_with(
+    bytes: &[u8],
+    loader: impl SyncLoader,
+    options: DecodeOptions,
+) -> Result<JsonValue, DecodeError> {
+    block_on(decode_from_bytes_with(bytes, Blocking(loader), options))
+}
+
 /// CBOR-LD decoder.
-pub struct Decoder<L> {
+///
+/// The output JSON-LD documents can be any [`JsonLike`] representation,
+/// [`JsonValue`] by default.
+pub struct Decoder<L, V = JsonValue> {
     loader: L,
-    state: TransformerState,
+    pub(crate) state: TransformerState,
+    lossless_tag: Option<u64>,
+    expansion: Option<Expansion>,
+    value: PhantomData<fn() -> V>,
 }
 
-impl<L> Decoder<L> {
+impl<L, V> Decoder<L, V> {
     pub fn new(
         loader: L,
         // application_context_map: IdMap,
-        codecs: Codecs,
-        tables: Cow<'static, Tables>,
+        codecs: impl Into<Arc<Codecs>>,
+        tables: impl Into<Arc<Tables>>,
     ) -> Self {
         Self {
             loader,
             state: TransformerState::new(
                 // application_context_map,
-                codecs, tables,
+                codecs.into(),
+                tables.into(),
             ),
+            lossless_tag: None,
+            expansion: None,
+            value: PhantomData,
         }
     }
+
+    /// Uses the given processed context cache.
+    pub fn with_context_cache(mut self, cache: Arc<ContextCache>) -> Self {
+        self.state.context_cache = Some(cache);
+        self
+    }
+
+    /// Shares term allocation and learned context IDs between the decoded
+    /// documents, as the documents of a CBOR sequence.
+    pub(crate) fn with_shared_sequence_state(mut self) -> Self {
+        self.state.share_sequence_state();
+        self
+    }
+
+    /// Sets the lenient mode, discarding warnings.
+    ///
+    /// Used to mirror a lenient encoder, whose warnings are already reported.
+    pub(crate) fn with_quiet_lenient_mode(mut self, lenient: bool) -> Self {
+        self.state.lenient = lenient;
+        self.state.warnings = Some(Arc::new(Warnings::new()));
+        self
+    }
 }
 
-impl<L> Decoder<L>
+impl<L, V> Decoder<L, V>
 where
     L: json_ld::Loader,
+    V: JsonLike,
 {
-    pub async fn decode(&mut self, json_ld_document: &CborValue) -> Result<JsonValue, DecodeError> {
+    pub async fn decode(&mut self, json_ld_document: &CborValue) -> Result<V, DecodeError> {
+        self.state.reset();
         let active_context = json_ld::Context::new(None);
         self.transform(&active_context, json_ld_document).await
     }
 
+    /// Decodes the body of a CBOR-LD document, resolving value references
+    /// and applying the lossless side channel, if any.
+    pub(crate) async fn decode_body(&mut self, body: &CborValue) -> Result<V, DecodeError> {
+        let body = resolved_body(body)?;
+        match lossless::split(self.lossless_tag, &body)? {
+            (body, Some(patch)) => lossless::restore(&self.decode(body).await?, patch),
+            (body, None) => self.decode(body).await,
+        }
+    }
+
+    /// Decodes the body of a CBOR-LD document into an expanded JSON-LD
+    /// document, built from the active contexts while decoding.
+    ///
+    /// The lossless side channel, if any, is ignored: it does not change
+    /// the meaning of the document.
+    pub(crate) async fn decode_expanded_body(
+        &mut self,
+        body: &CborValue,
+    ) -> Result<json_ld::ExpandedDocument, DecodeError> {
+        let body = resolved_body(body)?;
+        let (body, _) = lossless::split(self.lossless_tag, &body)?;
+        self.expansion = Some(Expansion::new());
+        let result = self.decode(body).await;
+        let expansion = self.expansion.take().unwrap_or_default();
+        result?;
+        expansion.finish()
+    }
+
     fn decode_vocab_term(
         &self,
         active_context: &json_ld::Context,
@@ -124,34 +632,132 @@ where
     ) -> Result<String, DecodeError> {
         self.state.decode_vocab_term(active_context, value)
     }
+
+    /// Records the handler of the current value, when annotating a
+    /// document.
+    fn record(&self, handler: impl FnOnce() -> Handler) {
+        if let Some(recorder) = &self.state.recorder {
+            recorder.handler(&self.state.path, handler())
+        }
+    }
+
+    /// Records the IRI codec used to encode the current value.
+    fn record_iri(&self, value: &CborValue) {
+        self.record(|| {
+            let scheme = match value {
+                CborValue::Array(items) => match items.first() {
+                    Some(CborValue::Integer(id)) => u64::try_from(*id)
+                        .ok()
+                        .and_then(|id| self.state.codecs.iri.get_by_id(id))
+                        .map(|(prefix, _)| prefix.to_owned()),
+                    _ => None,
+                },
+                _ => None,
+            };
+
+            Handler::Iri(scheme)
+        })
+    }
+
+    /// Leaves the value as-is if it could not be decoded, in lenient mode.
+    fn or_as_is(
+        &self,
+        result: Result<V, DecodeError>,
+        value: &CborValue,
+    ) -> Result<V, DecodeError> {
+        match result {
+            Err(e) if self.state.lenient => {
+                self.warn(WarningKind::UnhandledValue(e.to_string()));
+                self.transform_json(value)
+            }
+            result => result,
+        }
+    }
 }
 
-impl<L> Transformer for Decoder<L>
+impl<L> Decoder<L>
 where
     L: json_ld::Loader,
+{
+    /// Decodes a CBOR-LD document body, writing the resulting JSON-LD
+    /// document incrementally to the given writer.
+    ///
+    /// See the [`stream`](crate::stream) module.
+    pub async fn decode_to_writer(
+        &mut self,
+        json_ld_document: &CborValue,
+        writer: impl io::Write,
+    ) -> Result<(), DecodeError> {
+        self.decode_node_to_writer(json_ld_document, &NoDeferredEntries, writer)
+            .await
+    }
+
+    /// Decodes a CBOR-LD document body whose root node entries may be
+    /// deferred, writing the resulting JSON-LD document incrementally.
+    async fn decode_node_to_writer(
+        &mut self,
+        json_ld_document: &CborValue,
+        deferred: &impl DeferredEntries<CborValue, CborValue, DecodeError>,
+        writer: impl io::Write,
+    ) -> Result<(), DecodeError> {
+        self.state.reset();
+        let active_context = json_ld::Context::new(None);
+        let object = json_ld_document
+            .as_map()
+            .ok_or(DecodeError::ExpectedNodeObject)?;
+        let mut sink = StreamWriter::<Json, _>::new(writer);
+        self.transform_node_into(&active_context, object, deferred, &mut sink)
+            .await
+    }
+
+    /// Decodes the body of a CBOR-LD document to the given writer, resolving
+    /// value references first if any.
+    ///
+    /// Restoring a lossless document needs the whole decoded document: it is
+    /// then built in memory before being written.
+    pub(crate) async fn decode_body_to_writer(
+        &mut self,
+        body: &CborValue,
+        deferred: &impl DeferredEntries<CborValue, CborValue, DecodeError>,
+        mut writer: impl io::Write,
+    ) -> Result<(), DecodeError> {
+        let body = resolved_body(body)?;
+        match lossless::split(self.lossless_tag, &body)? {
+            (body, Some(patch)) => {
+                let json = lossless::restore(&self.decode(body).await?, patch)?;
+                Json::write_value(&mut writer, &json).map_err(Into::into)
+            }
+            (body, None) => self.decode_node_to_writer(body, deferred, writer).await,
+        }
+    }
+}
+
+impl<L, V> Transformer for Decoder<L, V>
+where
+    L: json_ld::Loader,
+    V: JsonLike,
 {
     type Input = CborValue;
-    type Output = JsonValue;
+    type Output = V;
 
     type InputObject = CborObject;
-    type OutputObject = JsonObject;
+    type OutputObject = V::Map;
 
     type InputKey = CborValue;
-    type OutputKey = json_ld::syntax::object::Key;
+    type OutputKey = V::Key;
 
     type Loader = L;
     type Error = DecodeError;
 
     fn context_iri_ref(&self, value: &Self::Input) -> Result<IriRefBuf, Self::Error> {
+        self.record(|| Handler::Context);
         match value {
             CborValue::Integer(i) => {
                 let i = u64::try_from(*i)
                     .map_err(|_| DecodeError::UndefinedCompressedContext(value.clone()))?;
 
                 self.state
-                    .tables
-                    .context
-                    .get_iri_ref(i)
+                    .context_iri_ref(i)
                     .ok_or_else(|| DecodeError::UndefinedCompressedContext(value.clone()))
                     .map(ToOwned::to_owned)
             }
@@ -163,22 +769,26 @@ where
     }
 
     fn context_id(&self, _value: &Self::Input, iri_ref: &IriRef) -> Self::Output {
-        JsonValue::String(iri_ref.as_str().into())
+        V::string(iri_ref.as_str().to_owned())
     }
 
     fn term_key(&self, term: &str, _plural: bool) -> Result<Self::OutputKey, Self::Error> {
-        Ok(term.into())
+        Ok(V::Key::from_string(term.to_owned()))
     }
 
     fn term_value(&self, term: &str) -> Result<Self::Output, Self::Error> {
-        Ok(JsonValue::String(term.into()))
+        Ok(V::string(term.to_owned()))
     }
 
     fn key_term<'a>(
         &'a self,
         key: &'a Self::InputKey,
-        _value: &Self::Input,
+        value: &Self::Input,
     ) -> Result<Option<(&'a str, bool)>, Self::Error> {
+        if let CborValue::Text(term) = key {
+            return Ok(Some((term, value.is_array())));
+        }
+
         let i = key.as_integer().ok_or(DecodeError::InvalidVocabTermKind)?;
 
         let i = u64::try_from(i).map_err(|_| DecodeError::UndefinedCompressedTerm(key.clone()))?;
@@ -196,9 +806,9 @@ where
     }
 
     fn transform_id(&self, value: &Self::Input) -> Result<Self::Output, Self::Error> {
-        Ok(JsonValue::String(
-            self.state.codecs.iri.decode(value)?.into_string().into(),
-        ))
+        self.record_iri(value);
+        let id = self.state.codecs.iri.decode(value);
+        self.or_as_is(id.map(|id| V::string(id.into_string())), value)
     }
 
     fn transform_vocab(
@@ -206,14 +816,96 @@ where
         active_context: &json_ld::Context,
         value: &Self::Input,
     ) -> Result<Self::Output, Self::Error> {
-        self.decode_vocab_term(active_context, value)
-            .map(|v| JsonValue::String(v.into()))
+        let term = self.decode_vocab_term(active_context, value);
+        self.record(|| Handler::Vocab);
+        self.or_as_is(term.map(V::string), value)
+    }
+
+    fn transform_json(&self, value: &Self::Input) -> Result<Self::Output, Self::Error> {
+        self.record(|| Handler::Json);
+        self.state.codecs.json.decode(value).map(V::from_json_value)
+    }
+
+    fn transform_language(&self, value: &Self::Input) -> Result<Self::Output, Self::Error> {
+        self.record(|| Handler::Language);
+        let tag = self.state.codecs.language.decode(value);
+        self.or_as_is(tag.map(V::string), value)
+    }
+
+    fn language_key(&self, key: &Self::InputKey) -> Result<Self::OutputKey, Self::Error> {
+        self.state
+            .codecs
+            .language
+            .decode(key)
+            .map(V::Key::from_string)
     }
 
     fn state_and_loader_mut(&mut self) -> (&mut TransformerState, &mut Self::Loader) {
         (&mut self.state, &mut self.loader)
     }
 
+    fn path_key(&self, key: &Self::InputKey) -> String {
+        Diagnostic(key).to_string()
+    }
+
+    fn warn(&self, kind: WarningKind) {
+        self.state.warn(Warning {
+            kind,
+            location: ErrorLocation::cbor_path(&self.state.path),
+        })
+    }
+
+    fn inline_context(
+        &self,
+        value: &Self::Input,
+    ) -> Result<Option<(json_ld::syntax::Context, Self::Output)>, Self::Error> {
+        if !value.is_map() {
+            return Ok(None);
+        }
+
+        if self.state.lenient {
+            self.warn(WarningKind::InlineContext);
+        }
+
+        let json = self.state.codecs.json.decode(value)?;
+        let context = json_ld::syntax::Context::try_from_json(json.clone())?;
+        Ok(Some((context, V::from_json_value(json))))
+    }
+
+    fn begin_expanded_node(&mut self) {
+        if let Some(expansion) = &mut self.expansion {
+            expansion.begin_node()
+        }
+    }
+
+    fn expand_entry(
+        &mut self,
+        active_context: &json_ld::Context,
+        term: &str,
+        def: Option<TermDefinitionRef>,
+        value: Option<&Self::Output>,
+    ) -> Result<(), Self::Error> {
+        match &mut self.expansion {
+            Some(expansion) => {
+                let value = value.map(V::to_json_value);
+                expansion.entry(active_context, term, def, value.as_deref())
+            }
+            None => Ok(()),
+        }
+    }
+
+    fn end_expanded_node(&mut self) -> Result<(), Self::Error> {
+        match &mut self.expansion {
+            Some(expansion) => expansion.end_node(),
+            None => Ok(()),
+        }
+    }
+
+    fn uncompressed_key(&self, key: &Self::InputKey, _defined: bool) -> Option<Self::OutputKey> {
+        key.as_text()
+            .map(|term| V::Key::from_string(term.to_owned()))
+    }
+
     fn transform_typed_value(
         &mut self,
         active_context: &json_ld::Context,
@@ -225,11 +917,22 @@ where
         } else {
             match type_ {
                 Some(type_) => match self.state.tables.types.get(type_) {
-                    Some(t) => t.decode(value).map(Some),
+                    Some(t) => {
+                        self.record(|| Handler::TypeTable(type_.to_string()));
+                        t.decode(value).map(|v| Some(V::from_json_value(v)))
+                    }
                     None => match self.state.codecs.type_.get(type_) {
-                        Some(codec) => codec
-                            .decode(&self.state, active_context, value)
-                            .map(|ty| Some(JsonValue::String(ty.into()))),
+                        Some(codec) => {
+                            match (type_, value) {
+                                // IRIs compressed by the IRI codecs.
+                                (json_ld::Type::Id | json_ld::Type::Vocab, CborValue::Array(_)) => {
+                                    self.record_iri(value)
+                                }
+                                _ => self.record(|| Handler::TypeCodec(type_.to_string())),
+                            }
+                            let decoded = codec.decode(&self.state, active_context, value);
+                            self.or_as_is(decoded.map(V::string), value).map(Some)
+                        }
                         None => Ok(None),
                     },
                 },
@@ -244,26 +947,54 @@ where
         value: &Self::Input,
     ) -> Result<Self::Output, Self::Error> {
         match value {
-            CborValue::Null => Ok(JsonValue::Null),
-            CborValue::Bool(b) => Ok(JsonValue::Boolean(*b)),
+            CborValue::Null => {
+                self.record(|| Handler::Plain);
+                Ok(V::null())
+            }
+            CborValue::Bool(b) => {
+                self.record(|| Handler::Plain);
+                Ok(V::boolean(*b))
+            }
             CborValue::Integer(n) => {
-                let n: i128 = (*n).into();
-                Ok(JsonValue::Number(n.to_string().parse().unwrap()))
+                self.record(|| Handler::Plain);
+                Ok(V::integer((*n).into()))
+            }
+            CborValue::Float(f) => {
+                self.record(|| Handler::Plain);
+                V::float(*f).ok_or(DecodeError::NonFiniteFloat)
+            }
+            CborValue::Text(_) | CborValue::Tag(_, _) => {
+                let dictionary = self.state.tables.strings.as_ref().filter(|d| match value {
+                    CborValue::Tag(tag, _) => *tag == d.tag(),
+                    _ => true,
+                });
+
+                match dictionary {
+                    Some(dictionary) => {
+                        self.record(|| Handler::Dictionary);
+                        Ok(V::string(self.state.strings.decode(dictionary, value)?))
+                    }
+                    None => match value {
+                        CborValue::Text(s) => {
+                            self.record(|| Handler::Plain);
+                            Ok(V::string(s.clone()))
+                        }
+                        _ => Err(DecodeError::InvalidValue),
+                    },
+                }
             }
-            CborValue::Float(f) => Ok(JsonValue::Number(
-                (*f).try_into().map_err(|_| DecodeError::NonFiniteFloat)?,
-            )),
-            CborValue::Text(s) => Ok(JsonValue::String(s.as_str().into())),
             CborValue::Array(array) => {
                 let mut json_array = Vec::with_capacity(array.len());
 
-                for item in array {
-                    json_array.push(Box::pin(self.transform(active_context, item)).await?);
+                for (i, item) in array.iter().enumerate() {
+                    self.enter(PathSegment::Index(i));
+                    let json_item = Box::pin(self.transform(active_context, item)).await;
+                    json_array.push(self.leave(json_item)?);
                 }
 
-                Ok(JsonValue::Array(json_array))
+                Ok(V::new_array(json_array))
             }
-            CborValue::Map(object) => Ok(JsonValue::Object(
+            CborValue::Map(object) => Ok(V::new_object(
                 Box::pin(self.transform_node(active_context, object)).await?,
             )),
             _ => Err(DecodeError::InvalidValue),
@@ -275,7 +1006,7 @@ where
 mod tests {
     use std::future::Future;
 
-    use super::{decode, decode_from_bytes};
+    use super::{decode, decode_from_bytes, untag_cbor_ld, DecodeError};
 
     fn assert_send(_: impl Send + Future) {}
 
@@ -288,4 +1019,13 @@ mod tests {
     fn decode_from_bytes_is_send() {
         assert_send(decode_from_bytes(b"", json_ld::NoLoader))
     }
+
+    #[test]
+    fn varint_compression_mode() {
+        let document = ciborium::Value::Tag(0x0680, Box::new(ciborium::Value::Null));
+        assert!(matches!(
+            untag_cbor_ld(&document),
+            Err(DecodeError::UnsupportedCompressionMode(0x80))
+        ))
+    }
 }
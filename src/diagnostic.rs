@@ -1,5 +1,40 @@
-use crate::CborValue;
-use std::fmt;
+//! CBOR extended diagnostic notation (EDN).
+//!
+//! [`Edn`] represents a CBOR data item along with the encoding details that
+//! [`CborValue`] does not keep: encoding indicators and indefinite lengths
+//! (RFC 8949 §8.1). It is printed in diagnostic notation by its `Display`
+//! implementation (pretty-printed with the alternate `{:#}` flag), parsed
+//! from diagnostic notation with [`FromStr`], and converted from and to CBOR
+//! bytes and [`CborValue`].
+//!
+//! ```
+//! use cbor_ld::diagnostic::Edn;
+//!
+//! let edn: Edn = "{1: [_ 2, 3.5_2], 4: h'cafe'}".parse().unwrap();
+//! assert_eq!(edn.to_bytes(), [0xa2, 0x01, 0x9f, 0x02, 0xfa, 0x40, 0x60, 0x00, 0x00, 0xff, 0x04, 0x42, 0xca, 0xfe]);
+//! assert_eq!(edn.to_string(), "{1: [_ 2, 3.5_2], 4: h'cafe'}");
+//! ```
+//!
+//! Besides the RFC 8949 notation, the parser accepts the `b64'...'` and
+//! `'...'` byte strings, and the hexadecimal (`0x`), octal (`0o`) and binary
+//! (`0b`) integers of RFC 8610 Appendix G, along with `/ ... /` and `# ...`
+//! (end of line) comments, so that test vectors can be written in
+//! diagnostic notation.
+//!
+//! [`Edn::annotated`] pretty-prints a CBOR-LD document with comments giving
+//! the term of each integer key and value, the IRI of each context ID and the
+//! codec or table that handled each value, as collected by [`annotations`],
+//! and optionally the byte offset and length of each entry.
+use std::{collections::HashMap, fmt, str::FromStr};
+
+use half::f16;
+
+use crate::{
+    decode::{untag_cbor_ld, DecodePipeline},
+    stats::{Handler, Recorder},
+    transform::{json_pointer, PathSegment, TransformerState},
+    CborValue, CompressionMode, DecodeError, DecodeOptions, JsonValue,
+};
 
 pub struct Diagnostic<T>(pub T);
 
@@ -13,7 +48,7 @@ pub trait DisplayDiagnostic {
     fn fmt_diagnostic(&self, f: &mut fmt::Formatter) -> fmt::Result;
 }
 
-impl<'a, T: DisplayDiagnostic> DisplayDiagnostic for &'a T {
+impl<T: DisplayDiagnostic> DisplayDiagnostic for &T {
     fn fmt_diagnostic(&self, f: &mut fmt::Formatter) -> fmt::Result {
         T::fmt_diagnostic(*self, f)
     }
@@ -27,59 +62,1657 @@ impl<T: DisplayDiagnostic> DisplayDiagnostic for Box<T> {
 
 impl DisplayDiagnostic for CborValue {
     fn fmt_diagnostic(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(&Edn::from(self), f)
+    }
+}
+
+impl DisplayDiagnostic for Edn {
+    fn fmt_diagnostic(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+/// CBOR data item, with its encoding details.
+///
+/// Encoding indicators (`Option<u8>`) give the size of the argument of an
+/// item (or of a floating-point number): `Some(n)` means the argument is
+/// encoded on `2^n` bytes (additional information `24 + n`), printed `_n`.
+/// `None` stands for the preferred (shortest) encoding, and indicators
+/// matching the preferred encoding are normalized to `None` when parsing.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Edn {
+    /// Unsigned or negative integer, between `-2^64` and `2^64 - 1`.
+    Integer(i128, Option<u8>),
+
+    /// Byte string.
+    Bytes(Chunks<Vec<u8>>),
+
+    /// Text string.
+    Text(Chunks<String>),
+
+    /// Array.
+    Array(Vec<Edn>, Length),
+
+    /// Map.
+    Map(Vec<(Edn, Edn)>, Length),
+
+    /// Tagged item.
+    Tag(u64, Option<u8>, Box<Edn>),
+
+    /// Simple value: `false` (20), `true` (21), `null` (22), `undefined` (23)
+    /// or `simple(n)`.
+    Simple(u8),
+
+    /// Floating-point number. The encoding indicator is `1` for half, `2`
+    /// for single and `3` for double precision.
+    Float(f64, Option<u8>),
+}
+
+/// Array or map length encoding.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Length {
+    /// Definite length, with its encoding indicator.
+    Definite(Option<u8>),
+
+    /// Indefinite length, printed `[_ ...]` or `{_ ...}`.
+    Indefinite,
+}
+
+/// Byte or text string encoding.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Chunks<T> {
+    /// Definite-length string, with its encoding indicator.
+    Definite(T, Option<u8>),
+
+    /// Indefinite-length string made of definite-length chunks, printed
+    /// `(_ chunk, ...)`.
+    Indefinite(Vec<(T, Option<u8>)>),
+}
+
+impl<T> Chunks<T> {
+    /// Returns an iterator over the chunks of the string.
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        let (single, chunks) = match self {
+            Self::Definite(value, _) => (Some(value), &[][..]),
+            Self::Indefinite(chunks) => (None, chunks.as_slice()),
+        };
+
+        single
+            .into_iter()
+            .chain(chunks.iter().map(|(value, _)| value))
+    }
+
+    fn try_map<U, E>(self, f: impl Fn(T) -> Result<U, E>) -> Result<Chunks<U>, E> {
         match self {
-            Self::Null => write!(f, "null"),
-            Self::Bool(true) => write!(f, "true"),
-            Self::Bool(false) => write!(f, "false"),
-            Self::Integer(i) => {
-                let i: i128 = (*i).into();
-                write!(f, "{i}")
+            Self::Definite(value, indicator) => Ok(Chunks::Definite(f(value)?, indicator)),
+            Self::Indefinite(chunks) => chunks
+                .into_iter()
+                .map(|(value, indicator)| Ok((f(value)?, indicator)))
+                .collect::<Result<_, _>>()
+                .map(Chunks::Indefinite),
+        }
+    }
+}
+
+/// Invalid CBOR bytes.
+#[derive(Debug, thiserror::Error)]
+pub enum InvalidCbor {
+    #[error("unexpected end of input")]
+    UnexpectedEnd,
+
+    #[error("reserved additional information {0} at offset {1}")]
+    AdditionalInformation(u8, usize),
+
+    #[error("unexpected break at offset {0}")]
+    UnexpectedBreak(usize),
+
+    #[error("invalid indefinite-length string chunk at offset {0}")]
+    InvalidChunk(usize),
+
+    #[error("invalid UTF-8 text string at offset {0}")]
+    InvalidUtf8(usize),
+
+    #[error("trailing bytes at offset {0}")]
+    TrailingBytes(usize),
+}
+
+/// Data item that has no [`CborValue`] representation.
+#[derive(Debug, thiserror::Error)]
+pub enum UnsupportedValue {
+    #[error("integer {0} out of range")]
+    Integer(i128),
+
+    #[error("unsupported simple value {0}")]
+    Simple(u8),
+}
+
+/// Invalid diagnostic notation.
+#[derive(Debug, Clone, thiserror::Error)]
+#[error("{kind} at offset {offset}")]
+pub struct ParseError {
+    /// Byte offset of the error in the input text.
+    pub offset: usize,
+    pub kind: ParseErrorKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum ParseErrorKind {
+    #[error("unexpected end of input")]
+    UnexpectedEnd,
+
+    #[error("unexpected character `{0}`")]
+    Unexpected(char),
+
+    #[error("invalid number")]
+    InvalidNumber,
+
+    #[error("integer out of range")]
+    IntegerOutOfRange,
+
+    #[error("invalid encoding indicator")]
+    InvalidIndicator,
+
+    #[error("invalid escape sequence")]
+    InvalidEscape,
+
+    #[error("invalid hexadecimal byte string")]
+    InvalidHex,
+
+    #[error("invalid base64 byte string")]
+    InvalidBase64,
+
+    #[error("invalid indefinite-length string chunk")]
+    InvalidChunk,
+}
+
+impl Edn {
+    /// Reads a data item from CBOR bytes, keeping its encoding details.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, InvalidCbor> {
+        let mut reader = Reader { bytes, offset: 0 };
+        let item = reader.item()?;
+
+        if reader.offset < bytes.len() {
+            return Err(InvalidCbor::TrailingBytes(reader.offset));
+        }
+
+        Ok(item)
+    }
+
+    /// Encodes the data item into CBOR bytes, following its encoding
+    /// indicators and lengths.
+    ///
+    /// Encoding indicators too small for their argument are ignored.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        self.write(&mut bytes);
+        bytes
+    }
+
+    /// Converts the data item into a [`CborValue`], dropping its encoding
+    /// details.
+    pub fn to_value(&self) -> Result<CborValue, UnsupportedValue> {
+        Ok(match self {
+            Self::Integer(i, _) => {
+                CborValue::Integer((*i).try_into().map_err(|_| UnsupportedValue::Integer(*i))?)
+            }
+            Self::Bytes(chunks) => CborValue::Bytes(chunks.iter().flatten().copied().collect()),
+            Self::Text(chunks) => CborValue::Text(chunks.iter().map(String::as_str).collect()),
+            Self::Array(items, _) => {
+                CborValue::Array(items.iter().map(Self::to_value).collect::<Result<_, _>>()?)
+            }
+            Self::Map(entries, _) => CborValue::Map(
+                entries
+                    .iter()
+                    .map(|(key, value)| Ok((key.to_value()?, value.to_value()?)))
+                    .collect::<Result<_, _>>()?,
+            ),
+            Self::Tag(tag, _, value) => CborValue::Tag(*tag, Box::new(value.to_value()?)),
+            Self::Simple(20) => CborValue::Bool(false),
+            Self::Simple(21) => CborValue::Bool(true),
+            Self::Simple(22) => CborValue::Null,
+            Self::Simple(n) => return Err(UnsupportedValue::Simple(*n)),
+            Self::Float(value, _) => CborValue::Float(*value),
+        })
+    }
+
+    /// Size of the encoded data item, in bytes.
+    pub fn encoded_len(&self) -> usize {
+        self.to_bytes().len()
+    }
+
+    /// Pretty-prints the data item with the given annotations as comments.
+    pub fn annotated<'a>(&'a self, annotations: &'a Annotations) -> Annotated<'a> {
+        Annotated {
+            edn: self,
+            annotations,
+            offsets: false,
+        }
+    }
+
+    fn write(&self, bytes: &mut Vec<u8>) {
+        match self {
+            Self::Integer(i, indicator) => {
+                if *i >= 0 {
+                    write_head(bytes, 0, *i as u64, *indicator)
+                } else {
+                    write_head(bytes, 1, (-1 - *i) as u64, *indicator)
+                }
             }
-            Self::Float(v) => {
-                write!(f, "{v}")
+            Self::Bytes(chunks) => write_chunks(bytes, 2, chunks, Vec::as_slice),
+            Self::Text(chunks) => write_chunks(bytes, 3, chunks, String::as_bytes),
+            Self::Array(items, length) => {
+                write_length(bytes, 4, items.len(), *length);
+                for item in items {
+                    item.write(bytes)
+                }
+                if *length == Length::Indefinite {
+                    bytes.push(BREAK)
+                }
             }
-            Self::Text(s) => {
-                write!(f, "\"{s}\"")
+            Self::Map(entries, length) => {
+                write_length(bytes, 5, entries.len(), *length);
+                for (key, value) in entries {
+                    key.write(bytes);
+                    value.write(bytes)
+                }
+                if *length == Length::Indefinite {
+                    bytes.push(BREAK)
+                }
             }
-            Self::Tag(t, v) => {
-                write!(f, "{t}({})", Diagnostic(v))
+            Self::Tag(tag, indicator, value) => {
+                write_head(bytes, 6, *tag, *indicator);
+                value.write(bytes)
             }
-            Self::Array(array) => {
-                write!(f, "[")?;
+            Self::Simple(n @ 0..=23) => bytes.push(0xe0 | n),
+            Self::Simple(n) => bytes.extend([0xf8, *n]),
+            Self::Float(value, indicator) => {
+                match indicator.unwrap_or_else(|| float_indicator(*value)) {
+                    0 | 1 => {
+                        bytes.push(0xf9);
+                        bytes.extend(f16::from_f64(*value).to_be_bytes())
+                    }
+                    2 => {
+                        bytes.push(0xfa);
+                        bytes.extend((*value as f32).to_be_bytes())
+                    }
+                    _ => {
+                        bytes.push(0xfb);
+                        bytes.extend(value.to_be_bytes())
+                    }
+                }
+            }
+        }
+    }
+}
 
-                for (i, item) in array.iter().enumerate() {
-                    if i > 0 {
-                        write!(f, ",")?;
+impl From<&CborValue> for Edn {
+    fn from(value: &CborValue) -> Self {
+        match value {
+            CborValue::Integer(i) => Self::Integer((*i).into(), None),
+            CborValue::Bytes(bytes) => Self::Bytes(Chunks::Definite(bytes.clone(), None)),
+            CborValue::Float(value) => Self::Float(*value, None),
+            CborValue::Text(text) => Self::Text(Chunks::Definite(text.clone(), None)),
+            CborValue::Bool(false) => Self::Simple(20),
+            CborValue::Bool(true) => Self::Simple(21),
+            CborValue::Null => Self::Simple(22),
+            CborValue::Tag(tag, value) => Self::Tag(*tag, None, Box::new(value.as_ref().into())),
+            CborValue::Array(items) => Self::Array(
+                items.iter().map(Into::into).collect(),
+                Length::Definite(None),
+            ),
+            CborValue::Map(entries) => Self::Map(
+                entries
+                    .iter()
+                    .map(|(key, value)| (key.into(), value.into()))
+                    .collect(),
+                Length::Definite(None),
+            ),
+            // Variants added by future versions of `ciborium`.
+            _ => Self::Simple(23),
+        }
+    }
+}
+
+impl From<CborValue> for Edn {
+    fn from(value: CborValue) -> Self {
+        (&value).into()
+    }
+}
+
+const BREAK: u8 = 0xff;
+
+/// Preferred encoding indicator of an argument, `None` if it fits in the
+/// initial byte.
+fn argument_indicator(value: u64) -> Option<u8> {
+    match value {
+        0..=23 => None,
+        24..=0xff => Some(0),
+        0x100..=0xffff => Some(1),
+        0x1_0000..=0xffff_ffff => Some(2),
+        _ => Some(3),
+    }
+}
+
+/// Preferred encoding indicator of a floating-point number: the shortest
+/// lossless precision, as chosen by `ciborium`.
+fn float_indicator(value: f64) -> u8 {
+    if f64::from(f16::from_f64(value)).to_bits() == value.to_bits() {
+        1
+    } else if f64::from(value as f32).to_bits() == value.to_bits() {
+        2
+    } else {
+        3
+    }
+}
+
+fn write_head(bytes: &mut Vec<u8>, major: u8, value: u64, indicator: Option<u8>) {
+    let preferred = argument_indicator(value);
+    let indicator = match (indicator, preferred) {
+        (Some(n), Some(p)) => Some(n.clamp(p, 3)),
+        (Some(n), None) => Some(n.min(3)),
+        (None, p) => p,
+    };
+
+    match indicator {
+        None => bytes.push(major << 5 | value as u8),
+        Some(n) => {
+            bytes.push(major << 5 | (24 + n));
+            bytes.extend_from_slice(&value.to_be_bytes()[8 - (1 << n)..])
+        }
+    }
+}
+
+fn write_length(bytes: &mut Vec<u8>, major: u8, len: usize, length: Length) {
+    match length {
+        Length::Definite(indicator) => write_head(bytes, major, len as u64, indicator),
+        Length::Indefinite => bytes.push(major << 5 | 31),
+    }
+}
+
+fn write_chunks<T>(bytes: &mut Vec<u8>, major: u8, chunks: &Chunks<T>, f: impl Fn(&T) -> &[u8]) {
+    match chunks {
+        Chunks::Definite(value, indicator) => {
+            let value = f(value);
+            write_head(bytes, major, value.len() as u64, *indicator);
+            bytes.extend_from_slice(value)
+        }
+        Chunks::Indefinite(chunks) => {
+            bytes.push(major << 5 | 31);
+            for (value, indicator) in chunks {
+                let value = f(value);
+                write_head(bytes, major, value.len() as u64, *indicator);
+                bytes.extend_from_slice(value)
+            }
+            bytes.push(BREAK)
+        }
+    }
+}
+
+/// CBOR bytes reader.
+struct Reader<'a> {
+    bytes: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn byte(&mut self) -> Result<u8, InvalidCbor> {
+        let byte = *self
+            .bytes
+            .get(self.offset)
+            .ok_or(InvalidCbor::UnexpectedEnd)?;
+        self.offset += 1;
+        Ok(byte)
+    }
+
+    fn take(&mut self, len: u64) -> Result<&'a [u8], InvalidCbor> {
+        let end = usize::try_from(len)
+            .ok()
+            .and_then(|len| self.offset.checked_add(len))
+            .filter(|end| *end <= self.bytes.len())
+            .ok_or(InvalidCbor::UnexpectedEnd)?;
+        let bytes = &self.bytes[self.offset..end];
+        self.offset = end;
+        Ok(bytes)
+    }
+
+    /// Consumes a break byte, if any.
+    fn is_break(&mut self) -> bool {
+        let is_break = self.bytes.get(self.offset) == Some(&BREAK);
+        if is_break {
+            self.offset += 1
+        }
+
+        is_break
+    }
+
+    fn argument(&mut self, info: u8) -> Result<(u64, Option<u8>), InvalidCbor> {
+        match info {
+            0..=23 => Ok((info as u64, None)),
+            24..=27 => {
+                let n = info - 24;
+                let value = self
+                    .take(1 << n)?
+                    .iter()
+                    .fold(0, |value, byte| value << 8 | *byte as u64);
+                Ok((value, (argument_indicator(value) != Some(n)).then_some(n)))
+            }
+            _ => Err(InvalidCbor::AdditionalInformation(info, self.offset - 1)),
+        }
+    }
+
+    fn string(&mut self, major: u8, info: u8) -> Result<Chunks<&'a [u8]>, InvalidCbor> {
+        if info == 31 {
+            let mut chunks = Vec::new();
+            while !self.is_break() {
+                let start = self.offset;
+                let initial = self.byte()?;
+                if initial >> 5 != major || initial & 0x1f == 31 {
+                    return Err(InvalidCbor::InvalidChunk(start));
+                }
+
+                let (len, indicator) = self.argument(initial & 0x1f)?;
+                chunks.push((self.take(len)?, indicator))
+            }
+
+            Ok(Chunks::Indefinite(chunks))
+        } else {
+            let (len, indicator) = self.argument(info)?;
+            Ok(Chunks::Definite(self.take(len)?, indicator))
+        }
+    }
+
+    fn length(&mut self, info: u8) -> Result<Option<(u64, Option<u8>)>, InvalidCbor> {
+        if info == 31 {
+            Ok(None)
+        } else {
+            self.argument(info).map(Some)
+        }
+    }
+
+    fn item(&mut self) -> Result<Edn, InvalidCbor> {
+        let start = self.offset;
+        let initial = self.byte()?;
+        let (major, info) = (initial >> 5, initial & 0x1f);
+
+        match major {
+            0 => {
+                let (value, indicator) = self.argument(info)?;
+                Ok(Edn::Integer(value as i128, indicator))
+            }
+            1 => {
+                let (value, indicator) = self.argument(info)?;
+                Ok(Edn::Integer(-1 - value as i128, indicator))
+            }
+            2 => Ok(Edn::Bytes(
+                self.string(major, info)?
+                    .try_map(|bytes| Ok(bytes.to_vec()))?,
+            )),
+            3 => Ok(Edn::Text(self.string(major, info)?.try_map(|bytes| {
+                std::str::from_utf8(bytes)
+                    .map(ToOwned::to_owned)
+                    .map_err(|_| InvalidCbor::InvalidUtf8(start))
+            })?)),
+            4 => {
+                let mut items = Vec::new();
+                match self.length(info)? {
+                    Some((len, indicator)) => {
+                        for _ in 0..len {
+                            items.push(self.item()?)
+                        }
+                        Ok(Edn::Array(items, Length::Definite(indicator)))
                     }
+                    None => {
+                        while !self.is_break() {
+                            items.push(self.item()?)
+                        }
+                        Ok(Edn::Array(items, Length::Indefinite))
+                    }
+                }
+            }
+            5 => {
+                let mut entries = Vec::new();
+                match self.length(info)? {
+                    Some((len, indicator)) => {
+                        for _ in 0..len {
+                            entries.push((self.item()?, self.item()?))
+                        }
+                        Ok(Edn::Map(entries, Length::Definite(indicator)))
+                    }
+                    None => {
+                        while !self.is_break() {
+                            entries.push((self.item()?, self.item()?))
+                        }
+                        Ok(Edn::Map(entries, Length::Indefinite))
+                    }
+                }
+            }
+            6 => {
+                let (tag, indicator) = self.argument(info)?;
+                Ok(Edn::Tag(tag, indicator, Box::new(self.item()?)))
+            }
+            _ => match info {
+                0..=23 => Ok(Edn::Simple(info)),
+                24 => Ok(Edn::Simple(self.byte()?)),
+                25..=27 => {
+                    let n = info - 24;
+                    let bytes = self.take(1 << n)?;
+                    let value = match bytes.len() {
+                        2 => f16::from_be_bytes([bytes[0], bytes[1]]).into(),
+                        4 => f32::from_be_bytes(bytes.try_into().unwrap()).into(),
+                        _ => f64::from_be_bytes(bytes.try_into().unwrap()),
+                    };
+                    Ok(Edn::Float(
+                        value,
+                        (float_indicator(value) != n).then_some(n),
+                    ))
+                }
+                31 => Err(InvalidCbor::UnexpectedBreak(start)),
+                _ => Err(InvalidCbor::AdditionalInformation(info, start)),
+            },
+        }
+    }
+}
+
+impl fmt::Display for Edn {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let pretty = f.alternate();
+        Printer::new(f, pretty, None).item(self, true)
+    }
+}
 
-                    item.fmt_diagnostic(f)?
+/// Data item pretty-printed with annotations.
+///
+/// See [`Edn::annotated`].
+pub struct Annotated<'a> {
+    edn: &'a Edn,
+    annotations: &'a Annotations,
+    offsets: bool,
+}
+
+impl Annotated<'_> {
+    /// Also shows the byte offset and length of the data item, and of each
+    /// map entry and array item.
+    pub fn with_offsets(mut self) -> Self {
+        self.offsets = true;
+        self
+    }
+}
+
+impl fmt::Display for Annotated<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut printer = Printer::new(f, true, Some(self.annotations));
+        printer.offsets = self.offsets;
+        printer.locate(self.edn.encoded_len());
+        printer.item(self.edn, true)?;
+        printer.flush_comment()
+    }
+}
+
+/// Diagnostic notation printer.
+struct Printer<'a, 'f> {
+    f: &'a mut fmt::Formatter<'f>,
+    pretty: bool,
+    annotations: Option<&'a Annotations>,
+    offsets: bool,
+    depth: usize,
+
+    /// Path of the current data item, used to find its annotations.
+    path: Vec<PathSegment>,
+
+    /// Byte offset of the current data item.
+    offset: usize,
+
+    /// Location shown at the end of the current line.
+    location: Option<(usize, usize)>,
+
+    /// Comments shown at the end of the current line.
+    comments: Vec<String>,
+}
+
+impl<'a, 'f> Printer<'a, 'f> {
+    fn new(
+        f: &'a mut fmt::Formatter<'f>,
+        pretty: bool,
+        annotations: Option<&'a Annotations>,
+    ) -> Self {
+        Self {
+            f,
+            pretty,
+            annotations,
+            offsets: false,
+            depth: 0,
+            path: Vec::new(),
+            offset: 0,
+            location: None,
+            comments: Vec::new(),
+        }
+    }
+
+    /// Shows the location of the data item (or map entry) starting at the
+    /// current offset, if enabled.
+    fn locate(&mut self, len: usize) {
+        if self.offsets {
+            self.location = Some((self.offset, len))
+        }
+    }
+
+    fn annotate(&mut self, comment: impl ToString) {
+        self.comments.push(comment.to_string())
+    }
+
+    fn flush_comment(&mut self) -> fmt::Result {
+        let comments = std::mem::take(&mut self.comments).join(", ");
+        let location = self.location.take().map(|(offset, len)| {
+            let s = if len == 1 { "" } else { "s" };
+            format!("at {offset}, {len} byte{s}")
+        });
+
+        match (location, comments.is_empty()) {
+            (Some(location), true) => write!(self.f, " # {location}"),
+            (Some(location), false) => write!(self.f, " # {location}: {comments}"),
+            (None, false) => write!(self.f, " # {comments}"),
+            (None, true) => Ok(()),
+        }
+    }
+
+    fn newline(&mut self) -> fmt::Result {
+        self.flush_comment()?;
+        writeln!(self.f)?;
+        write!(self.f, "{:1$}", "", self.depth * 2)
+    }
+
+    fn indicator(&mut self, indicator: Option<u8>) -> fmt::Result {
+        match indicator {
+            Some(n) => write!(self.f, "_{n}"),
+            None => Ok(()),
+        }
+    }
+
+    /// Annotates the current data item with the codec or table that handled
+    /// it, or the term or context IRI its ID stands for.
+    fn annotate_value(&mut self, item: &Edn) {
+        let Some(annotations) = self.annotations else {
+            return;
+        };
+
+        let Some(handler) = annotations.handlers.get(&json_pointer(&self.path)) else {
+            return;
+        };
+
+        let id = match item {
+            Edn::Integer(i, _) => u64::try_from(*i).ok(),
+            _ => None,
+        };
+
+        match handler {
+            Handler::Context => {
+                if let Some(iri_ref) = id.and_then(|id| annotations.contexts.get(&id)) {
+                    self.annotate(iri_ref)
+                }
+            }
+            Handler::Vocab => {
+                if let Some(term) = id.and_then(|id| annotations.terms.get(&id)) {
+                    self.annotate(term)
+                }
+            }
+            Handler::Iri(None) | Handler::Plain => (),
+            handler => {
+                self.annotate(handler);
+                if handler.is_term_codec() {
+                    if let Some(term) = id.and_then(|id| annotations.terms.get(&id)) {
+                        self.annotate(term)
+                    }
                 }
+            }
+        }
+    }
 
-                write!(f, "]")
+    /// Writes the items of an array or map, between the given delimiters.
+    fn sequence<T>(
+        &mut self,
+        (open, close): (char, char),
+        length: Length,
+        items: &[T],
+        mut item: impl FnMut(&mut Self, usize, &T) -> fmt::Result,
+    ) -> fmt::Result {
+        write!(self.f, "{open}")?;
+        match length {
+            Length::Definite(None) => (),
+            Length::Definite(Some(n)) => write!(self.f, "_{n} ")?,
+            Length::Indefinite => write!(self.f, "_ ")?,
+        }
+
+        if self.pretty && !items.is_empty() {
+            self.depth += 1;
+            for (i, value) in items.iter().enumerate() {
+                if i > 0 {
+                    write!(self.f, ",")?
+                }
+                self.newline()?;
+                item(self, i, value)?
             }
-            Self::Map(entries) => {
-                write!(f, "[")?;
+            self.depth -= 1;
+            self.newline()?
+        } else {
+            for (i, value) in items.iter().enumerate() {
+                if i > 0 {
+                    write!(self.f, ", ")?
+                }
+                item(self, i, value)?
+            }
+        }
+
+        if length == Length::Indefinite {
+            self.offset += 1
+        }
+
+        write!(self.f, "{close}")
+    }
 
-                for (i, (key, value)) in entries.iter().enumerate() {
+    fn chunks<T>(
+        &mut self,
+        chunks: &Chunks<T>,
+        empty: &str,
+        write: impl Fn(&mut fmt::Formatter, &T) -> fmt::Result,
+    ) -> fmt::Result {
+        match chunks {
+            Chunks::Definite(value, indicator) => {
+                write(self.f, value)?;
+                self.indicator(*indicator)
+            }
+            Chunks::Indefinite(chunks) if chunks.is_empty() => write!(self.f, "{empty}_"),
+            Chunks::Indefinite(chunks) => {
+                write!(self.f, "(_ ")?;
+                for (i, (value, indicator)) in chunks.iter().enumerate() {
                     if i > 0 {
-                        write!(f, ",")?;
+                        write!(self.f, ", ")?
                     }
-
-                    write!(f, "{}=>{}", Diagnostic(key), Diagnostic(value))?
+                    write(self.f, value)?;
+                    self.indicator(*indicator)?
                 }
+                write!(self.f, ")")
+            }
+        }
+    }
+
+    /// Writes a data item, annotating it if `annotate` is set.
+    fn item(&mut self, item: &Edn, annotate: bool) -> fmt::Result {
+        if annotate {
+            self.annotate_value(item)
+        }
+
+        match item {
+            Edn::Array(items, length) => {
+                let mut head = Vec::new();
+                write_length(&mut head, 4, items.len(), *length);
+                self.offset += head.len();
+
+                self.sequence(('[', ']'), *length, items, |p, i, item| {
+                    p.locate(item.encoded_len());
+                    p.path.push(PathSegment::Index(i));
+                    let result = p.item(item, true);
+                    p.path.pop();
+                    result
+                })
+            }
+            Edn::Map(entries, length) => {
+                let mut head = Vec::new();
+                write_length(&mut head, 5, entries.len(), *length);
+                self.offset += head.len();
+
+                self.sequence(('{', '}'), *length, entries, |p, _, (key, value)| {
+                    p.locate(key.encoded_len() + value.encoded_len());
+                    p.item(key, false)?;
+                    write!(p.f, ": ")?;
+
+                    let term = match (key, p.annotations) {
+                        (Edn::Integer(id, _), Some(annotations)) => u64::try_from(*id)
+                            .ok()
+                            .and_then(|id| annotations.terms.get(&id)),
+                        _ => None,
+                    };
 
-                write!(f, "]")
+                    if let Some(term) = term {
+                        p.annotate(term)
+                    }
+
+                    let segment = match key.to_value() {
+                        Ok(key) => Diagnostic(key).to_string(),
+                        Err(_) => key.to_string(),
+                    };
+
+                    p.path.push(PathSegment::Key(segment, None));
+                    let result = p.item(value, true);
+                    p.path.pop();
+                    result
+                })
             }
-            Self::Bytes(bytes) => {
+            Edn::Tag(tag, indicator, value) => {
+                let mut head = Vec::new();
+                write_head(&mut head, 6, *tag, *indicator);
+                self.offset += head.len();
+
+                write!(self.f, "{tag}")?;
+                self.indicator(*indicator)?;
+                write!(self.f, "(")?;
+                self.item(value, false)?;
+                write!(self.f, ")")
+            }
+            leaf => {
+                self.offset += leaf.encoded_len();
+                self.leaf(leaf)
+            }
+        }
+    }
+
+    fn leaf(&mut self, item: &Edn) -> fmt::Result {
+        match item {
+            Edn::Integer(i, indicator) => {
+                write!(self.f, "{i}")?;
+                self.indicator(*indicator)
+            }
+            Edn::Bytes(chunks) => self.chunks(chunks, "''", |f, bytes| {
                 write!(f, "h'")?;
+                for byte in bytes {
+                    write!(f, "{byte:02x}")?
+                }
+                write!(f, "'")
+            }),
+            Edn::Text(chunks) => self.chunks(chunks, "\"\"", |f, text| write_text(f, text)),
+            Edn::Simple(20) => write!(self.f, "false"),
+            Edn::Simple(21) => write!(self.f, "true"),
+            Edn::Simple(22) => write!(self.f, "null"),
+            Edn::Simple(23) => write!(self.f, "undefined"),
+            Edn::Simple(n) => write!(self.f, "simple({n})"),
+            Edn::Float(value, indicator) => {
+                if value.is_nan() {
+                    write!(self.f, "NaN")?
+                } else if value.is_infinite() {
+                    let sign = if *value < 0.0 { "-" } else { "" };
+                    write!(self.f, "{sign}Infinity")?
+                } else {
+                    write!(self.f, "{value:?}")?
+                }
+
+                self.indicator(*indicator)
+            }
+            Edn::Array(..) | Edn::Map(..) | Edn::Tag(..) => unreachable!(),
+        }
+    }
+}
+
+fn write_text(f: &mut fmt::Formatter, text: &str) -> fmt::Result {
+    write!(f, "\"")?;
+
+    for c in text.chars() {
+        match c {
+            '"' => write!(f, "\\\"")?,
+            '\\' => write!(f, "\\\\")?,
+            '\n' => write!(f, "\\n")?,
+            '\r' => write!(f, "\\r")?,
+            '\t' => write!(f, "\\t")?,
+            c if c.is_control() => write!(f, "\\u{:04x}", c as u32)?,
+            c => write!(f, "{c}")?,
+        }
+    }
+
+    write!(f, "\"")
+}
+
+impl FromStr for Edn {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parser = Parser {
+            input: s,
+            offset: 0,
+        };
+
+        let item = parser.item()?;
+        parser.skip_whitespace()?;
+        match parser.peek() {
+            Some(c) => Err(parser.error(ParseErrorKind::Unexpected(c))),
+            None => Ok(item),
+        }
+    }
+}
+
+/// Diagnostic notation parser.
+struct Parser<'a> {
+    input: &'a str,
+    offset: usize,
+}
+
+impl Parser<'_> {
+    fn error(&self, kind: ParseErrorKind) -> ParseError {
+        ParseError {
+            offset: self.offset,
+            kind,
+        }
+    }
+
+    fn rest(&self) -> &str {
+        &self.input[self.offset..]
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.rest().chars().next()
+    }
 
-                for b in bytes {
-                    write!(f, "{b:#}")?;
+    fn next(&mut self) -> Result<char, ParseError> {
+        let c = self
+            .peek()
+            .ok_or_else(|| self.error(ParseErrorKind::UnexpectedEnd))?;
+        self.offset += c.len_utf8();
+        Ok(c)
+    }
+
+    /// Consumes the given prefix, if present.
+    fn eat(&mut self, prefix: &str) -> bool {
+        let present = self.rest().starts_with(prefix);
+        if present {
+            self.offset += prefix.len()
+        }
+
+        present
+    }
+
+    fn skip_whitespace(&mut self) -> Result<(), ParseError> {
+        loop {
+            match self.peek() {
+                Some(c) if c.is_whitespace() => self.offset += c.len_utf8(),
+                Some('/') => {
+                    self.offset += 1;
+                    while self.next()? != '/' {}
                 }
+                Some('#') => {
+                    while !matches!(self.peek(), None | Some('\n')) {
+                        self.next()?;
+                    }
+                }
+                _ => break Ok(()),
+            }
+        }
+    }
 
-                write!(f, "'")
+    fn expect(&mut self, expected: char) -> Result<(), ParseError> {
+        self.skip_whitespace()?;
+        let start = self.offset;
+        match self.next()? {
+            c if c == expected => Ok(()),
+            c => Err(ParseError {
+                offset: start,
+                kind: ParseErrorKind::Unexpected(c),
+            }),
+        }
+    }
+
+    /// Parses an encoding indicator (`_n`), if any.
+    fn indicator(&mut self) -> Result<Option<u8>, ParseError> {
+        let mut chars = self.rest().chars();
+        match (chars.next(), chars.next()) {
+            (Some('_'), Some(d)) if d.is_ascii_digit() => {
+                self.offset += 2;
+                match d.to_digit(10) {
+                    Some(n @ 0..=3) => Ok(Some(n as u8)),
+                    _ => Err(self.error(ParseErrorKind::InvalidIndicator)),
+                }
             }
-            _ => panic!(),
+            _ => Ok(None),
         }
     }
+
+    fn length(&mut self) -> Result<Length, ParseError> {
+        match self.indicator()? {
+            Some(n) => Ok(Length::Definite(Some(n))),
+            None if self.eat("_") => Ok(Length::Indefinite),
+            None => Ok(Length::Definite(None)),
+        }
+    }
+
+    /// Parses comma-separated items up to the given closing delimiter.
+    fn sequence<T>(
+        &mut self,
+        close: char,
+        mut item: impl FnMut(&mut Self) -> Result<T, ParseError>,
+    ) -> Result<Vec<T>, ParseError> {
+        let mut items = Vec::new();
+
+        self.skip_whitespace()?;
+        if self.eat(&close.to_string()) {
+            return Ok(items);
+        }
+
+        loop {
+            items.push(item(self)?);
+            self.skip_whitespace()?;
+            let start = self.offset;
+            match self.next()? {
+                ',' => (),
+                c if c == close => break Ok(items),
+                c => {
+                    break Err(ParseError {
+                        offset: start,
+                        kind: ParseErrorKind::Unexpected(c),
+                    })
+                }
+            }
+        }
+    }
+
+    fn item(&mut self) -> Result<Edn, ParseError> {
+        self.skip_whitespace()?;
+
+        match self.peek() {
+            None => Err(self.error(ParseErrorKind::UnexpectedEnd)),
+            Some('[') => {
+                self.offset += 1;
+                let length = self.length()?;
+                let items = self.sequence(']', Self::item)?;
+                let length = normalized_length(length, items.len());
+                Ok(Edn::Array(items, length))
+            }
+            Some('{') => {
+                self.offset += 1;
+                let length = self.length()?;
+                let entries = self.sequence('}', |p| {
+                    let key = p.item()?;
+                    p.expect(':')?;
+                    Ok((key, p.item()?))
+                })?;
+                let length = normalized_length(length, entries.len());
+                Ok(Edn::Map(entries, length))
+            }
+            Some('(') => {
+                self.offset += 1;
+                let start = self.offset;
+                if !self.eat("_") {
+                    return Err(self.error(ParseErrorKind::InvalidChunk));
+                }
+
+                let chunks = self.sequence(')', Self::item)?;
+                let chunk_error = ParseError {
+                    offset: start,
+                    kind: ParseErrorKind::InvalidChunk,
+                };
+
+                match chunks.first() {
+                    Some(Edn::Text(_)) => chunks
+                        .into_iter()
+                        .map(|chunk| match chunk {
+                            Edn::Text(Chunks::Definite(text, indicator)) => Ok((text, indicator)),
+                            _ => Err(chunk_error.clone()),
+                        })
+                        .collect::<Result<_, _>>()
+                        .map(|chunks| Edn::Text(Chunks::Indefinite(chunks))),
+                    Some(Edn::Bytes(_)) => chunks
+                        .into_iter()
+                        .map(|chunk| match chunk {
+                            Edn::Bytes(Chunks::Definite(bytes, indicator)) => {
+                                Ok((bytes, indicator))
+                            }
+                            _ => Err(chunk_error.clone()),
+                        })
+                        .collect::<Result<_, _>>()
+                        .map(|chunks| Edn::Bytes(Chunks::Indefinite(chunks))),
+                    _ => Err(chunk_error),
+                }
+            }
+            Some('"') => {
+                self.offset += 1;
+                let text = self.quoted('"')?;
+                self.string_suffix(text).map(Edn::Text)
+            }
+            Some('\'') => {
+                self.offset += 1;
+                let text = self.quoted('\'')?;
+                self.string_suffix(text.into_bytes()).map(Edn::Bytes)
+            }
+            Some('h') if self.eat("h'") => {
+                let bytes = self.hex()?;
+                self.string_suffix(bytes).map(Edn::Bytes)
+            }
+            Some('b') if self.eat("b64'") => {
+                let bytes = self.base64()?;
+                self.string_suffix(bytes).map(Edn::Bytes)
+            }
+            Some(_) if self.eat("true") => Ok(Edn::Simple(21)),
+            Some(_) if self.eat("false") => Ok(Edn::Simple(20)),
+            Some(_) if self.eat("null") => Ok(Edn::Simple(22)),
+            Some(_) if self.eat("undefined") => Ok(Edn::Simple(23)),
+            Some(_) if self.eat("simple(") => {
+                let start = self.offset;
+                let n = match self.number()? {
+                    Number::Integer(n) => u8::try_from(n).ok(),
+                    Number::Float(_) => None,
+                };
+                let n = n.ok_or(ParseError {
+                    offset: start,
+                    kind: ParseErrorKind::InvalidNumber,
+                })?;
+                self.expect(')')?;
+                Ok(Edn::Simple(n))
+            }
+            Some(_) => {
+                let start = self.offset;
+                match self.number()? {
+                    Number::Integer(i) => {
+                        let indicator = self.indicator()?;
+                        if self.eat("(") {
+                            let tag = u64::try_from(i).map_err(|_| ParseError {
+                                offset: start,
+                                kind: ParseErrorKind::IntegerOutOfRange,
+                            })?;
+                            let value = self.item()?;
+                            self.expect(')')?;
+                            let indicator = self.argument_indicator(tag, indicator)?;
+                            Ok(Edn::Tag(tag, indicator, Box::new(value)))
+                        } else {
+                            let argument = if i >= 0 { i } else { -1 - i };
+                            let argument = u64::try_from(argument).map_err(|_| ParseError {
+                                offset: start,
+                                kind: ParseErrorKind::IntegerOutOfRange,
+                            })?;
+                            let indicator = self.argument_indicator(argument, indicator)?;
+                            Ok(Edn::Integer(i, indicator))
+                        }
+                    }
+                    Number::Float(value) => match self.indicator()? {
+                        Some(0) => Err(self.error(ParseErrorKind::InvalidIndicator)),
+                        indicator => Ok(Edn::Float(
+                            value,
+                            indicator.filter(|n| *n != float_indicator(value)),
+                        )),
+                    },
+                }
+            }
+        }
+    }
+
+    /// Checks that the encoding indicator fits the argument, and normalizes
+    /// it.
+    fn argument_indicator(
+        &self,
+        argument: u64,
+        indicator: Option<u8>,
+    ) -> Result<Option<u8>, ParseError> {
+        let preferred = argument_indicator(argument);
+        match indicator {
+            Some(n) if preferred.is_some_and(|p| n < p) => {
+                Err(self.error(ParseErrorKind::InvalidIndicator))
+            }
+            indicator => Ok(indicator.filter(|n| Some(*n) != preferred)),
+        }
+    }
+
+    /// Parses the encoding indicator following a string literal, or the `_`
+    /// of an empty indefinite-length string (`""_` or `''_`).
+    fn string_suffix<T: AsRef<[u8]>>(&mut self, value: T) -> Result<Chunks<T>, ParseError> {
+        match self.indicator()? {
+            Some(n) => {
+                let len = value.as_ref().len() as u64;
+                let indicator = self.argument_indicator(len, Some(n))?;
+                Ok(Chunks::Definite(value, indicator))
+            }
+            None if self.rest().starts_with('_') => {
+                if !value.as_ref().is_empty() {
+                    return Err(self.error(ParseErrorKind::Unexpected('_')));
+                }
+
+                self.offset += 1;
+                Ok(Chunks::Indefinite(Vec::new()))
+            }
+            None => Ok(Chunks::Definite(value, None)),
+        }
+    }
+
+    /// Parses a quoted string, after its opening quote.
+    fn quoted(&mut self, quote: char) -> Result<String, ParseError> {
+        let mut text = String::new();
+
+        loop {
+            match self.next()? {
+                c if c == quote => break Ok(text),
+                '\\' => {
+                    let c = match self.next()? {
+                        'b' => '\u{8}',
+                        'f' => '\u{c}',
+                        'n' => '\n',
+                        'r' => '\r',
+                        't' => '\t',
+                        'u' => self.unicode_escape()?,
+                        c @ ('"' | '\'' | '\\' | '/') => c,
+                        _ => return Err(self.error(ParseErrorKind::InvalidEscape)),
+                    };
+                    text.push(c)
+                }
+                c => text.push(c),
+            }
+        }
+    }
+
+    /// Parses the hexadecimal digits of a `\uXXXX` escape sequence, and of
+    /// the low surrogate that may follow.
+    fn unicode_escape(&mut self) -> Result<char, ParseError> {
+        let high = self.hex4()?;
+        let code = if (0xd800..0xdc00).contains(&high) {
+            if !self.eat("\\u") {
+                return Err(self.error(ParseErrorKind::InvalidEscape));
+            }
+
+            let low = self.hex4()?;
+            if !(0xdc00..0xe000).contains(&low) {
+                return Err(self.error(ParseErrorKind::InvalidEscape));
+            }
+
+            0x10000 + ((high - 0xd800) << 10) + (low - 0xdc00)
+        } else {
+            high
+        };
+
+        char::from_u32(code).ok_or_else(|| self.error(ParseErrorKind::InvalidEscape))
+    }
+
+    fn hex4(&mut self) -> Result<u32, ParseError> {
+        let digits = self
+            .rest()
+            .get(..4)
+            .filter(|digits| digits.chars().all(|c| c.is_ascii_hexdigit()))
+            .ok_or_else(|| self.error(ParseErrorKind::InvalidEscape))?;
+        let code = u32::from_str_radix(digits, 16).unwrap();
+        self.offset += 4;
+        Ok(code)
+    }
+
+    /// Returns the content of a prefixed byte string, without whitespace.
+    fn prefixed(&mut self) -> Result<String, ParseError> {
+        let mut content = String::new();
+
+        loop {
+            match self.next()? {
+                '\'' => break Ok(content),
+                c if c.is_whitespace() => (),
+                c => content.push(c),
+            }
+        }
+    }
+
+    fn hex(&mut self) -> Result<Vec<u8>, ParseError> {
+        let start = self.offset;
+        let digits = self.prefixed()?;
+        let error = ParseError {
+            offset: start,
+            kind: ParseErrorKind::InvalidHex,
+        };
+
+        if digits.len() % 2 != 0 {
+            return Err(error);
+        }
+
+        (0..digits.len())
+            .step_by(2)
+            .map(|i| {
+                digits
+                    .get(i..i + 2)
+                    .and_then(|byte| u8::from_str_radix(byte, 16).ok())
+                    .ok_or(error.clone())
+            })
+            .collect()
+    }
+
+    fn base64(&mut self) -> Result<Vec<u8>, ParseError> {
+        let start = self.offset;
+        let content = self.prefixed()?;
+        let content = content.trim_end_matches('=');
+        let base = if content.contains(['-', '_']) {
+            multibase::Base::Base64Url
+        } else {
+            multibase::Base::Base64
+        };
+
+        base.decode(content).map_err(|_| ParseError {
+            offset: start,
+            kind: ParseErrorKind::InvalidBase64,
+        })
+    }
+
+    fn number(&mut self) -> Result<Number, ParseError> {
+        let start = self.offset;
+        let error = |kind| ParseError {
+            offset: start,
+            kind,
+        };
+
+        if self.eat("NaN") {
+            return Ok(Number::Float(f64::NAN));
+        }
+
+        let negative = self.eat("-");
+        if self.eat("Infinity") {
+            let value = if negative {
+                f64::NEG_INFINITY
+            } else {
+                f64::INFINITY
+            };
+            return Ok(Number::Float(value));
+        }
+
+        let radix = if self.eat("0x") || self.eat("0X") {
+            16
+        } else if self.eat("0o") {
+            8
+        } else if self.eat("0b") {
+            2
+        } else {
+            10
+        };
+
+        let digits_start = self.offset;
+        let mut float = false;
+        while let Some(c) = self.peek() {
+            match c {
+                c if c.is_digit(radix) => (),
+                '.' if radix == 10 && !float => float = true,
+                'e' | 'E' if radix == 10 => {
+                    float = true;
+                    if matches!(self.rest()[1..].chars().next(), Some('+' | '-')) {
+                        self.offset += 1
+                    }
+                }
+                _ => break,
+            }
+            self.offset += 1
+        }
+
+        let digits = &self.input[digits_start..self.offset];
+        if digits.is_empty() {
+            return Err(match self.peek() {
+                Some(c) => error(ParseErrorKind::Unexpected(c)),
+                None => error(ParseErrorKind::UnexpectedEnd),
+            });
+        }
+
+        if float {
+            let value: f64 = self.input[start..self.offset]
+                .parse()
+                .map_err(|_| error(ParseErrorKind::InvalidNumber))?;
+            Ok(Number::Float(value))
+        } else {
+            let magnitude = u128::from_str_radix(digits, radix)
+                .map_err(|_| error(ParseErrorKind::IntegerOutOfRange))?;
+            let value = if negative {
+                i128::try_from(magnitude).map(|m| -m)
+            } else {
+                i128::try_from(magnitude)
+            }
+            .ok()
+            .filter(|i| (-(1i128 << 64)..(1i128 << 64)).contains(i))
+            .ok_or_else(|| error(ParseErrorKind::IntegerOutOfRange))?;
+            Ok(Number::Integer(value))
+        }
+    }
+}
+
+enum Number {
+    Integer(i128),
+    Float(f64),
+}
+
+fn normalized_length(length: Length, len: usize) -> Length {
+    match length {
+        Length::Definite(indicator) => {
+            Length::Definite(indicator.filter(|n| Some(*n) != argument_indicator(len as u64)))
+        }
+        Length::Indefinite => Length::Indefinite,
+    }
+}
+
+/// Names shown as comments next to the integer keys and values of a
+/// CBOR-LD document, along with the codec or table that handled each value.
+#[derive(Debug, Default, Clone)]
+pub struct Annotations {
+    /// Terms, by ID.
+    pub terms: HashMap<u64, String>,
+
+    /// Context IRI references, by ID.
+    pub contexts: HashMap<u64, String>,
+
+    /// Codec or table that handled each value, by JSON pointer into the
+    /// document body, whose keys are written in diagnostic notation.
+    pub handlers: HashMap<String, Handler>,
+}
+
+impl Annotations {
+    fn collect(
+        &mut self,
+        state: &TransformerState,
+        value: &CborValue,
+        path: &mut Vec<PathSegment>,
+    ) {
+        match value {
+            CborValue::Integer(id) => {
+                let Ok(id) = u64::try_from(*id) else {
+                    return;
+                };
+
+                match self.handlers.get(&json_pointer(path)) {
+                    Some(Handler::Context) => {
+                        if let Some(iri_ref) = state.context_iri_ref(id) {
+                            self.contexts.insert(id, iri_ref.as_str().to_owned());
+                        }
+                    }
+                    Some(handler) if *handler == Handler::Vocab || handler.is_term_codec() => {
+                        if let Some((term, _)) = state.allocator.decode_term(id) {
+                            self.terms.insert(id, term.to_owned());
+                        }
+                    }
+                    _ => (),
+                }
+            }
+            CborValue::Array(items) => {
+                for (i, item) in items.iter().enumerate() {
+                    path.push(PathSegment::Index(i));
+                    self.collect(state, item, path);
+                    path.pop();
+                }
+            }
+            CborValue::Map(entries) => {
+                for (key, value) in entries {
+                    if let CborValue::Integer(id) = key {
+                        if let Ok(id) = u64::try_from(*id) {
+                            if let Some((term, _)) = state.allocator.decode_term(id) {
+                                self.terms.insert(id, term.to_owned());
+                            }
+                        }
+                    }
+
+                    path.push(PathSegment::Key(Diagnostic(key).to_string(), None));
+                    self.collect(state, value, path);
+                    path.pop();
+                }
+            }
+            CborValue::Tag(_, value) => self.collect(state, value, path),
+            _ => (),
+        }
+    }
+}
+
+/// Decodes a CBOR-LD document using the given JSON-LD context loader and
+/// options, and returns the names of its term and context IDs along with the
+/// codec or table that handled each value.
+///
+/// The annotations of documents with value references or a lossless side
+/// channel only cover the values outside of them.
+pub async fn annotations(
+    cbor_ld_document: &CborValue,
+    loader: impl json_ld::Loader,
+    options: DecodeOptions,
+) -> Result<Annotations, DecodeError> {
+    let (compression_mode, body) = untag_cbor_ld(cbor_ld_document)?;
+    let mut annotations = Annotations::default();
+
+    if let CompressionMode::Compressed(_) = compression_mode {
+        let pipeline = DecodePipeline::from(options);
+        let tables = pipeline.tables(compression_mode)?;
+        let mut decoder = pipeline.decoder(pipeline.loader(loader), tables);
+        decoder.state.recorder = Some(Recorder::default());
+        let _: JsonValue = decoder.decode_body(body).await?;
+
+        let recording = decoder.state.recorder.take().unwrap().into_inner();
+        annotations.handlers = recording.handlers;
+        annotations.collect(&decoder.state, body, &mut Vec::new())
+    }
+
+    Ok(annotations)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Chunks, Edn, Length, ParseErrorKind};
+    use crate::CborValue;
+
+    fn hex(bytes: &str) -> Vec<u8> {
+        (0..bytes.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&bytes[i..i + 2], 16).unwrap())
+            .collect()
+    }
+
+    /// RFC 8949 Appendix A examples.
+    #[test]
+    fn rfc_8949_examples() {
+        for (edn, bytes) in [
+            ("0", "00"),
+            ("23", "17"),
+            ("24", "1818"),
+            ("1000000000000", "1b000000e8d4a51000"),
+            ("18446744073709551615", "1bffffffffffffffff"),
+            ("-18446744073709551616", "3bffffffffffffffff"),
+            ("-1000", "3903e7"),
+            ("0.0", "f90000"),
+            ("-0.0", "f98000"),
+            ("1.5", "f93e00"),
+            ("100000.0", "fa47c35000"),
+            ("1.1", "fb3ff199999999999a"),
+            ("5.960464477539063e-8", "f90001"),
+            ("-4.1", "fbc010666666666666"),
+            ("Infinity", "f97c00"),
+            ("NaN", "f97e00"),
+            ("-Infinity", "f9fc00"),
+            ("false", "f4"),
+            ("undefined", "f7"),
+            ("simple(16)", "f0"),
+            ("simple(255)", "f8ff"),
+            (
+                "0(\"2013-03-21T20:04:00Z\")",
+                "c074323031332d30332d32315432303a30343a30305a",
+            ),
+            ("23(h'01020304')", "d74401020304"),
+            ("h''", "40"),
+            ("\"ü\"", "62c3bc"),
+            ("\"\u{10151}\"", "64f0908591"),
+            ("[1, [2, 3], [4, 5]]", "8301820203820405"),
+            ("{\"a\": 1, \"b\": [2, 3]}", "a26161016162820203"),
+            ("(_ h'0102', h'030405')", "5f42010243030405ff"),
+            ("(_ \"strea\", \"ming\")", "7f657374726561646d696e67ff"),
+            ("[_ ]", "9fff"),
+            ("[_ 1, [2, 3], [_ 4, 5]]", "9f018202039f0405ffff"),
+            ("{_ \"a\": 1, \"b\": [_ 2, 3]}", "bf61610161629f0203ffff"),
+            ("1.5_3", "fb3ff8000000000000"),
+            ("0_1", "190000"),
+            ("[_0 1]", "980101"),
+            ("\"a\"_1", "79000161"),
+            ("1_0(0)", "d80100"),
+            ("''_", "5fff"),
+        ] {
+            let parsed: Edn = edn.parse().unwrap();
+            assert_eq!(parsed.to_bytes(), hex(bytes), "{edn}");
+
+            let decoded = Edn::from_bytes(&hex(bytes)).unwrap();
+            assert_eq!(decoded.to_string(), edn);
+        }
+    }
+
+    #[test]
+    fn cbor_value() {
+        let value = CborValue::Map(vec![
+            (
+                CborValue::Integer(100.into()),
+                CborValue::Array(vec![CborValue::Bool(true), CborValue::Null]),
+            ),
+            (
+                CborValue::Text("a\"b".to_owned()),
+                CborValue::Bytes(vec![0x0a, 0xff]),
+            ),
+            (
+                CborValue::Integer((-3).into()),
+                CborValue::Tag(1, Box::new(CborValue::Float(2.0))),
+            ),
+        ]);
+
+        let edn = Edn::from(&value);
+        assert_eq!(
+            edn.to_string(),
+            r#"{100: [true, null], "a\"b": h'0aff', -3: 1(2.0)}"#
+        );
+        assert_eq!(edn.to_string().parse::<Edn>().unwrap(), edn);
+        assert_eq!(edn.to_value().unwrap(), value);
+
+        let mut bytes = Vec::new();
+        ciborium::into_writer(&value, &mut bytes).unwrap();
+        assert_eq!(edn.to_bytes(), bytes);
+
+        let pretty = format!("{edn:#}");
+        assert_eq!(
+            pretty,
+            "{\n  100: [\n    true,\n    null\n  ],\n  \"a\\\"b\": h'0aff',\n  -3: 1(2.0)\n}"
+        );
+        assert_eq!(pretty.parse::<Edn>().unwrap(), edn);
+    }
+
+    #[test]
+    fn extensions() {
+        let edn: Edn = "[b64'AQID', 'hi', 0x1f, 0b11, /comment/ -0o10 # comment\n]"
+            .parse()
+            .unwrap();
+        assert_eq!(
+            edn,
+            Edn::Array(
+                vec![
+                    Edn::Bytes(Chunks::Definite(vec![1, 2, 3], None)),
+                    Edn::Bytes(Chunks::Definite(b"hi".to_vec(), None)),
+                    Edn::Integer(31, None),
+                    Edn::Integer(3, None),
+                    Edn::Integer(-8, None),
+                ],
+                Length::Definite(None)
+            )
+        );
+
+        let edn: Edn = r#""\u00fc\ud800\udd51\n""#.parse().unwrap();
+        assert_eq!(edn.to_string(), "\"\u{fc}\u{10151}\\n\"");
+    }
+
+    #[test]
+    fn errors() {
+        for (edn, offset, kind) in [
+            ("[1, 2", 5, ParseErrorKind::UnexpectedEnd),
+            ("{1 2}", 3, ParseErrorKind::Unexpected('2')),
+            ("1000_0", 6, ParseErrorKind::InvalidIndicator),
+            ("1.5_0", 5, ParseErrorKind::InvalidIndicator),
+            ("18446744073709551616", 0, ParseErrorKind::IntegerOutOfRange),
+            ("h'123'", 2, ParseErrorKind::InvalidHex),
+            ("(_ \"a\", h'00')", 1, ParseErrorKind::InvalidChunk),
+            ("\"\\x\"", 3, ParseErrorKind::InvalidEscape),
+            ("1 2", 2, ParseErrorKind::Unexpected('2')),
+        ] {
+            let error = edn.parse::<Edn>().unwrap_err();
+            assert_eq!((error.offset, error.kind), (offset, kind), "{edn}")
+        }
+
+        assert!(Edn::from_bytes(&hex("9f01")).is_err());
+        assert!(Edn::from_bytes(&hex("ff")).is_err());
+        assert!(Edn::from_bytes(&hex("5f6161ff")).is_err());
+        assert!(Edn::from_bytes(&hex("0000")).is_err());
+    }
 }
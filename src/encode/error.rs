@@ -1,17 +1,42 @@
 use crate::{
+    json::JsonKey,
     tables::UnknownCompressionTable,
-    transform::{DuplicateKey, ExpectedObject, InvalidTypeKind, MissingKeyTerm, UndefinedTerm},
+    transform::{
+        DuplicateKey, ErrorLocation, ExpectedObject, InvalidTypeKind, LocatedError, MissingKeyTerm,
+        PathSegment, UndefinedTerm,
+    },
 };
 use iref::IriRefBuf;
 
 #[derive(Debug, thiserror::Error)]
 pub enum EncodeError {
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+
+    #[error("invalid JSON: {0}")]
+    InvalidJson(String),
+
+    #[error("serialization failed: {0}")]
+    Serialization(String),
+
+    #[error("round-trip decoding failed: {0}")]
+    RoundTrip(crate::DecodeError),
+
     #[error("unknown compression table `{0}`")]
     UnknownCompressionTable(u64),
 
+    #[error("unsupported compression mode `{0}`")]
+    UnsupportedCompressionMode(u64),
+
+    #[error("string dictionary not bound to an application registry entry")]
+    UnregisteredStringDictionary,
+
     #[error("expected node object")]
     ExpectedNodeObject,
 
+    #[error("JSON-LD compaction failed: {0}")]
+    Compaction(#[from] json_ld::CompactError),
+
     #[error("invalid JSON-LD context entry")]
     InvalidContextEntry,
 
@@ -33,6 +58,9 @@ pub enum EncodeError {
     #[error("node ID must be a string")]
     InvalidIdKind,
 
+    #[error("language tag must be a string")]
+    InvalidLanguageTagKind,
+
     #[error("invalid vocabulary term")]
     InvalidVocabTermKind,
 
@@ -50,6 +78,42 @@ pub enum EncodeError {
 
     #[error("`{0}` codec error: {1}")]
     Codec(&'static str, String),
+
+    #[error("{error} ({location})")]
+    Located {
+        error: Box<Self>,
+        location: ErrorLocation,
+    },
+}
+
+impl EncodeError {
+    /// Returns the location of the error in the input document, if known.
+    pub fn location(&self) -> Option<&ErrorLocation> {
+        match self {
+            Self::Located { location, .. } => Some(location),
+            _ => None,
+        }
+    }
+
+    /// Returns the error without its location.
+    pub fn without_location(&self) -> &Self {
+        match self {
+            Self::Located { error, .. } => error,
+            other => other,
+        }
+    }
+}
+
+impl LocatedError for EncodeError {
+    fn at(self, path: &[PathSegment]) -> Self {
+        match self {
+            Self::Located { .. } => self,
+            error => Self::Located {
+                error: Box::new(error),
+                location: ErrorLocation::json_pointer(path),
+            },
+        }
+    }
 }
 
 impl From<UnknownCompressionTable> for EncodeError {
@@ -58,15 +122,15 @@ impl From<UnknownCompressionTable> for EncodeError {
     }
 }
 
-impl From<DuplicateKey<json_ld::syntax::object::Key>> for EncodeError {
-    fn from(value: DuplicateKey<json_ld::syntax::object::Key>) -> Self {
-        Self::DuplicateEntry(value.0.into_string())
+impl<K: JsonKey> From<DuplicateKey<K>> for EncodeError {
+    fn from(value: DuplicateKey<K>) -> Self {
+        Self::DuplicateEntry(value.0.as_str().to_owned())
     }
 }
 
-impl From<MissingKeyTerm<json_ld::syntax::object::Key>> for EncodeError {
-    fn from(value: MissingKeyTerm<json_ld::syntax::object::Key>) -> Self {
-        Self::MissingIdFor(value.0.into_string())
+impl<K: JsonKey> From<MissingKeyTerm<K>> for EncodeError {
+    fn from(value: MissingKeyTerm<K>) -> Self {
+        Self::MissingIdFor(value.0.as_str().to_owned())
     }
 }
 
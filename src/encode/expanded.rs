@@ -0,0 +1,111 @@
+use iref::IriBuf;
+use json_ld::{
+    syntax::context::ContextEntry, JsonLdProcessor, RemoteContextReference, RemoteDocument,
+};
+
+use crate::{
+    loader::{MemoLoader, PinnedLoader},
+    CborValue, EncodeError, EncodeOptions, JsonObject, JsonValue, RdfDataset,
+};
+
+/// Encodes an expanded JSON-LD document into CBOR-LD using the given target
+/// contexts, JSON-LD context loader and the default options.
+pub async fn encode_expanded(
+    expanded_document: &JsonValue,
+    contexts: &[IriBuf],
+    loader: impl json_ld::Loader,
+) -> Result<CborValue, EncodeError> {
+    encode_expanded_with(expanded_document, contexts, loader, Default::default()).await
+}
+
+/// Encodes an expanded JSON-LD document into CBOR-LD using the given target
+/// contexts, JSON-LD context loader and options.
+///
+/// The document is first compacted with the given contexts, which are then
+/// used as the `@context` of the encoded document. Documents with several
+/// root nodes are encoded as a `@graph` entry listing them.
+pub async fn encode_expanded_with(
+    expanded_document: &JsonValue,
+    contexts: &[IriBuf],
+    loader: impl json_ld::Loader,
+    mut options: EncodeOptions,
+) -> Result<CborValue, EncodeError> {
+    let loader = MemoLoader::new(PinnedLoader::new(
+        loader,
+        std::mem::take(&mut options.context_pins),
+    ));
+
+    let compacted = compact(expanded_document, contexts, &loader).await?;
+    crate::encode_with(&compacted, &loader, options).await
+}
+
+/// Encodes an RDF dataset into CBOR-LD using the given target contexts,
+/// JSON-LD context loader and the default options.
+pub async fn encode_rdf(
+    dataset: &RdfDataset,
+    contexts: &[IriBuf],
+    loader: impl json_ld::Loader,
+) -> Result<CborValue, EncodeError> {
+    encode_rdf_with(dataset, contexts, loader, Default::default()).await
+}
+
+/// Encodes an RDF dataset into CBOR-LD using the given target contexts,
+/// JSON-LD context loader and options.
+///
+/// The dataset is converted into an expanded JSON-LD document (see the
+/// [`rdf`](crate::rdf) module), then encoded with [`encode_expanded_with`].
+pub async fn encode_rdf_with(
+    dataset: &RdfDataset,
+    contexts: &[IriBuf],
+    loader: impl json_ld::Loader,
+    options: EncodeOptions,
+) -> Result<CborValue, EncodeError> {
+    encode_expanded_with(&dataset.to_expanded_json(), contexts, loader, options).await
+}
+
+/// Compacts an expanded JSON-LD document with the given contexts.
+async fn compact(
+    expanded_document: &JsonValue,
+    contexts: &[IriBuf],
+    loader: &impl json_ld::Loader,
+) -> Result<JsonValue, EncodeError> {
+    let mut entries: Vec<_> = contexts
+        .iter()
+        .map(|iri| ContextEntry::IriRef(iri.as_iri_ref().to_owned()))
+        .collect();
+    let context = if entries.len() == 1 {
+        json_ld::syntax::Context::One(entries.remove(0))
+    } else {
+        json_ld::syntax::Context::Many(entries)
+    };
+
+    let document = RemoteDocument::new(None, None, expanded_document.clone());
+    let compacted = document
+        .compact(
+            RemoteContextReference::Loaded(RemoteDocument::new(None, None, context)),
+            loader,
+        )
+        .await?;
+
+    let JsonValue::Object(node) = compacted else {
+        return Err(EncodeError::ExpectedNodeObject);
+    };
+
+    let context_value = match contexts {
+        [iri] => JsonValue::String(iri.as_str().into()),
+        _ => JsonValue::Array(
+            contexts
+                .iter()
+                .map(|iri| JsonValue::String(iri.as_str().into()))
+                .collect(),
+        ),
+    };
+
+    let mut result = JsonObject::new();
+    result.insert("@context".into(), context_value);
+    for entry in node {
+        result.insert(entry.key, entry.value);
+    }
+
+    Ok(JsonValue::Object(result))
+}
@@ -1,13 +1,27 @@
-use std::borrow::Cow;
+use std::{borrow::Cow, io, marker::PhantomData, sync::Arc};
 
 use crate::{
-    transform::{Transformer, TransformerState},
-    CborObject, CborValue, Codecs, CompressionMode, JsonObject, JsonValue, Tables,
-    CBOR_LD_TAG_HIGH,
+    cache::ContextCache,
+    json::{JsonKey, JsonLike, JsonNumber, JsonRef},
+    lenient::{Warning, WarningKind, Warnings},
+    loader::{block_on, Blocking, ContextPins, PinnedLoader, SyncLoader},
+    lossless,
+    references::deduplicate,
+    stats::Handler,
+    stream::{read_node, Cbor, Json, StreamInput, StreamWriter},
+    tables::{Registry, RegistryEntry},
+    transform::{
+        DeferredEntries, ErrorLocation, NoDeferredEntries, PathSegment, Transformer,
+        TransformerState,
+    },
+    CborObject, CborValue, Codecs, CompressionMode, Decoder, JsonValue, Tables,
 };
 use iref::{Iri, IriBuf, IriRef, IriRefBuf};
+use json_ld::syntax::TryFromJson;
 mod error;
 pub use error::*;
+mod expanded;
+pub use expanded::*;
 
 /// Encoding options.
 #[derive(Debug, Default)]
@@ -16,12 +30,56 @@ pub struct EncodeOptions {
     pub compression_mode: CompressionMode,
 
     /// Default compression tables.
+    ///
+    /// Must not have a string dictionary, which requires its own
+    /// [`registry`](Self::registry) entry.
     pub default_table: Cow<'static, Tables>,
 
+    /// Application compression tables registry, used to resolve the
+    /// compression mode registry entries unknown to this library.
+    pub registry: Registry,
+
     // /// Map associating JSON-LD context URLs to CBOR-LD (integer) identifiers.
     // pub context_map: IdMap,
     /// Datatype codecs.
     pub codecs: Codecs,
+
+    /// Replace repeated strings, arrays and maps with references.
+    ///
+    /// See the [`references`](crate::references) module.
+    pub deduplicate: bool,
+
+    /// Pinned context digests.
+    ///
+    /// Encoding fails if a pinned context does not match its digest.
+    pub context_pins: ContextPins,
+
+    /// Processed context cache, shared between calls.
+    pub context_cache: Option<Arc<ContextCache>>,
+
+    /// Share term allocation and context IDs between the documents of a CBOR
+    /// sequence, recording the position of each document in the given tag.
+    ///
+    /// The tag is chosen by the application, and must not be used for
+    /// anything else in the documents. See [`encode_sequence_with`].
+    pub shared_sequence_tag: Option<u64>,
+
+    /// Record the original entry order and compact IRI spelling so that
+    /// decoding restores the exact input document, in a side channel wrapped
+    /// with the given tag.
+    ///
+    /// The tag is chosen by the application, and must be given to the
+    /// decoder. See the [`lossless`](crate::lossless) module.
+    pub lossless_tag: Option<u64>,
+
+    /// Keep undefined terms uncompressed and leave values that no codec can
+    /// handle as-is, instead of failing.
+    ///
+    /// See the [`lenient`](crate::lenient) module.
+    pub lenient: bool,
+
+    /// Lenient mode warnings collector. Warnings are logged if none is given.
+    pub warnings: Option<Arc<Warnings>>,
 }
 
 /// Encodes a JSON-LD document into CBOR-LD using the given JSON-LD context
@@ -40,29 +98,290 @@ pub async fn encode_with(
     loader: impl json_ld::Loader,
     options: EncodeOptions,
 ) -> Result<CborValue, EncodeError> {
-    let cbor_value = match options.compression_mode {
-        CompressionMode::Uncompressed => {
-            todo!()
+    encode_document_with(json_ld_document, loader, options).await
+}
+
+/// Encodes a [`serde_json::Value`] JSON-LD document into CBOR-LD using the
+/// given JSON-LD context loader and the default options.
+#[cfg(feature = "serde_json")]
+pub async fn encode_serde_json(
+    json_ld_document: &serde_json::Value,
+    loader: impl json_ld::Loader,
+) -> Result<CborValue, EncodeError> {
+    encode_serde_json_with(json_ld_document, loader, Default::default()).await
+}
+
+/// Encodes a [`serde_json::Value`] JSON-LD document into CBOR-LD using the
+/// given JSON-LD context loader and the given options.
+///
+/// The document is processed as is, without being converted to a
+/// [`JsonValue`] first.
+#[cfg(feature = "serde_json")]
+pub async fn encode_serde_json_with(
+    json_ld_document: &serde_json::Value,
+    loader: impl json_ld::Loader,
+    options: EncodeOptions,
+) -> Result<CborValue, EncodeError> {
+    encode_document_with(json_ld_document, loader, options).await
+}
+
+async fn encode_document_with<V: JsonLike>(
+    json_ld_document: &V,
+    loader: impl json_ld::Loader,
+    options: EncodeOptions,
+) -> Result<CborValue, EncodeError> {
+    let pipeline = EncodePipeline::from(options);
+    let loader = pipeline.loader(loader);
+    pipeline.encode(&loader, json_ld_document).await
+}
+
+/// Encoding pipeline configured by [`EncodeOptions`].
+///
+/// Shared by the encoding functions and [`CborLd`](crate::CborLd) sessions.
+pub(crate) struct EncodePipeline {
+    pub compression_mode: CompressionMode,
+    pub default_tables: Arc<Tables>,
+    pub registry: Registry,
+    pub codecs: Arc<Codecs>,
+    pub deduplicate: bool,
+    pub context_pins: ContextPins,
+    pub context_cache: Option<Arc<ContextCache>>,
+    pub shared_sequence_tag: Option<u64>,
+    pub lossless_tag: Option<u64>,
+    pub lenient: bool,
+    pub warnings: Option<Arc<Warnings>>,
+}
+
+impl From<EncodeOptions> for EncodePipeline {
+    fn from(options: EncodeOptions) -> Self {
+        Self {
+            compression_mode: options.compression_mode,
+            default_tables: Arc::new(options.default_table.into_owned()),
+            registry: options.registry,
+            codecs: Arc::new(options.codecs),
+            deduplicate: options.deduplicate,
+            context_pins: options.context_pins,
+            context_cache: options.context_cache,
+            shared_sequence_tag: options.shared_sequence_tag,
+            lossless_tag: options.lossless_tag,
+            lenient: options.lenient,
+            warnings: options.warnings,
+        }
+    }
+}
+
+impl EncodePipeline {
+    /// Wraps the given loader to check the pinned contexts.
+    pub fn loader<L>(&self, loader: L) -> PinnedLoader<L> {
+        PinnedLoader::new(loader, self.context_pins.clone())
+    }
+
+    /// Returns the compression tables of the compression mode.
+    pub fn tables(&self) -> Result<Arc<Tables>, EncodeError> {
+        match self.compression_mode {
+            CompressionMode::Uncompressed => Err(EncodeError::UnsupportedCompressionMode(
+                self.compression_mode.id(),
+            )),
+            CompressionMode::Compressed(entry) => {
+                let tables = self.registry.resolve(entry, &self.default_tables)?;
+                if entry == RegistryEntry::Default && tables.strings.is_some() {
+                    return Err(EncodeError::UnregisteredStringDictionary);
+                }
+
+                Ok(tables)
+            }
         }
-        CompressionMode::Compressed(t) => {
-            let mut compressor =
-                Encoder::new(loader, options.codecs, t.tables(options.default_table)?);
+    }
+
+    /// Creates a new encoder.
+    pub fn encoder<L, V>(&self, loader: L, tables: Arc<Tables>) -> Encoder<L, V> {
+        let mut encoder = Encoder::new(loader, self.codecs.clone(), tables);
+        encoder.state.context_cache = self.context_cache.clone();
+        encoder.state.lenient = self.lenient;
+        encoder.state.warnings = self.warnings.clone();
+        encoder
+    }
 
-            compressor.encode(json_ld_document).await
+    /// Creates a new decoder mirroring the encoders, used to compute the
+    /// lossless side channels.
+    pub fn decoder<L, V>(&self, loader: L, tables: Arc<Tables>) -> Decoder<L, V> {
+        let decoder =
+            Decoder::new(loader, self.codecs.clone(), tables).with_quiet_lenient_mode(self.lenient);
+        match self.context_cache.clone() {
+            Some(cache) => decoder.with_context_cache(cache),
+            None => decoder,
         }
-    }?;
+    }
 
-    let id = options.compression_mode.id();
+    /// Encodes a document body with the given encoder, along with its
+    /// lossless side channel if enabled.
+    ///
+    /// The side channel is computed with the given decoder, or a new one.
+    pub async fn encode_body<L, V>(
+        &self,
+        encoder: &mut Encoder<L, V>,
+        decoder: Option<&mut Decoder<L, V>>,
+        json_ld_document: &V,
+    ) -> Result<CborValue, EncodeError>
+    where
+        L: json_ld::Loader + Clone,
+        V: JsonLike,
+    {
+        let body = encoder.encode(json_ld_document).await?;
+
+        let Some(tag) = self.lossless_tag else {
+            return Ok(body);
+        };
+
+        match decoder {
+            Some(decoder) => wrap_lossless(tag, json_ld_document, body, decoder).await,
+            None => {
+                let mut decoder =
+                    self.decoder(encoder.loader.clone(), encoder.state.tables.clone());
+                wrap_lossless(tag, json_ld_document, body, &mut decoder).await
+            }
+        }
+    }
+
+    /// Deduplicates an encoded document body if enabled, and wraps it in the
+    /// CBOR-LD tag.
+    ///
+    /// The position of documents encoded with shared sequence state is
+    /// recorded in the document, in the shared sequence tag.
+    pub fn finish(
+        &self,
+        body: CborValue,
+        sequence_position: Option<usize>,
+    ) -> Result<CborValue, EncodeError> {
+        let body = if self.deduplicate {
+            deduplicate(body)
+        } else {
+            body
+        };
+
+        let body = match self.shared_sequence_tag.zip(sequence_position) {
+            Some((tag, position)) => CborValue::Tag(
+                tag,
+                Box::new(CborValue::Array(vec![(position as u64).into(), body])),
+            ),
+            None => body,
+        };
+
+        tag_cbor_ld(self.compression_mode, body)
+    }
+
+    /// Encodes a JSON-LD document.
+    pub async fn encode<L, V>(
+        &self,
+        loader: L,
+        json_ld_document: &V,
+    ) -> Result<CborValue, EncodeError>
+    where
+        L: json_ld::Loader + Clone,
+        V: JsonLike,
+    {
+        let mut encoder = self.encoder(loader, self.tables()?);
+        let body = self
+            .encode_body(&mut encoder, None, json_ld_document)
+            .await?;
+        self.finish(body, None)
+    }
+
+    /// Reads a JSON-LD document from `reader`, writing the CBOR-LD output
+    /// incrementally.
+    ///
+    /// See the [`stream`](crate::stream) module. Deduplication and lossless
+    /// encoding need the whole document: when enabled, the input and output
+    /// are built in memory.
+    pub async fn encode_stream<L>(
+        &self,
+        loader: L,
+        mut reader: impl io::Read,
+        mut writer: impl io::Write,
+    ) -> Result<(), EncodeError>
+    where
+        L: json_ld::Loader + Clone,
+    {
+        if self.deduplicate || self.lossless_tag.is_some() {
+            let json_ld_document = read_json(&mut reader)?;
+            let cbor_value = self.encode(loader, &json_ld_document).await?;
+            return write_cbor(&mut writer, &cbor_value).map_err(Into::into);
+        }
 
-    if id < 128 {
-        let tag = (CBOR_LD_TAG_HIGH as u64) << 8 | id;
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes)?;
+        match read_node::<Json>(bytes, 0) {
+            Ok((json_ld_document, deferred)) => {
+                self.encode_to_writer(loader, &json_ld_document, &deferred, writer)
+                    .await
+            }
+            Err(bytes) => {
+                let json_ld_document = Json::parse(&bytes)?;
+                drop(bytes);
+                self.encode_to_writer(loader, &json_ld_document, &NoDeferredEntries, writer)
+                    .await
+            }
+        }
+    }
 
-        Ok(CborValue::Tag(tag, Box::new(cbor_value)))
-    } else {
-        todo!()
+    /// Encodes a JSON-LD document whose root node entries may be deferred,
+    /// writing the output incrementally.
+    async fn encode_to_writer<L>(
+        &self,
+        loader: L,
+        json_ld_document: &JsonValue,
+        deferred: &impl DeferredEntries<json_ld::syntax::object::Key, JsonValue, EncodeError>,
+        mut writer: impl io::Write,
+    ) -> Result<(), EncodeError>
+    where
+        L: json_ld::Loader,
+    {
+        let mut encoder = self.encoder(loader, self.tables()?);
+        let tag = self
+            .compression_mode
+            .tag()
+            .ok_or(EncodeError::UnsupportedCompressionMode(
+                self.compression_mode.id(),
+            ))?;
+        Cbor::write_head(&mut writer, 6, tag)?;
+        encoder
+            .encode_node_to_writer(json_ld_document, deferred, writer)
+            .await
     }
 }
 
+/// Wraps an encoded document body with the lossless side channel restoring
+/// the original document, computed by decoding the body with `decoder`.
+///
+/// See the [`lossless`](crate::lossless) module.
+pub(crate) async fn wrap_lossless<L: json_ld::Loader, V: JsonLike>(
+    tag: u64,
+    json_ld_document: &V,
+    body: CborValue,
+    decoder: &mut Decoder<L, V>,
+) -> Result<CborValue, EncodeError> {
+    let decoded = decoder
+        .decode(&body)
+        .await
+        .map_err(EncodeError::RoundTrip)?;
+    let patch = lossless::diff(json_ld_document, &decoded);
+    Ok(lossless::wrap(tag, body, patch))
+}
+
+/// Wraps an encoded document body in the CBOR-LD tag of the given
+/// compression mode.
+pub(crate) fn tag_cbor_ld(
+    compression_mode: CompressionMode,
+    value: CborValue,
+) -> Result<CborValue, EncodeError> {
+    let tag = compression_mode
+        .tag()
+        .ok_or(EncodeError::UnsupportedCompressionMode(
+            compression_mode.id(),
+        ))?;
+    Ok(CborValue::Tag(tag, Box::new(value)))
+}
+
 /// Encodes a JSON-LD document into CBOR-LD bytes using the given JSON-LD
 /// context loader and the default options.
 pub async fn encode_to_bytes(
@@ -84,31 +403,244 @@ pub async fn encode_to_bytes_with(
         .map(cbor_into_bytes)
 }
 
+/// Encodes a JSON-LD document into CBOR-LD using the given synchronous
+/// JSON-LD context loader and the default options, blocking the current
+/// thread.
+pub fn encode_sync(
+    json_ld_document: &json_ld::syntax::Value,
+    loader: impl SyncLoader,
+) -> Result<CborValue, EncodeError> {
+    encode_sync_with(json_ld_document, loader, Default::default())
+}
+
+/// Encodes a JSON-LD document into CBOR-LD using the given synchronous
+/// JSON-LD context loader and the given options, blocking the current thread.
+pub fn encode_sync_with(
+    json_ld_document: &json_ld::syntax::Value,
+    loader: impl SyncLoader,
+    options: EncodeOptions,
+) -> Result<CborValue, EncodeError> {
+    block_on(encode_with(json_ld_document, Blocking(loader), options))
+}
+
+/// Encodes a JSON-LD document into CBOR-LD bytes using the given synchronous
+/// JSON-LD context loader and the default options, blocking the current
+/// thread.
+pub fn encode_to_bytes_sync(
+    json_ld_document: &json_ld::syntax::Value,
+    loader: impl SyncLoader,
+) -> Result<Vec<u8>, EncodeError> {
+    encode_to_bytes_sync_with(json_ld_document, loader, Default::default())
+}
+
+/// Encodes a JSON-LD document into CBOR-LD bytes using the given synchronous
+/// JSON-LD context loader and the given options, blocking the current thread.
+pub fn encode_to_bytes_sync_with(
+    json_ld_document: &json_ld::syntax::Value,
+    loader: impl SyncLoader,
+    options: EncodeOptions,
+) -> Result<Vec<u8>, EncodeError> {
+    encode_sync_with(json_ld_document, loader, options).map(cbor_into_bytes)
+}
+
+/// Serializes a value as a JSON-LD document and encodes it into CBOR-LD bytes
+/// using the given JSON-LD context loader and the default options.
+pub async fn to_vec<T: serde::Serialize + ?Sized>(
+    value: &T,
+    loader: impl json_ld::Loader,
+) -> Result<Vec<u8>, EncodeError> {
+    to_vec_with(value, loader, Default::default()).await
+}
+
+/// Serializes a value as a JSON-LD document and encodes it into CBOR-LD bytes
+/// using the given JSON-LD context loader and the given options.
+///
+/// The value is serialized into a JSON tree first, without going through JSON
+/// text. The encoder processes the `@context` and `@type` entries of a node
+/// before its other entries, whatever their serialization order, so it does
+/// not consume the serializer output directly.
+pub async fn to_vec_with<T: serde::Serialize + ?Sized>(
+    value: &T,
+    loader: impl json_ld::Loader,
+    options: EncodeOptions,
+) -> Result<Vec<u8>, EncodeError> {
+    let json_ld_document =
+        json_syntax::to_value(value).map_err(|e| EncodeError::Serialization(e.to_string()))?;
+    encode_to_bytes_with(&json_ld_document, loader, options).await
+}
+
+/// Encodes JSON-LD documents into a CBOR sequence (RFC 8742) of CBOR-LD
+/// documents using the given JSON-LD context loader and the default options.
+pub async fn encode_sequence<'a>(
+    json_ld_documents: impl IntoIterator<Item = &'a JsonValue>,
+    loader: impl json_ld::Loader,
+) -> Result<Vec<CborValue>, EncodeError> {
+    encode_sequence_with(json_ld_documents, loader, Default::default()).await
+}
+
+/// Encodes JSON-LD documents into a CBOR sequence (RFC 8742) of CBOR-LD
+/// documents using the given JSON-LD context loader and the given options.
+///
+/// Each document is encoded as a tagged CBOR-LD document. By default the
+/// documents are independent. With [`EncodeOptions::shared_sequence_tag`],
+/// the term IDs allocated and the context IRIs met in a document are reused
+/// by the following ones, which must then be decoded in order, with the same
+/// option. Each document then records its position in the sequence, in the
+/// given tag:
+/// ```text
+/// tag([position, body])
+/// ```
+pub async fn encode_sequence_with<'a>(
+    json_ld_documents: impl IntoIterator<Item = &'a JsonValue>,
+    loader: impl json_ld::Loader,
+    options: EncodeOptions,
+) -> Result<Vec<CborValue>, EncodeError> {
+    let pipeline = EncodePipeline::from(options);
+    let loader = pipeline.loader(loader);
+    let tables = pipeline.tables()?;
+    let mut compressor = pipeline.encoder(&loader, tables.clone());
+    let mut shared_decoder = None;
+
+    let share_sequence_state = pipeline.shared_sequence_tag.is_some();
+    if share_sequence_state {
+        compressor.state.share_sequence_state();
+        if pipeline.lossless_tag.is_some() {
+            shared_decoder = Some(
+                pipeline
+                    .decoder(&loader, tables)
+                    .with_shared_sequence_state(),
+            )
+        }
+    }
+
+    let mut result = Vec::new();
+    for (position, json_ld_document) in json_ld_documents.into_iter().enumerate() {
+        if !share_sequence_state {
+            compressor.state.reset_allocator()
+        }
+
+        let body = pipeline
+            .encode_body(&mut compressor, shared_decoder.as_mut(), json_ld_document)
+            .await?;
+        let sequence_position = share_sequence_state.then_some(position);
+        result.push(pipeline.finish(body, sequence_position)?)
+    }
+
+    Ok(result)
+}
+
+/// Encodes JSON-LD documents into CBOR sequence (RFC 8742) bytes using the
+/// given JSON-LD context loader and the default options.
+pub async fn encode_sequence_to_bytes<'a>(
+    json_ld_documents: impl IntoIterator<Item = &'a JsonValue>,
+    loader: impl json_ld::Loader,
+) -> Result<Vec<u8>, EncodeError> {
+    encode_sequence_to_bytes_with(json_ld_documents, loader, Default::default()).await
+}
+
+/// Encodes JSON-LD documents into CBOR sequence (RFC 8742) bytes using the
+/// given JSON-LD context loader and the given options.
+pub async fn encode_sequence_to_bytes_with<'a>(
+    json_ld_documents: impl IntoIterator<Item = &'a JsonValue>,
+    loader: impl json_ld::Loader,
+    options: EncodeOptions,
+) -> Result<Vec<u8>, EncodeError> {
+    encode_sequence_with(json_ld_documents, loader, options)
+        .await
+        .map(cbor_sequence_into_bytes)
+}
+
+/// Reads a JSON-LD document from `reader` and writes its CBOR-LD encoding
+/// to `writer` using the given JSON-LD context loader and the default
+/// options.
+///
+/// The output is written incrementally, without building the JSON or CBOR
+/// tree of the whole document. See the [`stream`](crate::stream) module.
+pub async fn encode_stream(
+    reader: impl io::Read,
+    writer: impl io::Write,
+    loader: impl json_ld::Loader,
+) -> Result<(), EncodeError> {
+    encode_stream_with(reader, writer, loader, Default::default()).await
+}
+
+/// Reads a JSON-LD document from `reader` and writes its CBOR-LD encoding
+/// to `writer` using the given JSON-LD context loader and the given options.
+///
+/// Deduplication and lossless encoding need the whole encoded document: when
+/// enabled, the output is built in memory before being written.
+pub async fn encode_stream_with(
+    reader: impl io::Read,
+    writer: impl io::Write,
+    loader: impl json_ld::Loader,
+    options: EncodeOptions,
+) -> Result<(), EncodeError> {
+    let pipeline = EncodePipeline::from(options);
+    let loader = pipeline.loader(loader);
+    pipeline.encode_stream(&loader, reader, writer).await
+}
+
+pub(crate) fn read_json(reader: &mut impl io::Read) -> Result<JsonValue, EncodeError> {
+    use json_ld::syntax::Parse;
+    let mut buffer = String::new();
+    reader.read_to_string(&mut buffer)?;
+    JsonValue::parse_str(&buffer)
+        .map(|(value, _)| value)
+        .map_err(|e| EncodeError::InvalidJson(e.to_string()))
+}
+
+pub(crate) fn write_cbor(writer: &mut impl io::Write, cbor: &CborValue) -> io::Result<()> {
+    <Cbor as crate::stream::StreamFormat>::write_value(writer, cbor)
+}
+
 pub fn cbor_into_bytes(cbor: CborValue) -> Vec<u8> {
     let mut bytes = Vec::new();
     ciborium::into_writer(&cbor, &mut bytes).unwrap();
     bytes
 }
 
-pub struct Encoder<L> {
+/// Serializes the given CBOR values as a CBOR sequence (RFC 8742).
+pub fn cbor_sequence_into_bytes(sequence: Vec<CborValue>) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    for cbor in sequence {
+        ciborium::into_writer(&cbor, &mut bytes).unwrap();
+    }
+    bytes
+}
+
+/// CBOR-LD encoder.
+///
+/// The input JSON-LD documents can be any [`JsonLike`] representation,
+/// [`JsonValue`] by default.
+pub struct Encoder<L, V = JsonValue> {
     loader: L,
-    state: TransformerState,
+    pub(crate) state: TransformerState,
+    value: PhantomData<fn(&V)>,
 }
 
-impl<L> Encoder<L> {
-    pub fn new(loader: L, codecs: Codecs, tables: Cow<'static, Tables>) -> Self {
+impl<L, V> Encoder<L, V> {
+    pub fn new(loader: L, codecs: impl Into<Arc<Codecs>>, tables: impl Into<Arc<Tables>>) -> Self {
         Self {
             loader,
-            state: TransformerState::new(codecs, tables),
+            state: TransformerState::new(codecs.into(), tables.into()),
+            value: PhantomData,
         }
     }
+
+    /// Uses the given processed context cache.
+    pub fn with_context_cache(mut self, cache: Arc<ContextCache>) -> Self {
+        self.state.context_cache = Some(cache);
+        self
+    }
 }
 
-impl<L> Encoder<L>
+impl<L, V> Encoder<L, V>
 where
     L: json_ld::Loader,
+    V: JsonLike,
 {
-    pub async fn encode(&mut self, json_ld_document: &JsonValue) -> Result<CborValue, EncodeError> {
+    pub async fn encode(&mut self, json_ld_document: &V) -> Result<CborValue, EncodeError> {
+        self.state.reset();
         let active_context = json_ld::Context::new(None);
         self.transform(&active_context, json_ld_document).await
     }
@@ -116,24 +648,112 @@ where
     fn encode_vocab_term(
         &self,
         active_context: &json_ld::Context,
-        value: &JsonValue,
+        value: &V,
     ) -> Result<CborValue, EncodeError> {
         let value = value.as_str().ok_or(EncodeError::InvalidVocabTermKind)?;
         self.state.encode_vocab_term(active_context, value)
     }
+
+    fn encode_id(&self, value: &V) -> Result<CborValue, EncodeError> {
+        let id = value.as_str().ok_or(EncodeError::InvalidIdKind)?;
+        let id = Iri::new(id).map_err(|_| EncodeError::InvalidId(id.to_owned()))?;
+        self.state.codecs.iri.encode(id)
+    }
+
+    /// Records the handler of the current value, when computing compression
+    /// statistics.
+    fn record(&self, handler: impl FnOnce() -> Handler) {
+        if let Some(recorder) = &self.state.recorder {
+            recorder.handler(&self.state.path, handler())
+        }
+    }
+
+    /// Records the IRI codec used to encode the current value.
+    fn record_iri(
+        &self,
+        result: &Result<CborValue, EncodeError>,
+        handler: fn(Option<String>) -> Handler,
+    ) {
+        self.record(|| {
+            let scheme = match result {
+                Ok(CborValue::Array(items)) => match items.first() {
+                    Some(CborValue::Integer(id)) => u64::try_from(*id)
+                        .ok()
+                        .and_then(|id| self.state.codecs.iri.get_by_id(id))
+                        .map(|(prefix, _)| prefix.to_owned()),
+                    _ => None,
+                },
+                _ => None,
+            };
+
+            handler(scheme)
+        })
+    }
+
+    /// Leaves the value as-is if it could not be encoded, in lenient mode.
+    fn or_as_is(
+        &self,
+        result: Result<CborValue, EncodeError>,
+        value: &V,
+    ) -> Result<CborValue, EncodeError> {
+        match result {
+            Err(e) if self.state.lenient => {
+                self.warn(WarningKind::UnhandledValue(e.to_string()));
+                self.transform_json(value)
+            }
+            result => result,
+        }
+    }
+}
+
+impl<L> Encoder<L>
+where
+    L: json_ld::Loader,
+{
+    /// Encodes a JSON-LD document, writing the resulting CBOR (without the
+    /// CBOR-LD tag) incrementally to the given writer.
+    ///
+    /// See the [`stream`](crate::stream) module.
+    pub async fn encode_to_writer(
+        &mut self,
+        json_ld_document: &JsonValue,
+        writer: impl io::Write,
+    ) -> Result<(), EncodeError> {
+        self.encode_node_to_writer(json_ld_document, &NoDeferredEntries, writer)
+            .await
+    }
+
+    /// Encodes a JSON-LD document whose root node entries may be deferred,
+    /// writing the resulting CBOR incrementally.
+    async fn encode_node_to_writer(
+        &mut self,
+        json_ld_document: &JsonValue,
+        deferred: &impl DeferredEntries<json_ld::syntax::object::Key, JsonValue, EncodeError>,
+        writer: impl io::Write,
+    ) -> Result<(), EncodeError> {
+        self.state.reset();
+        let active_context = json_ld::Context::new(None);
+        let object = json_ld_document
+            .as_object()
+            .ok_or(EncodeError::ExpectedNodeObject)?;
+        let mut sink = StreamWriter::<Cbor, _>::new(writer);
+        self.transform_node_into(&active_context, object, deferred, &mut sink)
+            .await
+    }
 }
 
-impl<L> Transformer for Encoder<L>
+impl<L, V> Transformer for Encoder<L, V>
 where
     L: json_ld::Loader,
+    V: JsonLike,
 {
-    type Input = JsonValue;
+    type Input = V;
     type Output = CborValue;
 
-    type InputObject = JsonObject;
+    type InputObject = V::Map;
     type OutputObject = CborObject;
 
-    type InputKey = json_ld::syntax::object::Key;
+    type InputKey = V::Key;
     type OutputKey = CborValue;
 
     type Loader = L;
@@ -148,7 +768,12 @@ where
     }
 
     fn context_id(&self, _value: &Self::Input, iri_ref: &IriRef) -> Self::Output {
-        match self.state.tables.context.get_id(iri_ref) {
+        self.record(|| Handler::Context);
+        if let Some(recorder) = &self.state.recorder {
+            recorder.context(iri_ref)
+        }
+
+        match self.state.context_id(iri_ref) {
             Some(id) => CborValue::Integer(id.into()),
             None => CborValue::Text(iri_ref.as_str().to_owned()),
         }
@@ -188,9 +813,9 @@ where
     }
 
     fn transform_id(&self, value: &Self::Input) -> Result<Self::Output, Self::Error> {
-        let id = value.as_str().ok_or(EncodeError::InvalidIdKind)?;
-        let id = Iri::new(id).map_err(|_| EncodeError::InvalidId(id.to_owned()))?;
-        self.state.codecs.iri.encode(id)
+        let result = self.encode_id(value);
+        self.record_iri(&result, Handler::Iri);
+        self.or_as_is(result, value)
     }
 
     fn transform_vocab(
@@ -198,28 +823,91 @@ where
         active_context: &json_ld::Context,
         value: &Self::Input,
     ) -> Result<Self::Output, Self::Error> {
-        self.encode_vocab_term(active_context, value)
+        let result = self.encode_vocab_term(active_context, value);
+        self.record(|| Handler::Vocab);
+        self.or_as_is(result, value)
+    }
+
+    fn transform_json(&self, value: &Self::Input) -> Result<Self::Output, Self::Error> {
+        self.record(|| Handler::Json);
+        self.state.codecs.json.encode(&value.to_json_value())
+    }
+
+    fn transform_language(&self, value: &Self::Input) -> Result<Self::Output, Self::Error> {
+        self.record(|| Handler::Language);
+        match value.as_str() {
+            Some(tag) => Ok(self.state.codecs.language.encode(tag)),
+            None => self.or_as_is(Err(EncodeError::InvalidLanguageTagKind), value),
+        }
+    }
+
+    fn language_key(&self, key: &Self::InputKey) -> Result<Self::OutputKey, Self::Error> {
+        Ok(self.state.codecs.language.encode(key.as_str()))
     }
 
     fn state_and_loader_mut(&mut self) -> (&mut TransformerState, &mut Self::Loader) {
         (&mut self.state, &mut self.loader)
     }
 
+    fn path_key(&self, key: &Self::InputKey) -> String {
+        key.as_str().to_owned()
+    }
+
+    fn warn(&self, kind: WarningKind) {
+        self.state.warn(Warning {
+            kind,
+            location: ErrorLocation::json_pointer(&self.state.path),
+        })
+    }
+
+    fn inline_context(
+        &self,
+        value: &Self::Input,
+    ) -> Result<Option<(json_ld::syntax::Context, Self::Output)>, Self::Error> {
+        if !self.state.lenient || value.as_object().is_none() {
+            return Ok(None);
+        }
+
+        self.warn(WarningKind::InlineContext);
+        let context = json_ld::syntax::Context::try_from_json(value.to_json_value().into_owned())?;
+        Ok(Some((context, self.transform_json(value)?)))
+    }
+
+    fn uncompressed_key(&self, key: &Self::InputKey, defined: bool) -> Option<Self::OutputKey> {
+        let term = key.as_str();
+        (self.state.lenient
+            && (!defined || self.state.allocator.encode_term(term, false).is_none()))
+        .then(|| CborValue::Text(term.to_owned()))
+    }
+
     fn transform_typed_value(
         &mut self,
         active_context: &json_ld::Context,
         value: &Self::Input,
         type_: Option<&json_ld::Type<IriBuf>>,
     ) -> Result<Option<Self::Output>, Self::Error> {
-        match value {
-            JsonValue::String(value) => match type_ {
-                Some(type_) => match self.state.tables.types.get(type_) {
-                    Some(table) => Ok(Some(table.encode(value))),
-                    None => match self.state.codecs.type_.get(type_) {
-                        Some(codec) => codec.encode(&self.state, active_context, value).map(Some),
-                        None => Ok(None),
-                    },
-                },
+        match value.as_str() {
+            Some(text) => match type_ {
+                Some(type_) => {
+                    if let Some(recorder) = &self.state.recorder {
+                        recorder.typed_value(type_, text)
+                    }
+
+                    match self.state.tables.types.get(type_) {
+                        Some(table) => {
+                            self.record(|| Handler::TypeTable(type_.to_string()));
+                            Ok(Some(table.encode(text)))
+                        }
+                        None => match self.state.codecs.type_.get(type_) {
+                            Some(codec) => {
+                                let result = codec.encode(&self.state, active_context, text);
+                                self.record(|| Handler::TypeCodec(type_.to_string()));
+                                self.or_as_is(result, value).map(Some)
+                            }
+                            None => Ok(None),
+                        },
+                    }
+                }
                 None => Ok(None),
             },
             _ => Ok(None),
@@ -231,27 +919,45 @@ where
         active_context: &json_ld::Context,
         value: &Self::Input,
     ) -> Result<Self::Output, Self::Error> {
-        match value {
-            JsonValue::Null => Ok(CborValue::Null),
-            JsonValue::Boolean(b) => Ok(CborValue::Bool(*b)),
-            JsonValue::Number(n) => match n.as_u64() {
-                Some(u) => Ok(CborValue::Integer(u.into())),
-                None => match n.as_i64() {
-                    Some(i) => Ok(CborValue::Integer(i.into())),
-                    None => Ok(CborValue::Float(n.as_f64_lossy())),
-                },
+        match value.view() {
+            JsonRef::Null => {
+                self.record(|| Handler::Plain);
+                Ok(CborValue::Null)
+            }
+            JsonRef::Boolean(b) => {
+                self.record(|| Handler::Plain);
+                Ok(CborValue::Bool(b))
+            }
+            JsonRef::Number(n) => {
+                self.record(|| Handler::Plain);
+                match n {
+                    JsonNumber::Unsigned(u) => Ok(CborValue::Integer(u.into())),
+                    JsonNumber::Signed(i) => Ok(CborValue::Integer(i.into())),
+                    JsonNumber::Float(f) => Ok(CborValue::Float(f)),
+                }
+            }
+            JsonRef::String(s) => match &self.state.tables.strings {
+                Some(dictionary) => {
+                    self.record(|| Handler::Dictionary);
+                    Ok(self.state.strings.encode(dictionary, s))
+                }
+                None => {
+                    self.record(|| Handler::Plain);
+                    Ok(CborValue::Text(s.to_owned()))
+                }
             },
-            JsonValue::String(s) => Ok(CborValue::Text(s.as_str().to_owned())),
-            JsonValue::Array(array) => {
+            JsonRef::Array(array) => {
                 let mut cbor_array = Vec::with_capacity(array.len());
 
-                for item in array {
-                    cbor_array.push(Box::pin(self.transform(active_context, item)).await?);
+                for (i, item) in array.iter().enumerate() {
+                    self.enter(PathSegment::Index(i));
+                    let cbor_item = Box::pin(self.transform(active_context, item)).await;
+                    cbor_array.push(self.leave(cbor_item)?);
                 }
 
                 Ok(CborValue::Array(cbor_array))
             }
-            JsonValue::Object(object) => Ok(CborValue::Map(
+            JsonRef::Object(object) => Ok(CborValue::Map(
                 Box::pin(self.transform_node(active_context, object)).await?,
             )),
         }
@@ -262,7 +968,8 @@ where
 mod tests {
     use std::future::Future;
 
-    use super::{encode, encode_to_bytes};
+    use super::{encode, encode_to_bytes, tag_cbor_ld, EncodeError};
+    use crate::CompressionMode;
 
     fn assert_send(_: impl Send + Future) {}
 
@@ -278,4 +985,14 @@ mod tests {
             json_ld::NoLoader,
         ))
     }
+
+    #[test]
+    fn varint_compression_mode() {
+        let compression_mode = CompressionMode::from_id(200);
+        assert_eq!(compression_mode.tag(), None);
+        assert!(matches!(
+            tag_cbor_ld(compression_mode, ciborium::Value::Null),
+            Err(EncodeError::UnsupportedCompressionMode(200))
+        ))
+    }
 }
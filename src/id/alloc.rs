@@ -19,6 +19,8 @@ impl IdAllocator {
             .map(|id| if plural { id + 1 } else { id })
     }
 
+    // `u64::is_multiple_of` needs Rust 1.87.
+    #[allow(clippy::manual_is_multiple_of)]
     pub fn decode_term(&self, id: u64) -> Option<(&str, bool)> {
         let singular = if id % 2 == 0 { id } else { id - 1 };
 
@@ -0,0 +1,297 @@
+//! JSON value representations.
+//!
+//! The encoder input and the decoder output can be any JSON representation
+//! implementing [`JsonLike`]. [`JsonValue`] is the default one. With the
+//! `serde_json` feature, `serde_json::Value` can be used directly, without
+//! converting it first.
+use std::borrow::Cow;
+
+use crate::{
+    transform::{TransformedObject, TransformedValue},
+    JsonObject, JsonValue,
+};
+
+/// JSON number.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum JsonNumber {
+    Unsigned(u64),
+    Signed(i64),
+    Float(f64),
+}
+
+/// Borrowed view of a JSON value.
+pub enum JsonRef<'a, V: JsonLike> {
+    Null,
+    Boolean(bool),
+    Number(JsonNumber),
+    String(&'a str),
+    Array(&'a [V]),
+    Object(&'a V::Map),
+}
+
+/// JSON object key.
+pub trait JsonKey: Clone + PartialOrd {
+    fn as_str(&self) -> &str;
+
+    fn from_string(key: String) -> Self;
+}
+
+/// JSON value representation.
+pub trait JsonLike: Clone + PartialEq + TransformedValue<Object = Self::Map> {
+    type Map: TransformedObject<Key = Self::Key, Value = Self>;
+    type Key: JsonKey;
+
+    fn view(&self) -> JsonRef<'_, Self>;
+
+    fn as_str(&self) -> Option<&str> {
+        match self.view() {
+            JsonRef::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    fn null() -> Self;
+
+    fn boolean(b: bool) -> Self;
+
+    /// Creates an integer number.
+    ///
+    /// Representations that cannot hold the given integer exactly may fall
+    /// back to the closest float.
+    fn integer(n: i128) -> Self;
+
+    /// Creates a float number, or returns `None` if the float is not finite.
+    fn float(f: f64) -> Option<Self>;
+
+    fn string(s: String) -> Self;
+
+    /// Returns this value as a [`JsonValue`], used by the JSON literal codec.
+    fn to_json_value(&self) -> Cow<'_, JsonValue>;
+
+    /// Converts a [`JsonValue`] produced by a codec or table.
+    fn from_json_value(value: JsonValue) -> Self;
+}
+
+impl JsonKey for json_ld::syntax::object::Key {
+    fn as_str(&self) -> &str {
+        self.as_str()
+    }
+
+    fn from_string(key: String) -> Self {
+        key.into()
+    }
+}
+
+impl JsonLike for JsonValue {
+    type Map = JsonObject;
+    type Key = json_ld::syntax::object::Key;
+
+    fn view(&self) -> JsonRef<'_, Self> {
+        match self {
+            Self::Null => JsonRef::Null,
+            Self::Boolean(b) => JsonRef::Boolean(*b),
+            Self::Number(n) => JsonRef::Number(match n.as_u64() {
+                Some(u) => JsonNumber::Unsigned(u),
+                None => match n.as_i64() {
+                    Some(i) => JsonNumber::Signed(i),
+                    None => JsonNumber::Float(n.as_f64_lossy()),
+                },
+            }),
+            Self::String(s) => JsonRef::String(s),
+            Self::Array(a) => JsonRef::Array(a),
+            Self::Object(o) => JsonRef::Object(o),
+        }
+    }
+
+    fn null() -> Self {
+        Self::Null
+    }
+
+    fn boolean(b: bool) -> Self {
+        Self::Boolean(b)
+    }
+
+    fn integer(n: i128) -> Self {
+        Self::Number(n.to_string().parse().unwrap())
+    }
+
+    fn float(f: f64) -> Option<Self> {
+        f.try_into().ok().map(Self::Number)
+    }
+
+    fn string(s: String) -> Self {
+        Self::String(s.into())
+    }
+
+    fn to_json_value(&self) -> Cow<'_, JsonValue> {
+        Cow::Borrowed(self)
+    }
+
+    fn from_json_value(value: JsonValue) -> Self {
+        value
+    }
+}
+
+#[cfg(feature = "serde_json")]
+mod serde_json_impl {
+    use std::borrow::Cow;
+
+    use super::{JsonKey, JsonLike, JsonNumber, JsonRef};
+    use crate::{
+        transform::{DuplicateKey, TransformedObject, TransformedValue},
+        JsonValue,
+    };
+
+    impl JsonKey for String {
+        fn as_str(&self) -> &str {
+            self
+        }
+
+        fn from_string(key: String) -> Self {
+            key
+        }
+    }
+
+    impl TransformedValue for serde_json::Value {
+        type Object = serde_json::Map<String, Self>;
+
+        fn new_array(items: Vec<Self>) -> Self {
+            Self::Array(items)
+        }
+
+        fn new_object(object: serde_json::Map<String, Self>) -> Self {
+            Self::Object(object)
+        }
+
+        fn as_array(&self) -> Option<&[Self]> {
+            self.as_array().map(Vec::as_slice)
+        }
+
+        fn as_object(&self) -> Option<&serde_json::Map<String, Self>> {
+            self.as_object()
+        }
+
+        fn is_array(&self) -> bool {
+            self.is_array()
+        }
+    }
+
+    impl TransformedObject for serde_json::Map<String, serde_json::Value> {
+        type Key = String;
+        type Value = serde_json::Value;
+
+        fn new(entries: Vec<(Self::Key, Self::Value)>) -> Self {
+            entries.into_iter().collect()
+        }
+
+        fn get_context(&self) -> Result<Option<&Self::Value>, DuplicateKey<Self::Key>> {
+            // Keys are unique in a `serde_json` map.
+            Ok(self.get("@context"))
+        }
+
+        fn entries(&self) -> impl Iterator<Item = (&Self::Key, &Self::Value)> {
+            self.iter()
+        }
+    }
+
+    impl JsonLike for serde_json::Value {
+        type Map = serde_json::Map<String, Self>;
+        type Key = String;
+
+        fn view(&self) -> JsonRef<'_, Self> {
+            match self {
+                Self::Null => JsonRef::Null,
+                Self::Bool(b) => JsonRef::Boolean(*b),
+                Self::Number(n) => JsonRef::Number(match n.as_u64() {
+                    Some(u) => JsonNumber::Unsigned(u),
+                    None => match n.as_i64() {
+                        Some(i) => JsonNumber::Signed(i),
+                        None => JsonNumber::Float(n.as_f64().unwrap_or(f64::NAN)),
+                    },
+                }),
+                Self::String(s) => JsonRef::String(s),
+                Self::Array(a) => JsonRef::Array(a),
+                Self::Object(o) => JsonRef::Object(o),
+            }
+        }
+
+        fn null() -> Self {
+            Self::Null
+        }
+
+        fn boolean(b: bool) -> Self {
+            Self::Bool(b)
+        }
+
+        fn integer(n: i128) -> Self {
+            match u64::try_from(n) {
+                Ok(u) => u.into(),
+                Err(_) => match i64::try_from(n) {
+                    Ok(i) => i.into(),
+                    Err(_) => (n as f64).into(),
+                },
+            }
+        }
+
+        fn float(f: f64) -> Option<Self> {
+            serde_json::Number::from_f64(f).map(Self::Number)
+        }
+
+        fn string(s: String) -> Self {
+            Self::String(s)
+        }
+
+        fn to_json_value(&self) -> Cow<'_, JsonValue> {
+            Cow::Owned(to_json_value(self))
+        }
+
+        fn from_json_value(value: JsonValue) -> Self {
+            from_json_value(value)
+        }
+    }
+
+    fn to_json_value(value: &serde_json::Value) -> JsonValue {
+        match value.view() {
+            JsonRef::Null => JsonValue::Null,
+            JsonRef::Boolean(b) => JsonValue::Boolean(b),
+            JsonRef::Number(JsonNumber::Unsigned(u)) => JsonValue::Number(u.into()),
+            JsonRef::Number(JsonNumber::Signed(i)) => JsonValue::Number(i.into()),
+            JsonRef::Number(JsonNumber::Float(f)) => match value {
+                // Keep the `serde_json` form of floats, so that `1.0` is not
+                // turned into an integer.
+                serde_json::Value::Number(n) => JsonValue::Number(n.to_string().parse().unwrap()),
+                // `serde_json` numbers are always finite.
+                _ => JsonValue::Number(f.try_into().unwrap()),
+            },
+            JsonRef::String(s) => JsonValue::String(s.into()),
+            JsonRef::Array(a) => JsonValue::Array(a.iter().map(to_json_value).collect()),
+            JsonRef::Object(o) => JsonValue::Object(
+                o.iter()
+                    .map(|(key, value)| (key.as_str().into(), to_json_value(value)))
+                    .collect(),
+            ),
+        }
+    }
+
+    fn from_json_value(value: JsonValue) -> serde_json::Value {
+        match value {
+            JsonValue::Null => serde_json::Value::Null,
+            JsonValue::Boolean(b) => serde_json::Value::Bool(b),
+            // Numbers such as `1.0` are kept as floats.
+            JsonValue::Number(n) => match n.as_u64() {
+                Some(u) if u.to_string() == n.as_str() => u.into(),
+                _ => match n.as_i64() {
+                    Some(i) if i.to_string() == n.as_str() => i.into(),
+                    _ => n.as_f64_lossy().into(),
+                },
+            },
+            JsonValue::String(s) => serde_json::Value::String(s.into_string()),
+            JsonValue::Array(a) => a.into_iter().map(from_json_value).collect(),
+            JsonValue::Object(o) => serde_json::Value::Object(
+                o.into_iter()
+                    .map(|entry| (entry.key.into_string(), from_json_value(entry.value)))
+                    .collect(),
+            ),
+        }
+    }
+}
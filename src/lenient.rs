@@ -0,0 +1,87 @@
+//! Lenient mode.
+//!
+//! By default, encoding fails as soon as a key is not defined by the loaded
+//! contexts, or a value cannot be handled by its codec. In lenient mode
+//! ([`EncodeOptions::lenient`]), such keys are kept as CBOR text keys and
+//! their values are left as-is, without term substitution, the same way JSON
+//! literals are. Values that no codec can handle and inline context
+//! definitions are left as-is too. Type entries holding values that are not
+//! terms or IRIs are kept as text keys, so that their values are not mistaken
+//! for term IDs when decoding.
+//!
+//! The decoder always accepts inline context definitions and maps mixing
+//! compressed (integer) and text keys, decoding the values of text keys
+//! as-is. In lenient mode ([`DecodeOptions::lenient`]), it also leaves the
+//! values its codecs cannot handle as-is.
+//!
+//! Each fallback is reported as a [`Warning`], collected in the [`Warnings`]
+//! given in the options, or logged otherwise.
+//!
+//! [`EncodeOptions::lenient`]: crate::EncodeOptions::lenient
+//! [`DecodeOptions::lenient`]: crate::DecodeOptions::lenient
+use std::{fmt, sync::Mutex};
+
+use crate::transform::ErrorLocation;
+
+/// Lenient mode fallback.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WarningKind {
+    /// Term kept as a text key, its value left as-is.
+    UncompressedTerm(String),
+
+    /// Value left as-is, because it could not be transformed.
+    UnhandledValue(String),
+
+    /// Inline context definition left as-is.
+    InlineContext,
+}
+
+impl fmt::Display for WarningKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::UncompressedTerm(term) => write!(f, "term `{term}` kept uncompressed"),
+            Self::UnhandledValue(reason) => write!(f, "value left as-is: {reason}"),
+            Self::InlineContext => write!(f, "inline context kept uncompressed"),
+        }
+    }
+}
+
+/// Lenient mode fallback, with its location in the input document.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Warning {
+    pub kind: WarningKind,
+    pub location: ErrorLocation,
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} ({})", self.kind, self.location)
+    }
+}
+
+/// Warnings collector, shared between calls.
+#[derive(Debug, Default)]
+pub struct Warnings(Mutex<Vec<Warning>>);
+
+impl Warnings {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&self, warning: Warning) {
+        self.0.lock().unwrap().push(warning)
+    }
+
+    /// Removes and returns the collected warnings.
+    pub fn take(&self) -> Vec<Warning> {
+        std::mem::take(&mut *self.0.lock().unwrap())
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
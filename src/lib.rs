@@ -23,12 +23,141 @@
 //! let decoded: cbor_ld::JsonValue = cbor_ld::decode(&encoded, &mut context_loader).await.unwrap();
 //!
 //! // The input and decoded JSON values should be equal
-//! // (modulo objects entries ordering and some compact IRI expansions).
+//! // (modulo objects entries ordering and some compact IRI expansions,
+//! // unless the document is encoded in lossless mode).
 //! use json_syntax::BorrowUnordered;
 //! assert_eq!(json.as_unordered(), decoded.as_unordered())
 //! # }
 //! ```
 //!
+//! # Synchronous API
+//!
+//! The `encode_sync` and `decode_sync` functions (and their variants) do not
+//! need any async runtime. They take a [`SyncLoader`] instead, such as a
+//! preloaded context store or a file-system loader.
+//!
+//! ```
+//! use static_iref::iri;
+//!
+//! let json: cbor_ld::JsonValue = include_str!("../tests/samples/note.jsonld").parse().unwrap();
+//!
+//! // Create a synchronous JSON-LD context loader.
+//! let mut context_loader = json_ld::FsLoader::new();
+//! context_loader.mount(
+//!   iri!("https://www.w3.org/ns/activitystreams").to_owned(),
+//!   "tests/contexts/activitystreams"
+//! );
+//!
+//! let encoded = cbor_ld::encode_sync(&json, &context_loader).unwrap();
+//! let decoded = cbor_ld::decode_sync(&encoded, &context_loader).unwrap();
+//!
+//! use json_syntax::BorrowUnordered;
+//! assert_eq!(json.as_unordered(), decoded.as_unordered())
+//! ```
+//!
+//! The `tokio` dependency is only required by the command-line interface.
+//!
+//! The `bundled-contexts` feature provides a `BundledLoader` serving well-known
+//! contexts compiled into the library, each checked against a pinned context
+//! digest (the same format as the context pins), so that documents using only
+//! these contexts can be processed offline without any setup.
+//!
+//! # Serde
+//!
+//! [`to_vec`] and [`from_slice`] encode any [`serde::Serialize`] value to
+//! CBOR-LD bytes and decode them back into any
+//! [`serde::de::DeserializeOwned`] type. The value goes through a JSON tree,
+//! but not through JSON text.
+//!
+//! With the `serde_json` feature, `encode_serde_json` and `decode_serde_json`
+//! accept and return `serde_json::Value` documents directly. Any other JSON
+//! representation can be used with the [`Encoder`] and [`Decoder`] by
+//! implementing [`json::JsonLike`].
+//!
+//! # Lossless mode
+//!
+//! Setting [`EncodeOptions::lossless_tag`] records the original entry order
+//! and compact IRI spelling in a side channel stored next to the document
+//! body, wrapped in a CBOR tag chosen by the application, so that decoding
+//! restores the exact input document. This is needed for documents signed
+//! over their JSON text (JWS, JCS). Decoding applies the side channel when
+//! given the same tag with [`DecodeOptions::lossless_tag`]. On the command
+//! line, use `cbor-ld encode --lossless <TAG>` and
+//! `cbor-ld decode --lossless <TAG>`. See the [`lossless`] module.
+//!
+//! # Lenient mode
+//!
+//! Setting [`EncodeOptions::lenient`] keeps the terms undefined by the loaded
+//! contexts as CBOR text keys and leaves the values that no codec can handle
+//! as-is, instead of failing. The decoder accepts such documents, and each
+//! fallback is reported as a warning. See the [`lenient`] module.
+//!
+//! # Expanded and RDF output
+//!
+//! [`decode_expanded`] and [`decode_to_rdf`] decode a document into expanded
+//! JSON-LD or an RDF dataset (printed in N-Quads syntax). The document is
+//! expanded while decoding, from the active context of each decoded entry,
+//! without a second JSON-LD processing pass.
+//!
+//! Conversely, [`encode_expanded`] and [`encode_rdf`] encode an expanded JSON-LD
+//! document or an RDF dataset (parsed from N-Quads) given a list of target
+//! contexts: the input is compacted with these contexts first, then encoded.
+//!
+//! # Compression statistics
+//!
+//! [`stats::analyze`] encodes a document and reports its size per entry
+//! (top-level properties and nested paths), the codec or table that handled
+//! each value and the strings left uncompressed, to help tune compression
+//! tables. With the `stats` feature, it also compares the result with the gzip
+//! and brotli compression of the JSON document. See the [`stats`] module.
+//!
+//! # Compression tables generation
+//!
+//! [`tables::generate_tables`] proposes compression tables for a corpus of
+//! documents: IDs for all the contexts found, and type tables for the enumerated values of
+//! datatypes without codec, along with an estimate of the size savings.
+//! [`Tables`] can be serialized and deserialized with serde, and are loaded
+//! from TOML files by the command-line interface.
+//!
+//! # Round-trip verification
+//!
+//! [`verify::verify_round_trip`] encodes and decodes a document, expands both
+//! the input and the decoded document to RDF, and reports the quads that differ
+//! between the two datasets. See the [`verify`] module.
+//!
+//! # Error locations
+//!
+//! Errors raised while transforming a document carry the location of the
+//! faulty value, returned by [`EncodeError::location`] and
+//! [`DecodeError::location`]: a JSON pointer (RFC 6901) into the input document
+//! when encoding, a CBOR path such as `$.100[0].104` when decoding, along with
+//! the active term. The command-line interface prints both.
+//!
+//! # Diagnostic notation
+//!
+//! [`diagnostic::Edn`] prints and parses CBOR diagnostic notation (RFC 8949
+//! §8), keeping encoding indicators and indefinite lengths, so that test
+//! vectors can be written in diagnostic notation. [`diagnostic::annotations`]
+//! names the term and context IDs of a CBOR-LD document and the codec or table
+//! that handled each value, shown as comments by
+//! [`diagnostic::Edn::annotated`].
+//!
+//! # Sessions
+//!
+//! Services processing many documents should create a single [`CborLd`]
+//! session holding the context loader, codecs, tables and processed context
+//! cache, and share it between tasks. Its `encode` and `decode` methods only
+//! borrow the session and can be called concurrently.
+//!
+//! # Streaming
+//!
+//! [`encode_stream`] and [`decode_stream`] read a document from any
+//! [`std::io::Read`] and write the result incrementally to any
+//! [`std::io::Write`], without building the input or output tree of the
+//! whole document. The input is read as bytes, and the items of the root node
+//! arrays of node objects (such as the items of a large collection) are
+//! parsed one at a time. See the [`stream`] module.
+//!
 //! # Command-line interface
 //!
 //! A command-line interface is provided to easily encode and decode CBOR-LD
@@ -73,10 +202,75 @@
 //! cbor-ld decode -x path/to/input.cbor.hex > path/to/output.jsonld
 //! ```
 //!
-//! By default remote JSON-LD contexts will be fetched online. You can change
-//! this behavior by adding file-system endpoints for some URLs using the
-//! `-m` (`--mount`) option, and/or disable HTTP queries alltogether using the
-//! `-o` (`--offline`) flag.
+//! The `-l` (`--lines`) flag reads and writes JSON Lines, one document per
+//! line, encoded as a CBOR sequence (RFC 8742). Add `-s` (`--share`) so that
+//! documents share term and context IDs, on both ends. Its value is the CBOR
+//! tag, chosen by the application, recording the position of each document
+//! in the sequence:
+//! ```console
+//! cbor-ld encode -l -s 40961 path/to/input.jsonl > path/to/output.cbor
+//! cbor-ld decode -l -s 40961 path/to/output.cbor > path/to/input.jsonl
+//! ```
+//!
+//! The `-e` (`--expanded`) and `-n` (`--nquads`) decoding flags output the
+//! expanded JSON-LD document or its RDF dataset instead:
+//! ```console
+//! cbor-ld decode -n path/to/input.cbor > path/to/output.nq
+//! ```
+//!
+//! The same flags on the `encode` command read expanded JSON-LD or N-Quads,
+//! along with the target contexts given with `-c` (`--context`):
+//! ```console
+//! cbor-ld encode -n -c https://www.w3.org/ns/credentials/v2 path/to/input.nq > path/to/output.cbor
+//! ```
+//!
+//! The `--lenient` flag enables the lenient mode, printing warnings for each
+//! fallback:
+//! ```console
+//! cbor-ld encode --lenient path/to/input.jsonld > path/to/output.cbor
+//! ```
+//!
+//! The `verify` command checks that a document survives a round trip with the
+//! same RDF meaning, printing the missing (`-`) and unexpected (`+`) quads:
+//! ```console
+//! cbor-ld verify path/to/input.jsonld
+//! ```
+//!
+//! The `stats` command prints the size of the encoded document per entry,
+//! along with the codec or table handling each value and the strings left
+//! uncompressed:
+//! ```console
+//! cbor-ld stats path/to/input.jsonld
+//! ```
+//!
+//! The `inspect` command prints a CBOR-LD document in diagnostic notation,
+//! with the term or context IRI of each ID, the codec or table that handled
+//! each value, and the byte offset and length of each entry as comments:
+//! ```console
+//! cbor-ld inspect -x path/to/input.cbor.hex
+//! ```
+//!
+//! The `tables generate` command proposes compression tables for a corpus of
+//! documents, written in TOML. The `--tables` option loads such a file as the
+//! default compression tables:
+//! ```console
+//! cbor-ld tables generate corpus/*.jsonld > path/to/tables.toml
+//! cbor-ld --tables path/to/tables.toml encode path/to/input.jsonld > path/to/output.cbor
+//! ```
+//!
+//! Tables with a string dictionary (`strings`) must instead be bound to an
+//! application registry entry with the `--registry-entry` option, so that
+//! decoders without the dictionary reject the document:
+//! ```console
+//! cbor-ld --tables path/to/tables.toml --registry-entry 64 encode path/to/input.jsonld > path/to/output.cbor
+//! ```
+//!
+//! Well-known contexts (credentials v1/v2, DID v1, Multikey, data integrity
+//! v1/v2, the Ed25519 2020 security suite, vc-barcodes and ActivityStreams)
+//! are bundled with the executable. Other remote JSON-LD contexts will be
+//! fetched online by default. You can change this behavior by adding
+//! file-system endpoints for some URLs using the `-m` (`--mount`) option,
+//! and/or disable HTTP queries alltogether using the `-o` (`--offline`) flag.
 //! ```console
 //! cbor-ld --offline -m "https://www.w3.org/ns/credentials=tests/contexts/credentials" decode path/to/input.cbor > path/to/output.jsonld
 //! ```
@@ -94,21 +288,35 @@ pub use json_ld::syntax::Value as JsonValue;
 pub type CborObject = Vec<(CborValue, CborValue)>;
 pub type JsonObject = json_ld::syntax::Object;
 
+pub mod cache;
 pub mod tables;
 pub use tables::{ContextTable, Tables, TypeTable};
 pub mod codecs;
 mod decode;
 mod encode;
+pub mod json;
 pub mod keywords;
+pub mod lenient;
+pub mod loader;
+pub mod lossless;
+pub mod rdf;
+pub use rdf::{RdfDataset, RdfQuad};
 pub mod utils;
 pub use decode::*;
 pub use encode::*;
 pub mod diagnostic;
 mod id;
+pub mod references;
+mod session;
+pub mod stats;
+pub mod stream;
+pub use session::*;
 pub mod transform;
+pub mod verify;
 
 pub use codecs::Codecs;
 pub use id::*;
+pub use loader::SyncLoader;
 
 /// First byte value of the 2-byte tag announcing CBOR-LD.
 pub const CBOR_LD_TAG_HIGH: u8 = 0x06;
@@ -133,6 +341,20 @@ impl CompressionMode {
             Self::Compressed(t) => t.id(),
         }
     }
+
+    /// CBOR-LD tag announcing this compression mode.
+    ///
+    /// Returns `None` if the compression mode identifier does not fit in the
+    /// tag (128 or more), since the varint form is not supported.
+    pub fn tag(&self) -> Option<u64> {
+        let id = self.id();
+
+        if id < 128 {
+            Some((CBOR_LD_TAG_HIGH as u64) << 8 | id)
+        } else {
+            None
+        }
+    }
 }
 
 impl Default for CompressionMode {
@@ -0,0 +1,178 @@
+//! Bundled well-known JSON-LD contexts.
+use std::collections::HashMap;
+
+use iref::{Iri, IriBuf};
+use json_ld::{syntax::Parse, LoadError, LoadingResult, RemoteDocument};
+use lazy_static::lazy_static;
+use static_iref::iri;
+
+use super::{ContextDigest, SyncLoader};
+
+/// JSON-LD context compiled into the library.
+pub struct BundledContext {
+    /// Context URL.
+    pub url: &'static Iri,
+
+    /// Context document.
+    pub content: &'static str,
+
+    /// Pinned digest of `content`, see [`ContextDigest`].
+    pub digest: &'static str,
+}
+
+impl BundledContext {
+    /// Parses the content, checking that it matches the pinned digest.
+    pub fn document(&self) -> Result<json_ld::syntax::Value, BundledContextError> {
+        let (value, _) = json_ld::syntax::Value::parse_str(self.content)
+            .map_err(|e| BundledContextError::Parse(e.to_string()))?;
+
+        let expected: ContextDigest = self
+            .digest
+            .parse()
+            .map_err(|_| BundledContextError::DigestMismatch)?;
+
+        if ContextDigest::of(&value) == expected {
+            Ok(value)
+        } else {
+            Err(BundledContextError::DigestMismatch)
+        }
+    }
+
+    /// Checks that the content matches the pinned digest.
+    pub fn verify(&self) -> bool {
+        self.document().is_ok()
+    }
+}
+
+/// Contexts compiled into the library.
+///
+/// Copies of the published documents are stored in the `contexts` directory
+/// of the crate, by host and path.
+pub const BUNDLED_CONTEXTS: [BundledContext; 9] = [
+    BundledContext {
+        url: iri!("https://www.w3.org/2018/credentials/v1"),
+        content: include_str!("../../contexts/www.w3.org/2018/credentials/v1"),
+        digest: "uEiCwHmcehzmB8ZqRAqmlf2ZtvK6zG5nj4SQ3jRQ-cVSSRw",
+    },
+    BundledContext {
+        url: iri!("https://www.w3.org/ns/credentials/v2"),
+        content: include_str!("../../contexts/www.w3.org/ns/credentials/v2"),
+        digest: "uEiD8I168xafTIsYbANNdtYU1iFGepNVTjwG7kcQeM7UhUw",
+    },
+    BundledContext {
+        url: iri!("https://www.w3.org/ns/did/v1"),
+        content: include_str!("../../contexts/www.w3.org/ns/did/v1"),
+        digest: "uEiCn4UbED87ABdPw8UrnRWZfRG2ljXkUqdj3M3RmhvrYKw",
+    },
+    BundledContext {
+        url: iri!("https://w3id.org/security/multikey/v1"),
+        content: include_str!("../../contexts/w3id.org/security/multikey/v1"),
+        digest: "uEiC-IMPuavbpxBAuZqhcJmF4MSGOMiFr3wUPhw44nnN83A",
+    },
+    BundledContext {
+        url: iri!("https://w3id.org/security/data-integrity/v1"),
+        content: include_str!("../../contexts/w3id.org/security/data-integrity/v1"),
+        digest: "uEiCVBb-FM4pMISGtA5kqyQBhvIXUO8-jTZ1Ht3k4HPCLXw",
+    },
+    BundledContext {
+        url: iri!("https://w3id.org/security/data-integrity/v2"),
+        content: include_str!("../../contexts/w3id.org/security/data-integrity/v2"),
+        digest: "uEiD5ej2CdRdFEkxgQW91FC4Q4Jw3qRPRUXh3JTGcmxUuqg",
+    },
+    BundledContext {
+        url: iri!("https://w3id.org/security/suites/ed25519-2020/v1"),
+        content: include_str!("../../contexts/w3id.org/security/suites/ed25519-2020/v1"),
+        digest: "uEiD7UX8J2ZCCmu1zTJutjLuyrD1QY7hlx-PYHyRgdL9WkQ",
+    },
+    BundledContext {
+        url: iri!("https://w3id.org/vc-barcodes/v1"),
+        content: include_str!("../../contexts/w3id.org/vc-barcodes/v1"),
+        digest: "uEiAnH5FHQi-4wFt6lvbtICd1Nq-TiDVvPrQTaNirs3nzGw",
+    },
+    BundledContext {
+        url: iri!("https://www.w3.org/ns/activitystreams"),
+        content: include_str!("../../contexts/www.w3.org/ns/activitystreams"),
+        digest: "uEiBav2cj39hxs-d7Us6PpKqG9odrfpNZXlVTEbILO-JZ5Q",
+    },
+];
+
+#[derive(Debug, Clone, thiserror::Error)]
+pub enum BundledContextError {
+    #[error("context is not bundled")]
+    NotBundled,
+
+    #[error("bundled context does not match its pinned digest")]
+    DigestMismatch,
+
+    #[error("invalid bundled context: {0}")]
+    Parse(String),
+}
+
+lazy_static! {
+    static ref BUNDLED_DOCUMENTS: HashMap<&'static Iri, Result<RemoteDocument, BundledContextError>> =
+        BUNDLED_CONTEXTS
+            .iter()
+            .map(|context| {
+                let document = context.document().map(|value| {
+                    RemoteDocument::new(
+                        Some(context.url.to_owned()),
+                        Some("application/ld+json".parse().unwrap()),
+                        value,
+                    )
+                });
+
+                (context.url, document)
+            })
+            .collect();
+}
+
+/// Loader serving the [`BUNDLED_CONTEXTS`], without any network access.
+///
+/// Fails to load any other context. Use a [`json_ld::ChainLoader`] to fall
+/// back to another loader.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct BundledLoader;
+
+impl SyncLoader for BundledLoader {
+    fn load_sync(&self, url: &Iri) -> LoadingResult<IriBuf> {
+        match BUNDLED_DOCUMENTS.get(url) {
+            Some(Ok(document)) => Ok(document.clone()),
+            Some(Err(e)) => Err(LoadError::new(url.to_owned(), e.clone())),
+            None => Err(LoadError::new(
+                url.to_owned(),
+                BundledContextError::NotBundled,
+            )),
+        }
+    }
+}
+
+impl json_ld::Loader for BundledLoader {
+    async fn load(&self, url: &Iri) -> LoadingResult<IriBuf> {
+        self.load_sync(url)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BundledLoader, BUNDLED_CONTEXTS};
+    use crate::SyncLoader;
+    use static_iref::iri;
+
+    #[test]
+    fn pinned_digests() {
+        for context in &BUNDLED_CONTEXTS {
+            assert!(context.verify(), "{}", context.url)
+        }
+    }
+
+    #[test]
+    fn load() {
+        for context in &BUNDLED_CONTEXTS {
+            assert!(BundledLoader.load_sync(context.url).is_ok())
+        }
+
+        assert!(BundledLoader
+            .load_sync(iri!("https://example.org/unknown"))
+            .is_err())
+    }
+}
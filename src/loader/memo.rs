@@ -0,0 +1,46 @@
+use std::{collections::HashMap, sync::Mutex};
+
+use iref::{Iri, IriBuf};
+use json_ld::{LoadingResult, RemoteDocument};
+
+/// Loader keeping the documents it loads.
+///
+/// Later passes over the same document (expansion, RDF conversion) reuse the
+/// contexts loaded by the first pass instead of loading them again.
+pub struct MemoLoader<L> {
+    loader: L,
+    documents: Mutex<HashMap<IriBuf, RemoteDocument>>,
+}
+
+impl<L> MemoLoader<L> {
+    pub fn new(loader: L) -> Self {
+        Self {
+            loader,
+            documents: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Number of documents loaded so far.
+    pub fn len(&self) -> usize {
+        self.documents.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl<L: json_ld::Loader> json_ld::Loader for MemoLoader<L> {
+    async fn load(&self, url: &Iri) -> LoadingResult<IriBuf> {
+        if let Some(document) = self.documents.lock().unwrap().get(url) {
+            return Ok(document.clone());
+        }
+
+        let document = self.loader.load(url).await?;
+        self.documents
+            .lock()
+            .unwrap()
+            .insert(url.to_owned(), document.clone());
+        Ok(document)
+    }
+}
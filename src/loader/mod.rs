@@ -0,0 +1,99 @@
+//! JSON-LD context loaders.
+use std::{
+    collections::{BTreeMap, HashMap},
+    future::Future,
+    pin::pin,
+    sync::Arc,
+    task::{Context, Poll, Wake, Waker},
+    thread::{self, Thread},
+};
+
+use iref::{Iri, IriBuf};
+use json_ld::{FsLoader, LoadingResult, NoLoader, RemoteDocument};
+
+mod memo;
+pub use memo::*;
+
+mod pinned;
+pub use pinned::*;
+
+#[cfg(feature = "bundled-contexts")]
+mod bundled;
+#[cfg(feature = "bundled-contexts")]
+pub use bundled::*;
+
+/// Synchronous JSON-LD context loader.
+///
+/// Used by the blocking entry points ([`encode_sync`], [`decode_sync`], etc.)
+/// that do not require an async runtime.
+///
+/// [`encode_sync`]: crate::encode_sync
+/// [`decode_sync`]: crate::decode_sync
+pub trait SyncLoader {
+    fn load_sync(&self, url: &Iri) -> LoadingResult<IriBuf>;
+}
+
+impl<L: SyncLoader> SyncLoader for &L {
+    fn load_sync(&self, url: &Iri) -> LoadingResult<IriBuf> {
+        L::load_sync(*self, url)
+    }
+}
+
+impl SyncLoader for HashMap<IriBuf, RemoteDocument> {
+    fn load_sync(&self, url: &Iri) -> LoadingResult<IriBuf> {
+        block_on(json_ld::Loader::load(self, url))
+    }
+}
+
+impl SyncLoader for BTreeMap<IriBuf, RemoteDocument> {
+    fn load_sync(&self, url: &Iri) -> LoadingResult<IriBuf> {
+        block_on(json_ld::Loader::load(self, url))
+    }
+}
+
+impl SyncLoader for FsLoader {
+    /// Reads the context file, blocking the current thread.
+    fn load_sync(&self, url: &Iri) -> LoadingResult<IriBuf> {
+        block_on(json_ld::Loader::load(self, url))
+    }
+}
+
+impl SyncLoader for NoLoader {
+    fn load_sync(&self, url: &Iri) -> LoadingResult<IriBuf> {
+        block_on(json_ld::Loader::load(self, url))
+    }
+}
+
+/// Adapts a [`SyncLoader`] into a [`json_ld::Loader`].
+pub struct Blocking<L>(pub L);
+
+impl<L: SyncLoader> json_ld::Loader for Blocking<L> {
+    async fn load(&self, url: &Iri) -> LoadingResult<IriBuf> {
+        self.0.load_sync(url)
+    }
+}
+
+struct ThreadWaker(Thread);
+
+impl Wake for ThreadWaker {
+    fn wake(self: Arc<Self>) {
+        self.0.unpark()
+    }
+}
+
+/// Runs the given future to completion on the current thread.
+///
+/// The encoder and decoder only await their context loader, so with a
+/// [`Blocking`] loader their futures complete on the first poll.
+pub(crate) fn block_on<F: Future>(future: F) -> F::Output {
+    let mut future = pin!(future);
+    let waker = Waker::from(Arc::new(ThreadWaker(thread::current())));
+    let mut context = Context::from_waker(&waker);
+
+    loop {
+        match future.as_mut().poll(&mut context) {
+            Poll::Ready(output) => break output,
+            Poll::Pending => thread::park(),
+        }
+    }
+}
@@ -0,0 +1,168 @@
+//! Context integrity pinning.
+use std::{collections::HashMap, fmt, str::FromStr, sync::Arc};
+
+use iref::{Iri, IriBuf};
+use json_ld::{LoadError, LoadingResult};
+use sha2::{Digest, Sha256};
+
+use crate::JsonValue;
+
+/// SHA-256 multihash code.
+const SHA2_256: u8 = 0x12;
+
+/// SHA-256 digest of a JSON-LD context document.
+///
+/// The digest is computed over the canonical form (RFC 8785) of the context
+/// document, so that it does not depend on formatting. It is displayed and
+/// parsed as a multibase-encoded (base64url by default) multihash.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ContextDigest(pub [u8; 32]);
+
+impl ContextDigest {
+    /// Computes the digest of the given context document.
+    pub fn of(document: &JsonValue) -> Self {
+        let mut document = document.clone();
+        document.canonicalize();
+        Self(Sha256::digest(document.to_string().as_bytes()).into())
+    }
+
+    pub fn from_multihash(bytes: &[u8]) -> Result<Self, InvalidContextDigest> {
+        match bytes {
+            [SHA2_256, 32, digest @ ..] => {
+                Ok(Self(digest.try_into().map_err(|_| InvalidContextDigest)?))
+            }
+            _ => Err(InvalidContextDigest),
+        }
+    }
+
+    pub fn to_multihash(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(34);
+        bytes.push(SHA2_256);
+        bytes.push(32);
+        bytes.extend_from_slice(&self.0);
+        bytes
+    }
+}
+
+impl fmt::Display for ContextDigest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        multibase::encode(multibase::Base::Base64Url, self.to_multihash()).fmt(f)
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("invalid context digest")]
+pub struct InvalidContextDigest;
+
+impl FromStr for ContextDigest {
+    type Err = InvalidContextDigest;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (_, bytes) = multibase::decode(s).map_err(|_| InvalidContextDigest)?;
+        Self::from_multihash(&bytes)
+    }
+}
+
+/// Pinned context digests, by context URL.
+///
+/// Cheap to clone: the digests are shared until modified.
+#[derive(Debug, Default, Clone)]
+pub struct ContextPins(Arc<HashMap<IriBuf, ContextDigest>>);
+
+impl ContextPins {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn get(&self, url: &Iri) -> Option<&ContextDigest> {
+        self.0.get(url)
+    }
+
+    pub fn insert(&mut self, url: IriBuf, digest: ContextDigest) {
+        Arc::make_mut(&mut self.0).insert(url, digest);
+    }
+}
+
+impl FromIterator<(IriBuf, ContextDigest)> for ContextPins {
+    fn from_iter<T: IntoIterator<Item = (IriBuf, ContextDigest)>>(iter: T) -> Self {
+        Self(Arc::new(iter.into_iter().collect()))
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("context digest mismatch: expected `{expected}`, found `{found}`")]
+pub struct ContextDigestMismatch {
+    pub expected: ContextDigest,
+    pub found: ContextDigest,
+}
+
+/// Loader checking the digest of pinned contexts.
+///
+/// Loading a pinned context fails with [`ContextDigestMismatch`] if its
+/// digest differs from the pinned one. Other contexts are loaded unchecked.
+pub struct PinnedLoader<L> {
+    loader: L,
+    pins: ContextPins,
+}
+
+impl<L> PinnedLoader<L> {
+    pub fn new(loader: L, pins: ContextPins) -> Self {
+        Self { loader, pins }
+    }
+
+    pub fn pins(&self) -> &ContextPins {
+        &self.pins
+    }
+
+    pub fn pins_mut(&mut self) -> &mut ContextPins {
+        &mut self.pins
+    }
+}
+
+impl<L: json_ld::Loader> json_ld::Loader for PinnedLoader<L> {
+    async fn load(&self, url: &Iri) -> LoadingResult<IriBuf> {
+        let document = self.loader.load(url).await?;
+
+        if let Some(expected) = self.pins.get(url) {
+            let found = ContextDigest::of(document.document());
+            if found != *expected {
+                return Err(LoadError::new(
+                    url.to_owned(),
+                    ContextDigestMismatch {
+                        expected: *expected,
+                        found,
+                    },
+                ));
+            }
+        }
+
+        Ok(document)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ContextDigest;
+    use crate::JsonValue;
+
+    #[test]
+    fn canonical_digest() {
+        let a: JsonValue = r#"{"@context": {"b": 1.0, "a": "x"}}"#.parse().unwrap();
+        let b: JsonValue = r#"{ "@context":{"a":"x","b":1} }"#.parse().unwrap();
+        let c: JsonValue = r#"{"@context": {"a": "y", "b": 1}}"#.parse().unwrap();
+        assert_eq!(ContextDigest::of(&a), ContextDigest::of(&b));
+        assert_ne!(ContextDigest::of(&a), ContextDigest::of(&c))
+    }
+
+    #[test]
+    fn multibase_round_trip() {
+        let digest = ContextDigest([42; 32]);
+        let encoded = digest.to_string();
+        assert!(encoded.starts_with("uEiA"));
+        assert_eq!(encoded.parse::<ContextDigest>().unwrap(), digest)
+    }
+}
@@ -0,0 +1,238 @@
+//! Lossless round trip.
+//!
+//! Decoding a CBOR-LD document yields a JSON-LD document equivalent to the
+//! input, but not always identical to it: node object entries come out sorted
+//! by term, and compact IRIs used as vocabulary terms come out expanded. This
+//! breaks signatures computed over the JSON text (JWS, JCS).
+//!
+//! With lossless encoding, the encoder decodes its own output and records the
+//! differences with the input in a side channel stored next to the document
+//! body, wrapped in a CBOR tag chosen by the application:
+//! ```text
+//! tag([body, patch])
+//! ```
+//!
+//! A patch is one of:
+//! - `[0, string]`: replaces the value with the given string;
+//! - `[1, order, children]`: restores the entry order of an object, then
+//!   patches its entries. `order` gives, for each input entry, the index of
+//!   the matching decoded entry (or is `null` if the order is unchanged).
+//!   `children` maps input entry indexes to patches;
+//! - `[2, children]`: patches the items of an array, mapping item indexes to
+//!   patches;
+//! - `[3, json]`: replaces the value with the given JSON text.
+//!
+//! Documents without differences are left untouched. The decoder applies the
+//! patches whenever the tag is present, if it is given the same tag.
+use json_ld::syntax::Parse;
+
+use crate::{
+    json::{JsonLike, JsonRef},
+    transform::TransformedObject,
+    CborValue, DecodeError, JsonValue,
+};
+
+const REPLACE_STRING: u64 = 0;
+const PATCH_OBJECT: u64 = 1;
+const PATCH_ARRAY: u64 = 2;
+const REPLACE_JSON: u64 = 3;
+
+/// Computes the patch turning `decoded` back into `original`, if they differ.
+pub fn diff<V: JsonLike>(original: &V, decoded: &V) -> Option<CborValue> {
+    if original == decoded {
+        return None;
+    }
+
+    match (original.view(), decoded.view()) {
+        (JsonRef::Object(original), JsonRef::Object(decoded)) => {
+            let original: Vec<_> = original.entries().collect();
+            let decoded: Vec<_> = decoded.entries().collect();
+
+            if let Some(order) = entry_order(&original, &decoded) {
+                let children = order
+                    .iter()
+                    .enumerate()
+                    .filter_map(|(i, &j)| {
+                        diff(original[i].1, decoded[j].1).map(|patch| (index(i), patch))
+                    })
+                    .collect();
+
+                let order = if order.iter().enumerate().all(|(i, &j)| i == j) {
+                    CborValue::Null
+                } else {
+                    CborValue::Array(order.into_iter().map(index).collect())
+                };
+
+                return Some(CborValue::Array(vec![
+                    PATCH_OBJECT.into(),
+                    order,
+                    CborValue::Map(children),
+                ]));
+            }
+        }
+        (JsonRef::Array(original), JsonRef::Array(decoded)) if original.len() == decoded.len() => {
+            let children = original
+                .iter()
+                .zip(decoded)
+                .enumerate()
+                .filter_map(|(i, (original, decoded))| {
+                    diff(original, decoded).map(|patch| (index(i), patch))
+                })
+                .collect();
+
+            return Some(CborValue::Array(vec![
+                PATCH_ARRAY.into(),
+                CborValue::Map(children),
+            ]));
+        }
+        _ => (),
+    }
+
+    Some(match original.as_str() {
+        Some(s) => CborValue::Array(vec![REPLACE_STRING.into(), CborValue::Text(s.to_owned())]),
+        None => CborValue::Array(vec![
+            REPLACE_JSON.into(),
+            CborValue::Text(original.to_json_value().to_string()),
+        ]),
+    })
+}
+
+/// Finds, for each original entry, the index of the decoded entry with the
+/// same key. Returns `None` if the keys differ.
+fn entry_order<K: PartialEq, V>(original: &[(&K, &V)], decoded: &[(&K, &V)]) -> Option<Vec<usize>> {
+    if original.len() != decoded.len() {
+        return None;
+    }
+
+    let mut used = vec![false; decoded.len()];
+    original
+        .iter()
+        .map(|(key, _)| {
+            let j = (0..decoded.len()).find(|&j| !used[j] && decoded[j].0 == *key)?;
+            used[j] = true;
+            Some(j)
+        })
+        .collect()
+}
+
+fn index(i: usize) -> CborValue {
+    CborValue::Integer((i as u64).into())
+}
+
+fn parse_index(value: &CborValue) -> Result<usize, DecodeError> {
+    value
+        .as_integer()
+        .and_then(|i| usize::try_from(i).ok())
+        .ok_or(DecodeError::InvalidSideChannel)
+}
+
+/// Applies the given patch to a decoded value.
+pub fn restore<V: JsonLike>(decoded: &V, patch: &CborValue) -> Result<V, DecodeError> {
+    let (kind, args) = patch
+        .as_array()
+        .and_then(|items| items.split_first())
+        .ok_or(DecodeError::InvalidSideChannel)?;
+
+    match (parse_index(kind)? as u64, args) {
+        (REPLACE_STRING, [CborValue::Text(s)]) => Ok(V::string(s.clone())),
+        (REPLACE_JSON, [CborValue::Text(json)]) => JsonValue::parse_str(json)
+            .map(|(value, _)| V::from_json_value(value))
+            .map_err(|_| DecodeError::InvalidSideChannel),
+        (PATCH_OBJECT, [order, CborValue::Map(children)]) => {
+            let object = decoded.as_object().ok_or(DecodeError::InvalidSideChannel)?;
+            let decoded_entries: Vec<_> = object.entries().collect();
+
+            let mut entries: Vec<(V::Key, V)> = match order {
+                CborValue::Null => decoded_entries
+                    .into_iter()
+                    .map(|(key, value)| (key.clone(), value.clone()))
+                    .collect(),
+                CborValue::Array(order) if order.len() == decoded_entries.len() => order
+                    .iter()
+                    .map(|j| {
+                        let (key, value) = decoded_entries
+                            .get(parse_index(j)?)
+                            .ok_or(DecodeError::InvalidSideChannel)?;
+                        Ok(((*key).clone(), (*value).clone()))
+                    })
+                    .collect::<Result<_, DecodeError>>()?,
+                _ => return Err(DecodeError::InvalidSideChannel),
+            };
+
+            for (i, patch) in children {
+                let (_, value) = entries
+                    .get_mut(parse_index(i)?)
+                    .ok_or(DecodeError::InvalidSideChannel)?;
+                *value = restore(value, patch)?;
+            }
+
+            Ok(V::new_object(V::Map::new(entries)))
+        }
+        (PATCH_ARRAY, [CborValue::Map(children)]) => {
+            let mut items = decoded
+                .as_array()
+                .ok_or(DecodeError::InvalidSideChannel)?
+                .to_vec();
+
+            for (i, patch) in children {
+                let item = items
+                    .get_mut(parse_index(i)?)
+                    .ok_or(DecodeError::InvalidSideChannel)?;
+                *item = restore(item, patch)?;
+            }
+
+            Ok(V::new_array(items))
+        }
+        _ => Err(DecodeError::InvalidSideChannel),
+    }
+}
+
+/// Wraps a document body with its side channel, if any.
+pub fn wrap(tag: u64, body: CborValue, patch: Option<CborValue>) -> CborValue {
+    match patch {
+        Some(patch) => CborValue::Tag(tag, Box::new(CborValue::Array(vec![body, patch]))),
+        None => body,
+    }
+}
+
+/// Splits a document body from its side channel, if any.
+pub fn split(
+    tag: Option<u64>,
+    body: &CborValue,
+) -> Result<(&CborValue, Option<&CborValue>), DecodeError> {
+    match body {
+        CborValue::Tag(t, content) if Some(*t) == tag => {
+            match content.as_array().map(Vec::as_slice) {
+                Some([body, patch]) => Ok((body, Some(patch))),
+                _ => Err(DecodeError::InvalidSideChannel),
+            }
+        }
+        body => Ok((body, None)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{diff, restore};
+    use crate::JsonValue;
+
+    #[test]
+    fn restore_order_and_values() {
+        let original: JsonValue =
+            r#"{"b": [1, "x:y"], "a": {"d": 1.0, "c": null}}"#.parse().unwrap();
+        let decoded: JsonValue = r#"{"a": {"c": null, "d": 1}, "b": [1, "http://x/y"]}"#
+            .parse()
+            .unwrap();
+
+        let patch = diff(&original, &decoded).unwrap();
+        let restored = restore(&decoded, &patch).unwrap();
+        assert_eq!(restored, original);
+        assert_eq!(restored.to_string(), original.to_string())
+    }
+
+    #[test]
+    fn no_difference() {
+        let value: JsonValue = r#"{"a": [1, 2]}"#.parse().unwrap();
+        assert_eq!(diff(&value, &value), None)
+    }
+}
@@ -0,0 +1,725 @@
+//! RDF datasets.
+//!
+//! [`RdfDataset`] is produced by [`decode_to_rdf`](crate::decode_to_rdf) and
+//! consumed by [`encode_rdf`](crate::encode_rdf). It is printed and parsed in
+//! N-Quads syntax.
+//!
+//! Before encoding, a dataset is turned into an expanded JSON-LD document
+//! following the JSON-LD "Serialize RDF as JSON-LD" algorithm, without native
+//! types and without `rdf:type` properties. Nodes used as the object of
+//! exactly one quad are embedded in the node referencing them, as are named
+//! graphs in the node naming them, so that a dataset describing a single tree
+//! (such as a credential and its proof) becomes a single root node. Other
+//! datasets give several root nodes, encoded in a `@graph` entry. RDF lists
+//! are kept as `rdf:first`/`rdf:rest` nodes.
+//!
+//! Both the N-Quads parser and this conversion are implemented here: the
+//! dependencies provide no N-Quads parser, and the JSON-LD processor only
+//! serializes typed Linked-Data values into JSON-LD, node by node, without
+//! the embedding the encoding relies on. The parser follows the RDF 1.1
+//! N-Quads grammar and is tested with cases of the W3C N-Quads test suite.
+//! The conversion is tested by converting the result back to RDF with the
+//! JSON-LD processor and comparing it with the input dataset.
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    fmt,
+    str::FromStr,
+};
+
+use iref::IriBuf;
+use json_ld::{
+    rdf_types::{self, Id, LiteralType, Term},
+    syntax::Parse,
+    BlankIdBuf, LenientLangTagBuf,
+};
+
+use crate::{JsonObject, JsonValue};
+
+const RDF_TYPE: &str = "http://www.w3.org/1999/02/22-rdf-syntax-ns#type";
+const RDF_JSON: &str = "http://www.w3.org/1999/02/22-rdf-syntax-ns#JSON";
+const XSD_STRING: &str = "http://www.w3.org/2001/XMLSchema#string";
+
+/// RDF quad.
+pub type RdfQuad = json_ld::rdf::Quad<IriBuf, BlankIdBuf, rdf_types::Literal>;
+
+/// RDF dataset, displayed in N-Quads syntax.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RdfDataset(pub Vec<RdfQuad>);
+
+impl fmt::Display for RdfDataset {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for quad in &self.0 {
+            writeln!(f, "{quad} .")?
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("invalid N-Quads on line {line}: {reason}")]
+pub struct InvalidNQuads {
+    pub line: usize,
+    pub reason: &'static str,
+}
+
+impl FromStr for RdfDataset {
+    type Err = InvalidNQuads;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut quads = Vec::new();
+
+        for (i, line) in s.lines().enumerate() {
+            let quad = Parser(line).quad().map_err(|reason| InvalidNQuads {
+                line: i + 1,
+                reason,
+            })?;
+
+            if let Some(quad) = quad {
+                quads.push(quad)
+            }
+        }
+
+        Ok(Self(quads))
+    }
+}
+
+/// N-Quads line parser.
+struct Parser<'a>(&'a str);
+
+impl Parser<'_> {
+    fn skip_whitespace(&mut self) {
+        self.0 = self.0.trim_start_matches([' ', '\t']);
+    }
+
+    fn is_end(&mut self) -> bool {
+        self.skip_whitespace();
+        self.0.is_empty() || self.0.starts_with('#')
+    }
+
+    fn quad(&mut self) -> Result<Option<RdfQuad>, &'static str> {
+        if self.is_end() {
+            return Ok(None);
+        }
+
+        let subject = self.id()?;
+        let predicate = match self.id()? {
+            Id::Iri(iri) => Id::Iri(iri),
+            Id::Blank(_) => return Err("predicate must be an IRI"),
+        };
+        let object = self.term()?;
+
+        self.skip_whitespace();
+        let graph = if self.0.starts_with('.') {
+            None
+        } else {
+            Some(self.id()?)
+        };
+
+        self.skip_whitespace();
+        self.0 = self.0.strip_prefix('.').ok_or("expected `.`")?;
+        if !self.is_end() {
+            return Err("unexpected content after `.`");
+        }
+
+        Ok(Some(rdf_types::Quad(subject, predicate, object, graph)))
+    }
+
+    fn id(&mut self) -> Result<Id<IriBuf, BlankIdBuf>, &'static str> {
+        self.skip_whitespace();
+        if self.0.starts_with('<') {
+            self.iri().map(Id::Iri)
+        } else if self.0.starts_with("_:") {
+            let end = self
+                .0
+                .find(|c: char| c.is_whitespace() || c == '<' || c == '"')
+                .unwrap_or(self.0.len());
+            let label = self.0[..end].trim_end_matches('.');
+            self.0 = &self.0[label.len()..];
+            BlankIdBuf::new(label.to_owned())
+                .map(Id::Blank)
+                .map_err(|_| "invalid blank node identifier")
+        } else {
+            Err("expected IRI or blank node identifier")
+        }
+    }
+
+    fn term(&mut self) -> Result<Term<Id<IriBuf, BlankIdBuf>, rdf_types::Literal>, &'static str> {
+        self.skip_whitespace();
+        if !self.0.starts_with('"') {
+            return self.id().map(Term::Id);
+        }
+
+        let value = self.string()?;
+        let type_ = if let Some(rest) = self.0.strip_prefix("^^") {
+            self.0 = rest;
+            LiteralType::Any(self.iri()?)
+        } else if let Some(rest) = self.0.strip_prefix('@') {
+            let end = rest
+                .find(|c: char| !c.is_ascii_alphanumeric() && c != '-')
+                .unwrap_or(rest.len());
+            self.0 = &rest[end..];
+            LiteralType::LangString(
+                LenientLangTagBuf::new(rest[..end].to_owned())
+                    .0
+                    .into_well_formed()
+                    .map_err(|_| "invalid language tag")?,
+            )
+        } else {
+            LiteralType::Any(IriBuf::new(XSD_STRING.to_owned()).unwrap())
+        };
+
+        Ok(Term::Literal(rdf_types::Literal::new(value, type_)))
+    }
+
+    fn iri(&mut self) -> Result<IriBuf, &'static str> {
+        let rest = self.0.strip_prefix('<').ok_or("expected IRI")?;
+        let end = rest.find('>').ok_or("unterminated IRI")?;
+        let iri = unescape(&rest[..end], false)?;
+        self.0 = &rest[end + 1..];
+        IriBuf::new(iri).map_err(|_| "invalid IRI")
+    }
+
+    fn string(&mut self) -> Result<String, &'static str> {
+        let rest = &self.0[1..];
+        let mut escaped = false;
+        let end = rest
+            .find(|c| {
+                let end = c == '"' && !escaped;
+                escaped = c == '\\' && !escaped;
+                end
+            })
+            .ok_or("unterminated string literal")?;
+
+        self.0 = &rest[end + 1..];
+        unescape(&rest[..end], true)
+    }
+}
+
+/// Resolves Unicode (`\uXXXX`, `\UXXXXXXXX`) escape sequences, and string
+/// (`\n`, `\"`, etc.) escape sequences if allowed (in string literals, not
+/// IRIs).
+fn unescape(s: &str, string_escapes: bool) -> Result<String, &'static str> {
+    let mut result = String::with_capacity(s.len());
+    let mut chars = s.chars();
+
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            result.push(c);
+            continue;
+        }
+
+        let c = match chars.next().ok_or("invalid escape sequence")? {
+            c @ ('u' | 'U') => {
+                let len = if c == 'u' { 4 } else { 8 };
+                let hex: String = chars.by_ref().take(len).collect();
+                u32::from_str_radix(&hex, 16)
+                    .ok()
+                    .filter(|_| hex.len() == len && hex.chars().all(|c| c.is_ascii_hexdigit()))
+                    .and_then(char::from_u32)
+                    .ok_or("invalid Unicode escape sequence")?
+            }
+            _ if !string_escapes => return Err("invalid escape sequence"),
+            't' => '\t',
+            'b' => '\u{8}',
+            'n' => '\n',
+            'r' => '\r',
+            'f' => '\u{c}',
+            c @ ('"' | '\'' | '\\') => c,
+            _ => return Err("invalid escape sequence"),
+        };
+
+        result.push(c)
+    }
+
+    Ok(result)
+}
+
+/// Object of a quad, in a node map.
+enum Object {
+    Node(String),
+    Value(JsonValue),
+}
+
+#[derive(Default)]
+struct Node {
+    types: Vec<String>,
+    properties: BTreeMap<String, Vec<Object>>,
+}
+
+impl Node {
+    fn is_empty(&self) -> bool {
+        self.types.is_empty() && self.properties.is_empty()
+    }
+}
+
+/// Nodes of a graph, by identifier.
+type NodeMap = BTreeMap<String, Node>;
+
+/// Named graph, or the default graph (`None`).
+type GraphName = Option<String>;
+
+impl RdfDataset {
+    /// Converts this dataset into an expanded JSON-LD document.
+    ///
+    /// See the [module documentation](self).
+    pub fn to_expanded_json(&self) -> JsonValue {
+        let mut graphs: BTreeMap<GraphName, NodeMap> = BTreeMap::new();
+        graphs.insert(None, NodeMap::new());
+
+        // Number of times each node identifier appears in the dataset: once
+        // per graph it is the subject of, per object position and per graph
+        // it names. Blank node identifiers appearing in more places than the
+        // one a node object is built from must be kept.
+        let mut occurrences: HashMap<String, usize> = HashMap::new();
+
+        for rdf_types::Quad(subject, predicate, object, graph) in &self.0 {
+            let nodes = graphs
+                .entry(graph.as_ref().map(ToString::to_string))
+                .or_default();
+            let subject = subject.to_string();
+            if !nodes.contains_key(&subject) {
+                *occurrences.entry(subject.clone()).or_default() += 1
+            }
+            let node = nodes.entry(subject).or_default();
+
+            match object {
+                Term::Id(id) if predicate.as_str() == RDF_TYPE => {
+                    let ty = id.to_string();
+                    if !node.types.contains(&ty) {
+                        *occurrences.entry(ty.clone()).or_default() += 1;
+                        node.types.push(ty)
+                    }
+                }
+                object => {
+                    let object = match object {
+                        Term::Id(id) => Object::Node(id.to_string()),
+                        Term::Literal(literal) => Object::Value(value_object(literal)),
+                    };
+
+                    let values = node.properties.entry(predicate.to_string()).or_default();
+                    if !values.iter().any(|v| same_object(v, &object)) {
+                        if let Object::Node(id) = &object {
+                            *occurrences.entry(id.clone()).or_default() += 1
+                        }
+                        values.push(object)
+                    }
+                }
+            }
+        }
+
+        for name in graphs.keys().flatten() {
+            *occurrences.entry(name.clone()).or_default() += 1
+        }
+
+        let parents = graph_parents(&graphs);
+        JsonValue::Array(build_graph(None, &mut graphs, &parents, &occurrences))
+    }
+}
+
+/// Finds the graph in which each named graph is embedded: the graph
+/// containing the only reference to its name, or the default graph.
+fn graph_parents(graphs: &BTreeMap<GraphName, NodeMap>) -> BTreeMap<String, GraphName> {
+    let mut references: HashMap<&str, Vec<&GraphName>> = HashMap::new();
+    for (graph, nodes) in graphs {
+        for node in nodes.values() {
+            for values in node.properties.values() {
+                for value in values {
+                    if let Object::Node(id) = value {
+                        references.entry(id).or_default().push(graph)
+                    }
+                }
+            }
+        }
+    }
+
+    let mut parents: BTreeMap<String, GraphName> = graphs
+        .keys()
+        .flatten()
+        .map(|name| {
+            let parent = match references.get(name.as_str()).map(Vec::as_slice) {
+                Some([graph]) => (*graph).clone(),
+                _ => None,
+            };
+
+            (name.clone(), parent)
+        })
+        .collect();
+
+    // Graphs embedded in each other are attached to the default graph.
+    let names: Vec<String> = parents.keys().cloned().collect();
+    for name in names {
+        let mut current = parents[&name].clone();
+        for _ in 0..parents.len() {
+            match current {
+                Some(parent) if parent == name => {
+                    parents.insert(name.clone(), None);
+                    break;
+                }
+                Some(parent) => current = parents[&parent].clone(),
+                None => break,
+            }
+        }
+    }
+
+    parents
+}
+
+/// Builds the node objects of the given graph, with the named graphs
+/// embedded in it.
+fn build_graph(
+    name: GraphName,
+    graphs: &mut BTreeMap<GraphName, NodeMap>,
+    parents: &BTreeMap<String, GraphName>,
+    occurrences: &HashMap<String, usize>,
+) -> Vec<JsonValue> {
+    let mut nodes = graphs.remove(&name).unwrap_or_default();
+
+    let mut named_graphs = HashMap::new();
+    for (child, _) in parents.iter().filter(|(_, parent)| **parent == name) {
+        let graph = build_graph(Some(child.clone()), graphs, parents, occurrences);
+        nodes.entry(child.clone()).or_default();
+        named_graphs.insert(child.clone(), graph);
+    }
+
+    embed_nodes(&nodes, &mut named_graphs, occurrences)
+}
+
+fn value_object(literal: &rdf_types::Literal) -> JsonValue {
+    let mut object = JsonObject::new();
+
+    match &literal.type_ {
+        LiteralType::LangString(tag) => {
+            object.insert(
+                "@value".into(),
+                JsonValue::String(literal.value.as_str().into()),
+            );
+            object.insert("@language".into(), JsonValue::String(tag.as_str().into()));
+        }
+        LiteralType::Any(ty) if ty.as_str() == XSD_STRING => {
+            object.insert(
+                "@value".into(),
+                JsonValue::String(literal.value.as_str().into()),
+            );
+        }
+        LiteralType::Any(ty) => match JsonValue::parse_str(&literal.value) {
+            Ok((json, _)) if ty.as_str() == RDF_JSON => {
+                object.insert("@value".into(), json);
+                object.insert("@type".into(), JsonValue::String("@json".into()));
+            }
+            _ => {
+                object.insert(
+                    "@value".into(),
+                    JsonValue::String(literal.value.as_str().into()),
+                );
+                object.insert("@type".into(), JsonValue::String(ty.as_str().into()));
+            }
+        },
+    }
+
+    JsonValue::Object(object)
+}
+
+fn same_object(a: &Object, b: &Object) -> bool {
+    match (a, b) {
+        (Object::Node(a), Object::Node(b)) => a == b,
+        (Object::Value(a), Object::Value(b)) => a == b,
+        _ => false,
+    }
+}
+
+/// Builds the node objects of a graph, embedding nodes referenced exactly
+/// once, and the given named graphs in the node naming them.
+///
+/// Blank node identifiers are only kept when they are needed to reference the
+/// node.
+fn embed_nodes(
+    nodes: &NodeMap,
+    named_graphs: &mut HashMap<String, Vec<JsonValue>>,
+    occurrences: &HashMap<String, usize>,
+) -> Vec<JsonValue> {
+    let mut references: HashMap<&str, usize> = HashMap::new();
+    for node in nodes.values() {
+        for values in node.properties.values() {
+            for value in values {
+                if let Object::Node(id) = value {
+                    *references.entry(id).or_default() += 1
+                }
+            }
+        }
+    }
+
+    let mut graph = Graph {
+        nodes,
+        references,
+        named_graphs,
+        occurrences,
+        emitted: BTreeSet::new(),
+    };
+
+    let mut result = Vec::new();
+    for id in nodes.keys() {
+        if !graph.is_embedded(id) {
+            result.push(graph.node_object(id, false))
+        }
+    }
+
+    // Nodes only referenced inside a cycle.
+    for id in nodes.keys() {
+        if !graph.emitted.contains(id.as_str()) {
+            result.push(graph.node_object(id, false))
+        }
+    }
+
+    result
+}
+
+struct Graph<'a, 'g> {
+    nodes: &'a NodeMap,
+    references: HashMap<&'a str, usize>,
+    named_graphs: &'g mut HashMap<String, Vec<JsonValue>>,
+    occurrences: &'a HashMap<String, usize>,
+    emitted: BTreeSet<&'a str>,
+}
+
+impl<'a> Graph<'a, '_> {
+    fn is_embedded(&self, id: &str) -> bool {
+        self.nodes.contains_key(id) && self.references.get(id) == Some(&1)
+    }
+
+    /// Builds the node object of the given node, embedded in the node
+    /// referencing it or not.
+    fn node_object(&mut self, id: &'a str, embedded: bool) -> JsonValue {
+        self.emitted.insert(id);
+
+        // Occurrences of the identifier represented by this node object.
+        let local = usize::from(embedded)
+            + usize::from(self.nodes.get(id).is_some_and(|node| !node.is_empty()))
+            + usize::from(self.named_graphs.contains_key(id));
+        let keep_id =
+            !id.starts_with("_:") || self.occurrences.get(id).copied().unwrap_or(0) > local;
+
+        let mut object = JsonObject::new();
+        if keep_id {
+            object.insert("@id".into(), JsonValue::String(id.into()));
+        }
+
+        let nodes = self.nodes;
+        if let Some(node) = nodes.get(id) {
+            if !node.types.is_empty() {
+                object.insert(
+                    "@type".into(),
+                    JsonValue::Array(
+                        node.types
+                            .iter()
+                            .map(|ty| JsonValue::String(ty.as_str().into()))
+                            .collect(),
+                    ),
+                );
+            }
+
+            for (property, values) in &node.properties {
+                let values = values
+                    .iter()
+                    .map(|value| match value {
+                        Object::Node(id)
+                            if self.is_embedded(id) && !self.emitted.contains(id.as_str()) =>
+                        {
+                            self.node_object(id, true)
+                        }
+                        Object::Node(id) => {
+                            let mut reference = JsonObject::new();
+                            reference.insert("@id".into(), JsonValue::String(id.as_str().into()));
+                            JsonValue::Object(reference)
+                        }
+                        Object::Value(value) => value.clone(),
+                    })
+                    .collect();
+
+                object.insert(property.as_str().into(), JsonValue::Array(values));
+            }
+        }
+
+        if let Some(graph) = self.named_graphs.remove(id) {
+            object.insert("@graph".into(), JsonValue::Array(graph));
+        }
+
+        JsonValue::Object(object)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RdfDataset;
+
+    #[test]
+    fn parse_and_print() {
+        let nquads = concat!(
+            "<http://example.org/a> <http://example.org/p> \"x\\\"y\\u00e9\"@en-US .\n",
+            "_:b0 <http://example.org/p> \"1\"^^<http://www.w3.org/2001/XMLSchema#integer> <http://example.org/g> .\n",
+            "# comment\n",
+            "<http://example.org/a> <http://example.org/q> _:b0 .\n",
+        );
+
+        let dataset: RdfDataset = nquads.parse().unwrap();
+        assert_eq!(dataset.0.len(), 3);
+
+        let printed = dataset.to_string();
+        assert_eq!(printed.parse::<RdfDataset>().unwrap(), dataset);
+        assert!(printed.contains("\"x\\\"yé\"@en-US"))
+    }
+
+    #[test]
+    fn embed_blank_nodes() {
+        let dataset: RdfDataset = concat!(
+            "<http://example.org/a> <http://www.w3.org/1999/02/22-rdf-syntax-ns#type> <http://example.org/T> .\n",
+            "<http://example.org/a> <http://example.org/p> _:b0 .\n",
+            "_:b0 <http://example.org/q> \"v\" .\n",
+        )
+        .parse()
+        .unwrap();
+
+        let expected: crate::JsonValue = r#"[{
+            "@id": "http://example.org/a",
+            "@type": ["http://example.org/T"],
+            "http://example.org/p": [{
+                "http://example.org/q": [{"@value": "v"}]
+            }]
+        }]"#
+        .parse()
+        .unwrap();
+
+        assert_eq!(dataset.to_expanded_json(), expected)
+    }
+
+    #[test]
+    fn embed_nested_graphs() {
+        let dataset: RdfDataset = concat!(
+            "_:v <http://example.org/credential> _:g0 .\n",
+            "<http://example.org/c> <http://example.org/proof> _:g1 _:g0 .\n",
+            "_:p <http://example.org/value> \"z\" _:g1 .\n",
+        )
+        .parse()
+        .unwrap();
+
+        let expected: crate::JsonValue = r#"[{
+            "http://example.org/credential": [{
+                "@graph": [{
+                    "@id": "http://example.org/c",
+                    "http://example.org/proof": [{
+                        "@graph": [{
+                            "http://example.org/value": [{"@value": "z"}]
+                        }]
+                    }]
+                }]
+            }]
+        }]"#
+        .parse()
+        .unwrap();
+
+        assert_eq!(dataset.to_expanded_json(), expected)
+    }
+
+    #[test]
+    fn shared_blank_nodes() {
+        let dataset: RdfDataset = concat!(
+            "_:a <http://example.org/p> \"x\" .\n",
+            "_:a <http://example.org/q> \"y\" _:g .\n",
+        )
+        .parse()
+        .unwrap();
+
+        let expected: crate::JsonValue = r#"[
+            {
+                "@id": "_:a",
+                "http://example.org/p": [{"@value": "x"}]
+            },
+            {
+                "@graph": [{
+                    "@id": "_:a",
+                    "http://example.org/q": [{"@value": "y"}]
+                }]
+            }
+        ]"#
+        .parse()
+        .unwrap();
+
+        assert_eq!(dataset.to_expanded_json(), expected)
+    }
+
+    /// Cases adapted from the W3C RDF 1.1 N-Triples and N-Quads test suites,
+    /// with their number of quads.
+    const POSITIVE_SYNTAX: &[(&str, usize)] = &[
+        ("", 0),
+        ("#Empty file.\n", 0),
+        ("#One comment, one empty line.\n\n", 0),
+        ("<http://example/s> <http://example/p> <http://example/o> .", 1),
+        ("<http://example/\\u0053> <http://example/p> <http://example/o> .", 1),
+        ("<http://example/\\U00000053> <http://example/p> <http://example/o> .", 1),
+        ("<http://example/s> <http://example/p> <scheme:!$%25&'()*+,-./0123456789:/@ABCDEFGHIJKLMNOPQRSTUVWXYZ_abcdefghijklmnopqrstuvwxyz~?#> .", 1),
+        ("<http://example/s> <http://example/p> \"string\" .", 1),
+        ("<http://example/s> <http://example/p> \"string\"@en .", 1),
+        ("<http://example/s> <http://example/p> \"string\"@en-uk .", 1),
+        ("<http://example/s> <http://example/p> \"a\\n\" .", 1),
+        ("<http://example/s> <http://example/p> \"a\\u0020b\" .", 1),
+        ("<http://example/s> <http://example/p> \"a\\U00000020b\" .", 1),
+        ("<http://example/s> <http://example/p> \"\\t\\b\\n\\r\\f\\\"\\'\\\\\" .", 1),
+        ("_:a  <http://example/p> <http://example/o> .", 1),
+        ("<http://example/s> <http://example/p> _:a .\n_:a  <http://example/p> <http://example/o> .", 2),
+        ("<http://example/s> <http://example/p> _:1a .\n_:1a  <http://example/p> <http://example/o> .", 2),
+        ("<http://example/s> <http://example/p> \"123\"^^<http://www.w3.org/2001/XMLSchema#byte> .", 1),
+        ("<http://example/s> <http://example/p> <http://example/o> . # comment", 1),
+        ("<http://example/s><http://example/p><http://example/o>.", 1),
+        ("<http://example/s><http://example/p>\"Alice\"@en.", 1),
+        ("_:s<http://example/p>_:o.", 1),
+        ("<http://example/s> <http://example/p> <http://example/o> <http://example/g> .", 1),
+        ("<http://example/s> <http://example/p> <http://example/o> _:g .", 1),
+        ("_:s <http://example/p> \"o\"@en _:g .", 1),
+    ];
+
+    /// Cases adapted from the W3C RDF 1.1 N-Triples and N-Quads test suites.
+    const NEGATIVE_SYNTAX: &[&str] = &[
+        "<http://example/ s> <http://example/p> <http://example/o> .",
+        "<http://example/\\u00ZZ11> <http://example/p> <http://example/o> .",
+        "<http://example/\\n> <http://example/p> <http://example/o> .",
+        "<s> <http://example/p> <http://example/o> .",
+        "@prefix : <http://example/> .",
+        "@base <http://example/> .",
+        "<http://example/s> <http://example/p> <http://example/o>, <http://example/o2> .",
+        "<http://example/s> <http://example/p> <http://example/o>; <http://example/p2> <http://example/o2> .",
+        "<http://example/s> <http://example/p> \"string\"@1 .",
+        "<http://example/s> <http://example/p> \"\\a\" .",
+        "<http://example/s> <http://example/p> \"\\uWXYZ\" .",
+        "<http://example/s> <http://example/p> \"\\u+041\" .",
+        "<http://example/s> <http://example/p> \"abc' .",
+        "<http://example/s> <http://example/p> \"\"\"abc\"\"\" .",
+        "<http://example/s> <http://example/p> 1 .",
+        "<http://example/s> <http://example/p> true .",
+        "<http://example/s> <http://example/p> <http://example/o>",
+        "<http://example/s> _:p <http://example/o> .",
+        "\"s\" <http://example/p> <http://example/o> .",
+        "<http://example/s> <http://example/p> <http://example/o> \"o\" .",
+        "<http://example/s> <http://example/p> <http://example/o> <http://example/g> <http://example/x> .",
+    ];
+
+    #[test]
+    fn w3c_syntax() {
+        for (nquads, len) in POSITIVE_SYNTAX {
+            let dataset: RdfDataset = nquads.parse().unwrap_or_else(|e| panic!("{nquads:?}: {e}"));
+            assert_eq!(dataset.0.len(), *len, "{nquads:?}");
+            assert_eq!(dataset.to_string().parse::<RdfDataset>().unwrap(), dataset)
+        }
+
+        for nquads in NEGATIVE_SYNTAX {
+            assert!(nquads.parse::<RdfDataset>().is_err(), "{nquads:?}")
+        }
+    }
+
+    #[test]
+    fn invalid() {
+        let error = "<http://example.org/a> <http://example.org/p> \"x\"\n"
+            .parse::<RdfDataset>()
+            .unwrap_err();
+        assert_eq!(error.line, 1)
+    }
+}
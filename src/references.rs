@@ -0,0 +1,325 @@
+//! Value deduplication using CBOR string and shared value references.
+//!
+//! Repeated strings are replaced using the `stringref` tags (25 and 256), and
+//! repeated arrays and maps using the `sharedref` tags (28 and 29).
+//!
+//! See: <http://cbor.schmorp.de/stringref> and <http://cbor.schmorp.de/value-sharing>
+use std::collections::{HashMap, HashSet};
+
+use crate::{encode::cbor_into_bytes, CborValue, DecodeError};
+
+/// Tag opening a string reference namespace.
+pub const STRINGREF_NAMESPACE_TAG: u64 = 256;
+
+/// Tag referencing a string of the current namespace.
+pub const STRINGREF_TAG: u64 = 25;
+
+/// Tag marking a value that may be referenced later.
+pub const SHAREABLE_TAG: u64 = 28;
+
+/// Tag referencing a previously marked value.
+pub const SHAREDREF_TAG: u64 = 29;
+
+/// Minimum encoded length of arrays and maps that can be shared.
+///
+/// A shared value reference takes at least 3 bytes.
+const MIN_SHARED_LEN: usize = 4;
+
+/// Minimum length a string must have to be assigned an index in a string
+/// reference namespace of the given size, as mandated by the `stringref`
+/// specification.
+fn min_string_len(namespace_len: usize) -> usize {
+    if namespace_len < 24 {
+        3
+    } else if namespace_len < 256 {
+        4
+    } else if namespace_len < 65536 {
+        5
+    } else if (namespace_len as u64) < 1 << 32 {
+        7
+    } else {
+        11
+    }
+}
+
+#[derive(PartialEq, Eq, Hash)]
+enum StringKey {
+    Text(String),
+    Bytes(Vec<u8>),
+}
+
+impl StringKey {
+    fn new(value: &CborValue) -> Option<Self> {
+        match value {
+            CborValue::Text(s) => Some(Self::Text(s.clone())),
+            CborValue::Bytes(b) => Some(Self::Bytes(b.clone())),
+            _ => None,
+        }
+    }
+
+    fn len(&self) -> usize {
+        match self {
+            Self::Text(s) => s.len(),
+            Self::Bytes(b) => b.len(),
+        }
+    }
+}
+
+/// Deduplicates repeated values.
+///
+/// Returns the given value unchanged if deduplication does not make it
+/// smaller. Otherwise the result is wrapped in a string reference namespace
+/// tag, which signals decoders to call [`resolve_references`].
+pub fn deduplicate(value: CborValue) -> CborValue {
+    let mut seen = HashSet::new();
+    let mut referenced = HashSet::new();
+    find_shared_values(&value, &mut seen, &mut referenced);
+
+    let mut shared = HashMap::new();
+    let mut result = share_values(&value, &referenced, &mut shared);
+
+    let mut namespace = HashMap::new();
+    reference_strings(&mut result, &mut namespace);
+    let result = CborValue::Tag(STRINGREF_NAMESPACE_TAG, Box::new(result));
+
+    if cbor_into_bytes(result.clone()).len() < cbor_into_bytes(value.clone()).len() {
+        result
+    } else {
+        value
+    }
+}
+
+fn shareable_bytes(value: &CborValue) -> Option<Vec<u8>> {
+    match value {
+        CborValue::Array(_) | CborValue::Map(_) => {
+            let bytes = cbor_into_bytes(value.clone());
+            (bytes.len() >= MIN_SHARED_LEN).then_some(bytes)
+        }
+        _ => None,
+    }
+}
+
+fn children(value: &CborValue) -> Box<dyn Iterator<Item = &CborValue> + '_> {
+    match value {
+        CborValue::Array(items) => Box::new(items.iter()),
+        CborValue::Map(entries) => Box::new(entries.iter().flat_map(|(k, v)| [k, v])),
+        CborValue::Tag(_, value) => Box::new(std::iter::once(value.as_ref())),
+        _ => Box::new(std::iter::empty()),
+    }
+}
+
+/// Finds the values that are repeated in decoding order, ignoring the
+/// content of values that will be replaced by a reference.
+fn find_shared_values(
+    value: &CborValue,
+    seen: &mut HashSet<Vec<u8>>,
+    referenced: &mut HashSet<Vec<u8>>,
+) {
+    if let Some(bytes) = shareable_bytes(value) {
+        if seen.contains(&bytes) {
+            referenced.insert(bytes);
+            return;
+        }
+
+        seen.insert(bytes);
+    }
+
+    for child in children(value) {
+        find_shared_values(child, seen, referenced)
+    }
+}
+
+fn share_values(
+    value: &CborValue,
+    referenced: &HashSet<Vec<u8>>,
+    shared: &mut HashMap<Vec<u8>, u64>,
+) -> CborValue {
+    if let Some(bytes) = shareable_bytes(value).filter(|b| referenced.contains(b)) {
+        if let Some(i) = shared.get(&bytes) {
+            return CborValue::Tag(SHAREDREF_TAG, Box::new(CborValue::Integer((*i).into())));
+        }
+
+        shared.insert(bytes, shared.len() as u64);
+        let value = share_children(value, referenced, shared);
+        return CborValue::Tag(SHAREABLE_TAG, Box::new(value));
+    }
+
+    share_children(value, referenced, shared)
+}
+
+fn share_children(
+    value: &CborValue,
+    referenced: &HashSet<Vec<u8>>,
+    shared: &mut HashMap<Vec<u8>, u64>,
+) -> CborValue {
+    match value {
+        CborValue::Array(items) => CborValue::Array(
+            items
+                .iter()
+                .map(|item| share_values(item, referenced, shared))
+                .collect(),
+        ),
+        CborValue::Map(entries) => CborValue::Map(
+            entries
+                .iter()
+                .map(|(k, v)| {
+                    let k = share_values(k, referenced, shared);
+                    (k, share_values(v, referenced, shared))
+                })
+                .collect(),
+        ),
+        CborValue::Tag(tag, value) => {
+            CborValue::Tag(*tag, Box::new(share_values(value, referenced, shared)))
+        }
+        value => value.clone(),
+    }
+}
+
+fn reference_strings(value: &mut CborValue, namespace: &mut HashMap<StringKey, u64>) {
+    match value {
+        CborValue::Text(_) | CborValue::Bytes(_) => {
+            let key = StringKey::new(value).unwrap();
+            match namespace.get(&key) {
+                Some(i) => {
+                    *value =
+                        CborValue::Tag(STRINGREF_TAG, Box::new(CborValue::Integer((*i).into())))
+                }
+                None => {
+                    if key.len() >= min_string_len(namespace.len()) {
+                        namespace.insert(key, namespace.len() as u64);
+                    }
+                }
+            }
+        }
+        CborValue::Array(items) => {
+            for item in items {
+                reference_strings(item, namespace)
+            }
+        }
+        CborValue::Map(entries) => {
+            for (k, v) in entries {
+                reference_strings(k, namespace);
+                reference_strings(v, namespace)
+            }
+        }
+        CborValue::Tag(_, value) => reference_strings(value, namespace),
+        _ => (),
+    }
+}
+
+#[derive(Default)]
+struct Resolver {
+    namespace: Option<Vec<CborValue>>,
+    shared: Vec<Option<CborValue>>,
+}
+
+impl Resolver {
+    fn resolve(&mut self, value: &CborValue) -> Result<CborValue, DecodeError> {
+        match value {
+            CborValue::Text(_) | CborValue::Bytes(_) => {
+                if let Some(namespace) = &mut self.namespace {
+                    if StringKey::new(value).unwrap().len() >= min_string_len(namespace.len()) {
+                        namespace.push(value.clone())
+                    }
+                }
+
+                Ok(value.clone())
+            }
+            CborValue::Array(items) => items
+                .iter()
+                .map(|item| self.resolve(item))
+                .collect::<Result<_, _>>()
+                .map(CborValue::Array),
+            CborValue::Map(entries) => entries
+                .iter()
+                .map(|(k, v)| Ok((self.resolve(k)?, self.resolve(v)?)))
+                .collect::<Result<_, _>>()
+                .map(CborValue::Map),
+            CborValue::Tag(STRINGREF_NAMESPACE_TAG, value) => {
+                let outer = self.namespace.replace(Vec::new());
+                let result = self.resolve(value);
+                self.namespace = outer;
+                result
+            }
+            CborValue::Tag(STRINGREF_TAG, i) => {
+                let namespace = self.namespace.as_ref().ok_or_else(|| {
+                    DecodeError::InvalidReference("string reference outside namespace".to_owned())
+                })?;
+
+                reference_index(i)
+                    .and_then(|i| namespace.get(i))
+                    .cloned()
+                    .ok_or_else(|| {
+                        DecodeError::InvalidReference("undefined string reference".to_owned())
+                    })
+            }
+            CborValue::Tag(SHAREABLE_TAG, value) => {
+                let i = self.shared.len();
+                self.shared.push(None);
+                let value = self.resolve(value)?;
+                self.shared[i] = Some(value.clone());
+                Ok(value)
+            }
+            CborValue::Tag(SHAREDREF_TAG, i) => reference_index(i)
+                .and_then(|i| self.shared.get(i))
+                .cloned()
+                .flatten()
+                .ok_or_else(|| {
+                    DecodeError::InvalidReference("undefined shared value reference".to_owned())
+                }),
+            CborValue::Tag(tag, value) => Ok(CborValue::Tag(*tag, Box::new(self.resolve(value)?))),
+            value => Ok(value.clone()),
+        }
+    }
+}
+
+fn reference_index(value: &CborValue) -> Option<usize> {
+    match value {
+        CborValue::Integer(i) => usize::try_from(i128::from(*i)).ok(),
+        _ => None,
+    }
+}
+
+/// Replaces string and shared value references with the values they
+/// reference.
+pub fn resolve_references(value: &CborValue) -> Result<CborValue, DecodeError> {
+    Resolver::default().resolve(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{deduplicate, resolve_references, STRINGREF_NAMESPACE_TAG};
+    use crate::CborValue;
+
+    fn text(s: &str) -> CborValue {
+        CborValue::Text(s.to_owned())
+    }
+
+    #[test]
+    fn round_trip() {
+        let object = CborValue::Map(vec![
+            (CborValue::Integer(100.into()), text("did:example:123")),
+            (CborValue::Integer(102.into()), text("Alice")),
+        ]);
+        let value = CborValue::Array(vec![
+            object.clone(),
+            text("did:example:123"),
+            CborValue::Array(vec![object.clone(), text("Alice")]),
+            object,
+            CborValue::Bytes(b"did:example:123".to_vec()),
+        ]);
+
+        let deduplicated = deduplicate(value.clone());
+        assert!(matches!(
+            deduplicated,
+            CborValue::Tag(STRINGREF_NAMESPACE_TAG, _)
+        ));
+        assert_eq!(resolve_references(&deduplicated).unwrap(), value)
+    }
+
+    #[test]
+    fn no_repetition() {
+        let value = CborValue::Array(vec![text("a"), text("did:example:123")]);
+        assert_eq!(deduplicate(value.clone()), value)
+    }
+}
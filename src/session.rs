@@ -0,0 +1,235 @@
+//! Long-lived encoding/decoding session.
+use std::{borrow::Cow, io, sync::Arc};
+
+use crate::{
+    cache::ContextCache,
+    decode::DecodePipeline,
+    encode::{cbor_into_bytes, EncodePipeline},
+    lenient::Warnings,
+    loader::ContextPins,
+    tables::Registry,
+    CborValue, Codecs, CompressionMode, DecodeError, DecodeOptions, EncodeError, EncodeOptions,
+    JsonValue, Tables,
+};
+
+/// CBOR-LD session.
+///
+/// Holds everything that can be reused between documents: the context
+/// loader, the encoding and decoding options and a processed context cache
+/// shared by both. Encoding and decoding go through the same pipeline as
+/// [`encode_with`](crate::encode_with) and [`decode_with`](crate::decode_with).
+/// Encoding and decoding only borrow the session, so a single session
+/// (typically behind an [`Arc`]) can serve many concurrent tasks.
+///
+/// ```
+/// # async fn run() -> Result<(), Box<dyn std::error::Error>> {
+/// use cbor_ld::CborLd;
+/// use json_ld::FsLoader;
+///
+/// let mut loader = FsLoader::new();
+/// loader.mount(
+///     static_iref::iri!("https://www.w3.org/ns/activitystreams").to_owned(),
+///     "tests/contexts/activitystreams",
+/// );
+///
+/// let session = CborLd::new(loader);
+/// let document: cbor_ld::JsonValue = include_str!("../tests/samples/note.jsonld").parse()?;
+/// let bytes = session.encode_to_bytes(&document).await?;
+/// let decoded = session.decode_from_bytes(&bytes).await?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct CborLd<L> {
+    loader: L,
+    encode: EncodePipeline,
+    decode: DecodePipeline,
+}
+
+impl<L> CborLd<L> {
+    /// Creates a new session using the given context loader and the default
+    /// options.
+    pub fn new(loader: L) -> Self {
+        Self::with_options(loader, EncodeOptions::default(), DecodeOptions::default())
+    }
+
+    /// Creates a new session using the given context loader and options.
+    ///
+    /// If neither option set provides a processed context cache, the session
+    /// creates one. Sequence state sharing only applies to sequences and is
+    /// ignored.
+    pub fn with_options(loader: L, encode: EncodeOptions, decode: DecodeOptions) -> Self {
+        let context_cache = encode
+            .context_cache
+            .clone()
+            .or_else(|| decode.context_cache.clone())
+            .unwrap_or_default();
+
+        let mut encode = EncodePipeline::from(encode);
+        encode
+            .context_cache
+            .get_or_insert_with(|| context_cache.clone());
+
+        let mut decode = DecodePipeline::from(decode);
+        decode.context_cache.get_or_insert(context_cache);
+
+        Self {
+            loader,
+            encode,
+            decode,
+        }
+    }
+
+    /// Uses the given datatype codecs.
+    pub fn with_codecs(mut self, codecs: impl Into<Arc<Codecs>>) -> Self {
+        let codecs = codecs.into();
+        self.encode.codecs = codecs.clone();
+        self.decode.codecs = codecs;
+        self
+    }
+
+    /// Uses the given default tables.
+    pub fn with_default_tables(mut self, tables: Cow<'static, Tables>) -> Self {
+        let tables = Arc::new(tables.into_owned());
+        self.encode.default_tables = tables.clone();
+        self.decode.default_tables = tables;
+        self
+    }
+
+    /// Uses the given application compression tables registry.
+    pub fn with_registry(mut self, registry: Registry) -> Self {
+        self.encode.registry = registry.clone();
+        self.decode.registry = registry;
+        self
+    }
+
+    /// Uses the given processed context cache, possibly shared with other
+    /// sessions.
+    pub fn with_context_cache(mut self, cache: Arc<ContextCache>) -> Self {
+        self.encode.context_cache = Some(cache.clone());
+        self.decode.context_cache = Some(cache);
+        self
+    }
+
+    /// Checks loaded contexts against the given pinned digests.
+    pub fn with_context_pins(mut self, pins: ContextPins) -> Self {
+        self.encode.context_pins = pins.clone();
+        self.decode.context_pins = pins;
+        self
+    }
+
+    /// Sets the compression mode used for encoding.
+    pub fn with_compression_mode(mut self, compression_mode: CompressionMode) -> Self {
+        self.encode.compression_mode = compression_mode;
+        self
+    }
+
+    /// Replaces repeated values with references when encoding.
+    ///
+    /// See the [`references`](crate::references) module.
+    pub fn with_deduplication(mut self, deduplicate: bool) -> Self {
+        self.encode.deduplicate = deduplicate;
+        self
+    }
+
+    /// Records the original entry order and compact IRI spelling when
+    /// encoding, in a side channel wrapped with the given tag, so that
+    /// decoding restores the exact input document.
+    ///
+    /// See the [`lossless`](crate::lossless) module.
+    pub fn with_lossless_tag(mut self, tag: Option<u64>) -> Self {
+        self.encode.lossless_tag = tag;
+        self.decode.lossless_tag = tag;
+        self
+    }
+
+    /// Falls back to the uncompressed form instead of failing when encoding
+    /// or decoding.
+    ///
+    /// See the [`lenient`](crate::lenient) module.
+    pub fn with_lenient(mut self, lenient: bool) -> Self {
+        self.encode.lenient = lenient;
+        self.decode.lenient = lenient;
+        self
+    }
+
+    /// Collects lenient mode warnings in the given collector.
+    pub fn with_warnings(mut self, warnings: Arc<Warnings>) -> Self {
+        self.encode.warnings = Some(warnings.clone());
+        self.decode.warnings = Some(warnings);
+        self
+    }
+
+    pub fn codecs(&self) -> &Codecs {
+        &self.encode.codecs
+    }
+
+    pub fn default_tables(&self) -> &Tables {
+        &self.encode.default_tables
+    }
+
+    pub fn context_cache(&self) -> Option<&Arc<ContextCache>> {
+        self.encode.context_cache.as_ref()
+    }
+
+    pub fn compression_mode(&self) -> CompressionMode {
+        self.encode.compression_mode
+    }
+}
+
+impl<L: json_ld::Loader> CborLd<L> {
+    /// Encodes a JSON-LD document into CBOR-LD.
+    pub async fn encode(&self, json_ld_document: &JsonValue) -> Result<CborValue, EncodeError> {
+        let loader = self.encode.loader(&self.loader);
+        self.encode.encode(&loader, json_ld_document).await
+    }
+
+    /// Encodes a JSON-LD document into CBOR-LD bytes.
+    pub async fn encode_to_bytes(
+        &self,
+        json_ld_document: &JsonValue,
+    ) -> Result<Vec<u8>, EncodeError> {
+        self.encode(json_ld_document).await.map(cbor_into_bytes)
+    }
+
+    /// Reads a JSON-LD document from `reader` and writes its CBOR-LD encoding
+    /// to `writer` incrementally.
+    ///
+    /// See the [`stream`](crate::stream) module. Deduplication and lossless
+    /// encoding need the whole encoded document: when enabled, the output is
+    /// built in memory before being written.
+    pub async fn encode_stream(
+        &self,
+        reader: impl io::Read,
+        writer: impl io::Write,
+    ) -> Result<(), EncodeError> {
+        let loader = self.encode.loader(&self.loader);
+        self.encode.encode_stream(&loader, reader, writer).await
+    }
+
+    /// Decodes a CBOR-LD document.
+    ///
+    /// The compression mode is read from the document tag.
+    pub async fn decode(&self, cbor_ld_document: &CborValue) -> Result<JsonValue, DecodeError> {
+        let loader = self.decode.loader(&self.loader);
+        self.decode.decode(&loader, cbor_ld_document).await
+    }
+
+    /// Decodes CBOR-LD document bytes.
+    pub async fn decode_from_bytes(&self, bytes: &[u8]) -> Result<JsonValue, DecodeError> {
+        let cbor_ld_document = ciborium::from_reader(bytes)?;
+        self.decode(&cbor_ld_document).await
+    }
+
+    /// Reads a CBOR-LD document from `reader` and writes the decoded JSON-LD
+    /// document to `writer` incrementally.
+    ///
+    /// See the [`stream`](crate::stream) module.
+    pub async fn decode_stream(
+        &self,
+        reader: impl io::Read,
+        writer: impl io::Write,
+    ) -> Result<(), DecodeError> {
+        let loader = self.decode.loader(&self.loader);
+        self.decode.decode_stream(&loader, reader, writer).await
+    }
+}
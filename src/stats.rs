@@ -0,0 +1,371 @@
+//! Compression statistics.
+//!
+//! [`analyze`] encodes a document and reports where the bytes go: the size of
+//! every entry of the document, by JSON pointer, along with the codec or
+//! table that handled each value and the strings left uncompressed. This is
+//! useful to tune compression tables, for instance to fit a document into a
+//! QR code.
+//!
+//! With the `stats` feature, the report also includes the size of the JSON
+//! document compressed with gzip and brotli, for comparison.
+use std::{collections::HashMap, fmt, sync::Mutex};
+
+use iref::{IriRef, IriRefBuf};
+use json_ld::{syntax::Print, Type};
+
+use crate::{
+    encode::{tag_cbor_ld, EncodePipeline},
+    transform::{json_pointer, PathSegment},
+    CborValue, EncodeError, EncodeOptions, IdAllocator, JsonValue,
+};
+
+/// Codec or table handling a value.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Handler {
+    /// Context entry, replaced by its ID if it has one.
+    Context,
+
+    /// Node identifier, using the IRI codec of the given scheme if any.
+    Iri(Option<String>),
+
+    /// Vocabulary term, replaced by its ID if it has one.
+    Vocab,
+
+    /// Type table of the given datatype.
+    TypeTable(String),
+
+    /// Codec of the given datatype.
+    TypeCodec(String),
+
+    /// String dictionary.
+    Dictionary,
+
+    /// JSON literal codec.
+    Json,
+
+    /// Language tag codec.
+    Language,
+
+    /// Plain value: number, boolean, null or string.
+    Plain,
+}
+
+impl Handler {
+    /// Checks if this is the `@id` or `@vocab` codec, encoding IRIs as terms
+    /// when possible.
+    pub fn is_term_codec(&self) -> bool {
+        matches!(self, Self::TypeCodec(ty) if ty == "@id" || ty == "@vocab")
+    }
+}
+
+impl fmt::Display for Handler {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Context => write!(f, "context"),
+            Self::Iri(Some(scheme)) => write!(f, "`{scheme}` IRI codec"),
+            Self::Iri(None) => write!(f, "IRI"),
+            Self::Vocab => write!(f, "vocabulary term"),
+            Self::TypeTable(ty) => write!(f, "`{ty}` type table"),
+            Self::TypeCodec(ty) => write!(f, "`{ty}` codec"),
+            Self::Dictionary => write!(f, "string dictionary"),
+            Self::Json => write!(f, "JSON literal"),
+            Self::Language => write!(f, "language tag"),
+            Self::Plain => write!(f, "plain value"),
+        }
+    }
+}
+
+/// Values recorded while encoding a document, used to compute compression
+/// statistics and to generate compression tables, or while decoding a
+/// document, used to annotate it.
+#[derive(Debug, Default)]
+pub(crate) struct Recorder(Mutex<Recording>);
+
+#[derive(Debug, Default)]
+pub(crate) struct Recording {
+    /// Handlers of the transformed values, by JSON pointer into the input
+    /// document.
+    pub handlers: HashMap<String, Handler>,
+
+    /// Context entries, in order of appearance.
+    pub contexts: Vec<IriRefBuf>,
+
+    /// Typed string values, in order of appearance.
+    pub typed_values: Vec<(Type, String)>,
+}
+
+impl Recorder {
+    pub fn handler(&self, path: &[PathSegment], handler: Handler) {
+        self.0
+            .lock()
+            .unwrap()
+            .handlers
+            .insert(json_pointer(path), handler);
+    }
+
+    pub fn context(&self, iri_ref: &IriRef) {
+        self.0.lock().unwrap().contexts.push(iri_ref.to_owned())
+    }
+
+    pub fn typed_value(&self, type_: &Type, value: &str) {
+        self.0
+            .lock()
+            .unwrap()
+            .typed_values
+            .push((type_.clone(), value.to_owned()))
+    }
+
+    pub fn into_inner(self) -> Recording {
+        self.0.into_inner().unwrap()
+    }
+}
+
+/// Statistics of a document entry (or array item).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EntryStats {
+    /// JSON pointer of the entry.
+    pub path: String,
+
+    /// Depth of the entry, `1` for top-level properties.
+    pub depth: usize,
+
+    /// Size of the encoded entry (key and value), in bytes.
+    pub size: usize,
+
+    /// Size of the JSON entry (key and value), in bytes.
+    pub json_size: usize,
+
+    /// Codec or table that handled the value, if it is a leaf value.
+    pub handler: Option<Handler>,
+
+    /// String left uncompressed, if any.
+    pub uncompressed: Option<String>,
+}
+
+/// Compression statistics of a document.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Stats {
+    /// Size of the CBOR-LD document, in bytes.
+    pub size: usize,
+
+    /// Size of the compact JSON document, in bytes.
+    pub json_size: usize,
+
+    /// Size of the compact JSON document compressed with gzip, in bytes.
+    ///
+    /// Requires the `stats` feature.
+    pub gzip_size: Option<usize>,
+
+    /// Size of the compact JSON document compressed with brotli, in bytes.
+    ///
+    /// Requires the `stats` feature.
+    pub brotli_size: Option<usize>,
+
+    /// Entries of the document, depth first.
+    pub entries: Vec<EntryStats>,
+}
+
+impl Stats {
+    /// Ratio between the CBOR-LD and JSON sizes.
+    pub fn ratio(&self) -> f64 {
+        self.size as f64 / self.json_size as f64
+    }
+
+    /// Top-level properties of the document.
+    pub fn top_level(&self) -> impl Iterator<Item = &EntryStats> {
+        self.entries.iter().filter(|e| e.depth == 1)
+    }
+
+    /// Entries whose value is a string left uncompressed.
+    pub fn uncompressed_strings(&self) -> impl Iterator<Item = &EntryStats> {
+        self.entries.iter().filter(|e| e.uncompressed.is_some())
+    }
+}
+
+impl fmt::Display for Stats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let percent = |size: usize| 100.0 * size as f64 / self.json_size as f64;
+
+        writeln!(f, "JSON     {:>8} bytes", self.json_size)?;
+        if let Some(size) = self.gzip_size {
+            writeln!(f, "gzip     {size:>8} bytes ({:.1}%)", percent(size))?
+        }
+        if let Some(size) = self.brotli_size {
+            writeln!(f, "brotli   {size:>8} bytes ({:.1}%)", percent(size))?
+        }
+        writeln!(
+            f,
+            "CBOR-LD  {:>8} bytes ({:.1}%)",
+            self.size,
+            percent(self.size)
+        )?;
+
+        writeln!(f)?;
+        writeln!(f, "{:>8} {:>8}  path", "CBOR-LD", "JSON")?;
+        for entry in &self.entries {
+            write!(
+                f,
+                "{:>8} {:>8}  {}",
+                entry.size, entry.json_size, entry.path
+            )?;
+            if let Some(handler) = &entry.handler {
+                write!(f, " ({handler})")?
+            }
+            writeln!(f)?
+        }
+
+        let mut uncompressed = self.uncompressed_strings().peekable();
+        if uncompressed.peek().is_some() {
+            writeln!(f)?;
+            writeln!(f, "uncompressed strings:")?;
+            for entry in uncompressed {
+                let text = entry.uncompressed.as_deref().unwrap_or_default();
+                writeln!(f, "{:>8}  {} {text:?}", entry.size, entry.path)?
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Encodes a JSON-LD document using the given JSON-LD context loader and the
+/// default options, and reports its compression statistics.
+pub async fn analyze(
+    json_ld_document: &JsonValue,
+    loader: impl json_ld::Loader,
+) -> Result<Stats, EncodeError> {
+    analyze_with(json_ld_document, loader, Default::default()).await
+}
+
+/// Encodes a JSON-LD document using the given JSON-LD context loader and
+/// options, and reports its compression statistics.
+///
+/// Deduplication and lossless side channels are not applied. The
+/// uncompressed mode is not supported.
+pub async fn analyze_with(
+    json_ld_document: &JsonValue,
+    loader: impl json_ld::Loader,
+    options: EncodeOptions,
+) -> Result<Stats, EncodeError> {
+    let pipeline = EncodePipeline::from(options);
+    let mut encoder = pipeline.encoder(pipeline.loader(loader), pipeline.tables()?);
+    encoder.state.recorder = Some(Recorder::default());
+
+    let body = encoder.encode(json_ld_document).await?;
+    let recording = encoder.state.recorder.take().unwrap().into_inner();
+
+    let mut walker = Walker {
+        allocator: &encoder.state.allocator,
+        handlers: &recording.handlers,
+        path: Vec::new(),
+        entries: Vec::new(),
+    };
+    walker.walk(json_ld_document, &body);
+
+    let json = json_ld_document.compact_print().to_string();
+    let (gzip_size, brotli_size) = compressed_sizes(json.as_bytes());
+
+    Ok(Stats {
+        size: cbor_len(&tag_cbor_ld(pipeline.compression_mode, body)?),
+        json_size: json.len(),
+        gzip_size,
+        brotli_size,
+        entries: walker.entries,
+    })
+}
+
+/// Walks an input document along with its encoding.
+struct Walker<'a> {
+    allocator: &'a IdAllocator,
+    handlers: &'a HashMap<String, Handler>,
+    path: Vec<PathSegment>,
+    entries: Vec<EntryStats>,
+}
+
+impl Walker<'_> {
+    fn walk(&mut self, json: &JsonValue, cbor: &CborValue) {
+        match (json, cbor) {
+            (JsonValue::Object(object), CborValue::Map(map)) => {
+                for entry in object {
+                    let key = entry.key.as_str();
+                    let id = self.allocator.encode_term(key, entry.value.is_array());
+                    let cbor_entry = map.iter().find(|(cbor_key, _)| match cbor_key {
+                        CborValue::Integer(i) => id.is_some_and(|id| u64::try_from(*i) == Ok(id)),
+                        CborValue::Text(text) => text == key,
+                        _ => false,
+                    });
+
+                    if let Some((cbor_key, value)) = cbor_entry {
+                        let key_json_size = JsonValue::String(key.into())
+                            .compact_print()
+                            .to_string()
+                            .len();
+
+                        self.path.push(PathSegment::Key(key.to_owned(), None));
+                        self.entry(&entry.value, value, cbor_len(cbor_key), key_json_size + 1);
+                        self.path.pop();
+                    }
+                }
+            }
+            (JsonValue::Array(items), CborValue::Array(cbor_items))
+                if items.len() == cbor_items.len() =>
+            {
+                for (i, (item, cbor_item)) in items.iter().zip(cbor_items).enumerate() {
+                    self.path.push(PathSegment::Index(i));
+                    self.entry(item, cbor_item, 0, 0);
+                    self.path.pop();
+                }
+            }
+            _ => (),
+        }
+    }
+
+    fn entry(&mut self, json: &JsonValue, cbor: &CborValue, key_size: usize, key_json_size: usize) {
+        let path = json_pointer(&self.path);
+        let handler = self.handlers.get(&path).cloned();
+        let leaf = handler.is_some() || !matches!(cbor, CborValue::Map(_) | CborValue::Array(_));
+
+        self.entries.push(EntryStats {
+            path,
+            depth: self.path.len(),
+            size: key_size + cbor_len(cbor),
+            json_size: key_json_size + json.compact_print().to_string().len(),
+            handler,
+            uncompressed: match cbor {
+                CborValue::Text(text) if leaf => Some(text.clone()),
+                _ => None,
+            },
+        });
+
+        if !leaf {
+            self.walk(json, cbor)
+        }
+    }
+}
+
+pub(crate) fn cbor_len(value: &CborValue) -> usize {
+    let mut bytes = Vec::new();
+    ciborium::into_writer(value, &mut bytes).unwrap();
+    bytes.len()
+}
+
+/// Returns the sizes of the given bytes compressed with gzip and brotli.
+#[cfg(feature = "stats")]
+fn compressed_sizes(bytes: &[u8]) -> (Option<usize>, Option<usize>) {
+    use std::io::Write;
+
+    let mut gzip = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::best());
+    gzip.write_all(bytes).unwrap();
+    let gzip_size = gzip.finish().unwrap().len();
+
+    let mut brotli = brotli::CompressorWriter::new(Vec::new(), 4096, 11, 22);
+    brotli.write_all(bytes).unwrap();
+    let brotli_size = brotli.into_inner().len();
+
+    (Some(gzip_size), Some(brotli_size))
+}
+
+#[cfg(not(feature = "stats"))]
+fn compressed_sizes(_bytes: &[u8]) -> (Option<usize>, Option<usize>) {
+    (None, None)
+}
@@ -0,0 +1,935 @@
+//! Incremental input readers and output writers.
+//!
+//! When streaming, the root node of a document is written entry by entry as
+//! soon as each entry is transformed, and the items of its plural entries
+//! (such as the items of a large collection) one by one. Entries transformed
+//! out of output order are buffered as bytes until they can be written.
+//!
+//! On the input side, the document is read as bytes, but only the root node
+//! is parsed. Its arrays of node objects are deferred: each item is parsed
+//! when transformed, and dropped afterwards. The whole input must still be
+//! read before transforming it, since JSON-LD context processing may depend
+//! on entries appearing anywhere in the root node, and CBOR maps carry no
+//! ordering guarantee either.
+use std::{collections::BTreeMap, io, ops::Range};
+
+use json_ld::syntax::Parse;
+
+use crate::{
+    keywords::KEYWORDS_MAP,
+    transform::{DeferredEntries, NodeSink},
+    CborValue, DecodeError, EncodeError, JsonObject, JsonValue,
+};
+
+/// Output format of a [`StreamWriter`].
+pub trait StreamFormat {
+    type Key: Clone + PartialOrd;
+    type Value;
+
+    fn write_map_start(writer: &mut impl io::Write, len: usize) -> io::Result<()>;
+
+    fn write_map_end(writer: &mut impl io::Write) -> io::Result<()>;
+
+    fn write_array_start(writer: &mut impl io::Write, len: usize) -> io::Result<()>;
+
+    fn write_array_end(writer: &mut impl io::Write) -> io::Result<()>;
+
+    /// Writes the separator preceding the entry or item of the given index.
+    fn write_separator(writer: &mut impl io::Write, index: usize) -> io::Result<()>;
+
+    fn write_key(writer: &mut impl io::Write, key: &Self::Key) -> io::Result<()>;
+
+    fn write_value(writer: &mut impl io::Write, value: &Self::Value) -> io::Result<()>;
+}
+
+/// JSON output.
+pub struct Json;
+
+impl StreamFormat for Json {
+    type Key = json_ld::syntax::object::Key;
+    type Value = JsonValue;
+
+    fn write_map_start(writer: &mut impl io::Write, _len: usize) -> io::Result<()> {
+        writer.write_all(b"{")
+    }
+
+    fn write_map_end(writer: &mut impl io::Write) -> io::Result<()> {
+        writer.write_all(b"}")
+    }
+
+    fn write_array_start(writer: &mut impl io::Write, _len: usize) -> io::Result<()> {
+        writer.write_all(b"[")
+    }
+
+    fn write_array_end(writer: &mut impl io::Write) -> io::Result<()> {
+        writer.write_all(b"]")
+    }
+
+    fn write_separator(writer: &mut impl io::Write, index: usize) -> io::Result<()> {
+        if index > 0 {
+            writer.write_all(b",")?
+        }
+
+        Ok(())
+    }
+
+    fn write_key(writer: &mut impl io::Write, key: &Self::Key) -> io::Result<()> {
+        write!(writer, "{}:", JsonValue::String(key.as_str().into()))
+    }
+
+    fn write_value(writer: &mut impl io::Write, value: &Self::Value) -> io::Result<()> {
+        write!(writer, "{value}")
+    }
+}
+
+/// CBOR output.
+pub struct Cbor;
+
+impl Cbor {
+    /// Writes the head of a CBOR data item (RFC 8949 §3).
+    pub fn write_head(writer: &mut impl io::Write, major: u8, n: u64) -> io::Result<()> {
+        let major = major << 5;
+        match n {
+            0..=23 => writer.write_all(&[major | n as u8]),
+            24..=0xff => writer.write_all(&[major | 24, n as u8]),
+            0x100..=0xffff => {
+                writer.write_all(&[major | 25])?;
+                writer.write_all(&(n as u16).to_be_bytes())
+            }
+            0x10000..=0xffff_ffff => {
+                writer.write_all(&[major | 26])?;
+                writer.write_all(&(n as u32).to_be_bytes())
+            }
+            _ => {
+                writer.write_all(&[major | 27])?;
+                writer.write_all(&n.to_be_bytes())
+            }
+        }
+    }
+}
+
+impl StreamFormat for Cbor {
+    type Key = CborValue;
+    type Value = CborValue;
+
+    fn write_map_start(writer: &mut impl io::Write, len: usize) -> io::Result<()> {
+        Self::write_head(writer, 5, len as u64)
+    }
+
+    fn write_map_end(_writer: &mut impl io::Write) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn write_array_start(writer: &mut impl io::Write, len: usize) -> io::Result<()> {
+        Self::write_head(writer, 4, len as u64)
+    }
+
+    fn write_array_end(_writer: &mut impl io::Write) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn write_separator(_writer: &mut impl io::Write, _index: usize) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn write_key(writer: &mut impl io::Write, key: &Self::Key) -> io::Result<()> {
+        Self::write_value(writer, key)
+    }
+
+    fn write_value(writer: &mut impl io::Write, value: &Self::Value) -> io::Result<()> {
+        ciborium::into_writer(value, writer).map_err(|e| match e {
+            ciborium::ser::Error::Io(e) => e,
+            ciborium::ser::Error::Value(msg) => io::Error::new(io::ErrorKind::InvalidData, msg),
+        })
+    }
+}
+
+/// Input format of a streamed document.
+pub trait StreamInput: Sized {
+    type Key: Clone + PartialEq;
+    type Value;
+    type Error;
+
+    /// Parses the value at the start of the given bytes.
+    fn parse(bytes: &[u8]) -> Result<Self::Value, Self::Error>;
+
+    /// Converts a parsed value into an object key.
+    fn key(value: Self::Value) -> Option<Self::Key>;
+
+    /// Checks if the given key is the `@context` entry key.
+    fn is_context(key: &Self::Key) -> bool;
+
+    fn empty_array() -> Self::Value;
+
+    fn object(entries: Vec<(Self::Key, Self::Value)>) -> Self::Value;
+
+    /// Scans the object starting at `pos` in `bytes`, returning its entries.
+    ///
+    /// Returns `None` if `bytes` does not hold an object there, or holds
+    /// more than one value (when the format forbids it).
+    fn scan_object(bytes: &[u8], pos: usize) -> Option<Vec<ScannedEntry>>;
+}
+
+/// Node object entry located by [`StreamInput::scan_object`].
+pub struct ScannedEntry {
+    key: Range<usize>,
+    value: Range<usize>,
+
+    /// Items of the value, if it is a non-empty array of objects.
+    items: Option<Vec<Range<usize>>>,
+}
+
+/// Deferred entry of a node object read by [`read_node`].
+struct DeferredEntry<K> {
+    key: K,
+    value: Range<usize>,
+    items: Vec<Range<usize>>,
+}
+
+/// Entries of a node object read by [`read_node`], parsed on demand from the
+/// input bytes.
+pub struct Deferred<F: StreamInput> {
+    bytes: Vec<u8>,
+    entries: Vec<DeferredEntry<F::Key>>,
+}
+
+impl<F: StreamInput> Deferred<F> {
+    fn get(&self, key: &F::Key) -> Option<&DeferredEntry<F::Key>> {
+        self.entries.iter().find(|e| e.key == *key)
+    }
+}
+
+impl<F: StreamInput> DeferredEntries<F::Key, F::Value, F::Error> for Deferred<F> {
+    fn len(&self, key: &F::Key) -> Option<usize> {
+        self.get(key).map(|e| e.items.len())
+    }
+
+    fn item(&self, key: &F::Key, index: usize) -> Option<Result<F::Value, F::Error>> {
+        let span = self.get(key)?.items.get(index)?;
+        Some(F::parse(&self.bytes[span.clone()]))
+    }
+
+    fn value(&self, key: &F::Key) -> Option<Result<F::Value, F::Error>> {
+        let span = &self.get(key)?.value;
+        Some(F::parse(&self.bytes[span.clone()]))
+    }
+}
+
+/// Reads the node object starting at `pos` in `bytes`, deferring its
+/// entries holding arrays of objects.
+///
+/// Deferred entries hold an empty array in the returned object. The bytes
+/// are given back if they do not hold a node object with unique keys there:
+/// they must then be parsed as a whole, reporting the error if any.
+pub fn read_node<F: StreamInput>(
+    bytes: Vec<u8>,
+    pos: usize,
+) -> Result<(F::Value, Deferred<F>), Vec<u8>> {
+    let Some(scanned) = F::scan_object(&bytes, pos) else {
+        return Err(bytes);
+    };
+
+    let mut entries: Vec<(F::Key, F::Value)> = Vec::with_capacity(scanned.len());
+    let mut deferred = Vec::new();
+    for entry in scanned {
+        let Some(key) = F::parse(&bytes[entry.key]).ok().and_then(F::key) else {
+            return Err(bytes);
+        };
+
+        if entries.iter().any(|(k, _)| *k == key) {
+            return Err(bytes);
+        }
+
+        let value = match entry.items {
+            Some(items) if !F::is_context(&key) => {
+                deferred.push(DeferredEntry {
+                    key: key.clone(),
+                    value: entry.value,
+                    items,
+                });
+                F::empty_array()
+            }
+            _ => match F::parse(&bytes[entry.value]) {
+                Ok(value) => value,
+                Err(_) => return Err(bytes),
+            },
+        };
+
+        entries.push((key, value))
+    }
+
+    Ok((
+        F::object(entries),
+        Deferred {
+            bytes,
+            entries: deferred,
+        },
+    ))
+}
+
+impl StreamInput for Json {
+    type Key = json_ld::syntax::object::Key;
+    type Value = JsonValue;
+    type Error = EncodeError;
+
+    fn parse(bytes: &[u8]) -> Result<JsonValue, EncodeError> {
+        JsonValue::parse_slice(bytes)
+            .map(|(value, _)| value)
+            .map_err(|e| EncodeError::InvalidJson(e.to_string()))
+    }
+
+    fn key(value: JsonValue) -> Option<Self::Key> {
+        value
+            .as_str()
+            .map(|key| Self::Key::from_string(key.to_owned()))
+    }
+
+    fn is_context(key: &Self::Key) -> bool {
+        key.as_str() == "@context"
+    }
+
+    fn empty_array() -> JsonValue {
+        JsonValue::Array(Vec::new())
+    }
+
+    fn object(entries: Vec<(Self::Key, JsonValue)>) -> JsonValue {
+        JsonValue::Object(entries.into_iter().collect::<JsonObject>())
+    }
+
+    fn scan_object(bytes: &[u8], pos: usize) -> Option<Vec<ScannedEntry>> {
+        JsonScanner { bytes, pos }.object()
+    }
+}
+
+/// JSON value boundaries scanner.
+struct JsonScanner<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl JsonScanner<'_> {
+    fn peek(&self) -> Option<u8> {
+        self.bytes.get(self.pos).copied()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while matches!(self.peek(), Some(b' ' | b'\t' | b'\n' | b'\r')) {
+            self.pos += 1
+        }
+    }
+
+    fn expect(&mut self, byte: u8) -> Option<()> {
+        self.skip_whitespace();
+        (self.peek()? == byte).then(|| self.pos += 1)
+    }
+
+    fn object(mut self) -> Option<Vec<ScannedEntry>> {
+        self.expect(b'{')?;
+        let mut entries = Vec::new();
+        self.skip_whitespace();
+        if self.peek()? == b'}' {
+            self.pos += 1
+        } else {
+            loop {
+                self.skip_whitespace();
+                if self.peek()? != b'"' {
+                    return None;
+                }
+
+                let key = self.value()?;
+                self.expect(b':')?;
+                self.skip_whitespace();
+                let start = self.pos;
+                let items = self.items();
+                if items.is_none() {
+                    self.pos = start;
+                    self.value()?;
+                }
+
+                let items = items.filter(|items| {
+                    !items.is_empty() && items.iter().all(|item| self.bytes[item.start] == b'{')
+                });
+                entries.push(ScannedEntry {
+                    key,
+                    value: start..self.pos,
+                    items,
+                });
+
+                self.skip_whitespace();
+                match self.peek()? {
+                    b',' => self.pos += 1,
+                    b'}' => {
+                        self.pos += 1;
+                        break;
+                    }
+                    _ => return None,
+                }
+            }
+        }
+
+        self.skip_whitespace();
+        (self.pos == self.bytes.len()).then_some(entries)
+    }
+
+    /// Scans the array at the current position, returning the span of each
+    /// item.
+    fn items(&mut self) -> Option<Vec<Range<usize>>> {
+        self.expect(b'[')?;
+        let mut items = Vec::new();
+        self.skip_whitespace();
+        if self.peek()? == b']' {
+            self.pos += 1;
+            return Some(items);
+        }
+
+        loop {
+            items.push(self.value()?);
+            self.skip_whitespace();
+            match self.peek()? {
+                b',' => self.pos += 1,
+                b']' => {
+                    self.pos += 1;
+                    return Some(items);
+                }
+                _ => return None,
+            }
+        }
+    }
+
+    /// Skips the value at the current position, returning its span.
+    fn value(&mut self) -> Option<Range<usize>> {
+        self.skip_whitespace();
+        let start = self.pos;
+        let mut depth = 0usize;
+        loop {
+            match self.peek()? {
+                b'"' => self.string()?,
+                b'{' | b'[' => {
+                    depth += 1;
+                    self.pos += 1
+                }
+                b'}' | b']' => {
+                    depth = depth.checked_sub(1)?;
+                    self.pos += 1
+                }
+                _ if depth == 0 => {
+                    while !matches!(
+                        self.peek(),
+                        None | Some(b',' | b']' | b'}' | b' ' | b'\t' | b'\n' | b'\r')
+                    ) {
+                        self.pos += 1
+                    }
+                }
+                _ => self.pos += 1,
+            }
+
+            if depth == 0 {
+                break;
+            }
+        }
+
+        (self.pos > start).then_some(start..self.pos)
+    }
+
+    fn string(&mut self) -> Option<()> {
+        self.pos += 1;
+        loop {
+            match self.peek()? {
+                b'\\' => self.pos += 2,
+                b'"' => {
+                    self.pos += 1;
+                    return Some(());
+                }
+                _ => self.pos += 1,
+            }
+        }
+    }
+}
+
+impl Cbor {
+    /// Reads a CBOR-LD document, deferring the entries of its root node
+    /// holding arrays of node objects.
+    ///
+    /// The bytes are given back if the document body is not a node object
+    /// (such as a document with value references or a lossless side
+    /// channel): they must then be parsed as a whole.
+    pub fn read_document(bytes: Vec<u8>) -> Result<(CborValue, Deferred<Self>), Vec<u8>> {
+        let mut scanner = CborScanner {
+            bytes: &bytes,
+            pos: 0,
+        };
+
+        match scanner.head() {
+            Some((6, info, tag)) if info != 31 => {
+                let pos = scanner.pos;
+                read_node::<Self>(bytes, pos)
+                    .map(|(body, deferred)| (CborValue::Tag(tag, Box::new(body)), deferred))
+            }
+            _ => Err(bytes),
+        }
+    }
+}
+
+impl StreamInput for Cbor {
+    type Key = CborValue;
+    type Value = CborValue;
+    type Error = DecodeError;
+
+    fn parse(bytes: &[u8]) -> Result<CborValue, DecodeError> {
+        ciborium::from_reader(bytes).map_err(Into::into)
+    }
+
+    fn key(value: CborValue) -> Option<CborValue> {
+        Some(value)
+    }
+
+    fn is_context(key: &CborValue) -> bool {
+        let context_id = KEYWORDS_MAP.get_id("@context").unwrap();
+        key.as_integer()
+            .and_then(|i| u64::try_from(i).ok())
+            .is_some_and(|i| i == context_id || i == context_id + 1)
+    }
+
+    fn empty_array() -> CborValue {
+        CborValue::Array(Vec::new())
+    }
+
+    fn object(entries: Vec<(CborValue, CborValue)>) -> CborValue {
+        CborValue::Map(entries)
+    }
+
+    fn scan_object(bytes: &[u8], pos: usize) -> Option<Vec<ScannedEntry>> {
+        CborScanner { bytes, pos }.object()
+    }
+}
+
+/// Maximum nesting depth of the data items skipped by [`CborScanner`].
+const MAX_CBOR_DEPTH: usize = 256;
+
+/// CBOR data item boundaries scanner (RFC 8949 §3).
+struct CborScanner<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl CborScanner<'_> {
+    /// Reads the head of the data item at the current position: its major
+    /// type, additional information and argument.
+    fn head(&mut self) -> Option<(u8, u8, u64)> {
+        let initial = *self.bytes.get(self.pos)?;
+        self.pos += 1;
+        let (major, info) = (initial >> 5, initial & 0x1f);
+        let len = match info {
+            0..=23 => return Some((major, info, info as u64)),
+            24 => 1,
+            25 => 2,
+            26 => 4,
+            27 => 8,
+            31 => return Some((major, info, 0)),
+            _ => return None,
+        };
+
+        let argument = self.bytes.get(self.pos..self.pos + len)?;
+        self.pos += len;
+        Some((
+            major,
+            info,
+            argument.iter().fold(0, |n, b| n << 8 | *b as u64),
+        ))
+    }
+
+    fn object(mut self) -> Option<Vec<ScannedEntry>> {
+        let (5, info, len) = self.head()? else {
+            return None;
+        };
+
+        if info == 31 {
+            return None;
+        }
+
+        let mut entries = Vec::new();
+        for _ in 0..len {
+            let key = self.item(0)?;
+            let start = self.pos;
+            let items = self.items();
+            if items.is_none() {
+                self.pos = start;
+                self.item(0)?;
+            }
+
+            let items = items.filter(|items| {
+                !items.is_empty() && items.iter().all(|item| self.bytes[item.start] >> 5 == 5)
+            });
+            entries.push(ScannedEntry {
+                key,
+                value: start..self.pos,
+                items,
+            });
+        }
+
+        Some(entries)
+    }
+
+    /// Scans the definite-length array at the current position, returning
+    /// the span of each item.
+    fn items(&mut self) -> Option<Vec<Range<usize>>> {
+        let (4, info, len) = self.head()? else {
+            return None;
+        };
+
+        if info == 31 {
+            return None;
+        }
+
+        let mut items = Vec::new();
+        for _ in 0..len {
+            items.push(self.item(1)?)
+        }
+
+        Some(items)
+    }
+
+    /// Skips the data item at the current position, returning its span.
+    fn item(&mut self, depth: usize) -> Option<Range<usize>> {
+        if depth > MAX_CBOR_DEPTH {
+            return None;
+        }
+
+        let start = self.pos;
+        let (major, info, n) = self.head()?;
+        let indefinite = info == 31;
+        match major {
+            0 | 1 | 6 | 7 if indefinite => return None,
+            0 | 1 | 7 => (),
+            2..=5 if indefinite => {
+                while *self.bytes.get(self.pos)? != 0xff {
+                    self.item(depth + 1)?;
+                }
+                self.pos += 1
+            }
+            2 | 3 => {
+                self.pos = self.pos.checked_add(usize::try_from(n).ok()?)?;
+                if self.pos > self.bytes.len() {
+                    return None;
+                }
+            }
+            4 => {
+                for _ in 0..n {
+                    self.item(depth + 1)?;
+                }
+            }
+            5 => {
+                for _ in 0..n {
+                    self.item(depth + 1)?;
+                    self.item(depth + 1)?;
+                }
+            }
+            _ => {
+                self.item(depth + 1)?;
+            }
+        }
+
+        Some(start..self.pos)
+    }
+}
+
+/// Plural entry being written item by item.
+struct Items {
+    rank: usize,
+    count: usize,
+    buffer: Option<Vec<u8>>,
+}
+
+/// Node sink writing the entries of a node to an [`io::Write`] as soon as
+/// possible, in output key order.
+pub struct StreamWriter<F: StreamFormat, W> {
+    writer: W,
+    keys: Vec<F::Key>,
+    next: usize,
+    pending: BTreeMap<usize, Vec<u8>>,
+    items: Option<Items>,
+}
+
+impl<F: StreamFormat, W: io::Write> StreamWriter<F, W> {
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer,
+            keys: Vec::new(),
+            next: 0,
+            pending: BTreeMap::new(),
+            items: None,
+        }
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+
+    /// Position of the given key in the output.
+    ///
+    /// Fails if the key was not announced by [`NodeSink::begin`].
+    fn rank(&self, key: &F::Key) -> io::Result<usize> {
+        self.keys
+            .iter()
+            .position(|k| k == key)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "unannounced node entry"))
+    }
+
+    /// Writes the given entry prefix (separator and key) to `writer`.
+    fn write_entry_start(writer: &mut impl io::Write, rank: usize, key: &F::Key) -> io::Result<()> {
+        F::write_separator(writer, rank)?;
+        F::write_key(writer, key)
+    }
+
+    /// Marks the entry of rank `next` as written, and writes the pending
+    /// entries that follow.
+    fn advance(&mut self) -> io::Result<()> {
+        self.next += 1;
+        while let Some(bytes) = self.pending.remove(&self.next) {
+            self.writer.write_all(&bytes)?;
+            self.next += 1;
+        }
+
+        Ok(())
+    }
+}
+
+impl<F, W, E> NodeSink<F::Key, F::Value, E> for StreamWriter<F, W>
+where
+    F: StreamFormat,
+    W: io::Write,
+    E: From<io::Error>,
+{
+    fn begin(&mut self, keys: &[&F::Key]) -> Result<(), E> {
+        self.keys = keys.iter().map(|&k| k.clone()).collect();
+        self.keys.sort_unstable_by(|a, b| a.partial_cmp(b).unwrap());
+        F::write_map_start(&mut self.writer, self.keys.len())?;
+        Ok(())
+    }
+
+    fn entry(&mut self, key: F::Key, value: F::Value) -> Result<(), E> {
+        let rank = self.rank(&key)?;
+        if rank == self.next {
+            Self::write_entry_start(&mut self.writer, rank, &key)?;
+            F::write_value(&mut self.writer, &value)?;
+            self.advance()?;
+        } else {
+            let mut bytes = Vec::new();
+            Self::write_entry_start(&mut bytes, rank, &key)?;
+            F::write_value(&mut bytes, &value)?;
+            self.pending.insert(rank, bytes);
+        }
+
+        Ok(())
+    }
+
+    fn begin_items(&mut self, key: F::Key, len: usize) -> Result<(), E> {
+        let rank = self.rank(&key)?;
+        let buffer = if rank == self.next {
+            Self::write_entry_start(&mut self.writer, rank, &key)?;
+            F::write_array_start(&mut self.writer, len)?;
+            None
+        } else {
+            let mut bytes = Vec::new();
+            Self::write_entry_start(&mut bytes, rank, &key)?;
+            F::write_array_start(&mut bytes, len)?;
+            Some(bytes)
+        };
+
+        self.items = Some(Items {
+            rank,
+            count: 0,
+            buffer,
+        });
+
+        Ok(())
+    }
+
+    fn item(&mut self, value: F::Value) -> Result<(), E> {
+        if let Some(items) = &mut self.items {
+            match &mut items.buffer {
+                Some(bytes) => {
+                    F::write_separator(bytes, items.count)?;
+                    F::write_value(bytes, &value)?;
+                }
+                None => {
+                    F::write_separator(&mut self.writer, items.count)?;
+                    F::write_value(&mut self.writer, &value)?;
+                }
+            }
+
+            items.count += 1;
+        }
+
+        Ok(())
+    }
+
+    fn end_items(&mut self) -> Result<(), E> {
+        if let Some(items) = self.items.take() {
+            match items.buffer {
+                Some(mut bytes) => {
+                    F::write_array_end(&mut bytes)?;
+                    self.pending.insert(items.rank, bytes);
+                }
+                None => {
+                    F::write_array_end(&mut self.writer)?;
+                    self.advance()?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn end(&mut self) -> Result<(), E> {
+        if self.next != self.keys.len() {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "missing node entries").into());
+        }
+
+        F::write_map_end(&mut self.writer)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{read_node, Cbor, Json, StreamWriter};
+    use crate::{
+        transform::{DeferredEntries, NodeSink},
+        CborValue, JsonValue,
+    };
+
+    #[test]
+    fn deferred_json_entries() {
+        let input = r#"{
+            "@context": [{"a": "b"}],
+            "items": [{"a": 1}, {"b": "]}\"", "c": [{}]}],
+            "mixed": [{}, 1],
+            "empty": []
+        }"#;
+        let (json, deferred) = read_node::<Json>(input.as_bytes().to_vec(), 0)
+            .ok()
+            .unwrap();
+        let expected: JsonValue = input.parse().unwrap();
+        let object = json.as_object().unwrap();
+        let expected_object = expected.as_object().unwrap();
+
+        for key in ["@context", "mixed", "empty"] {
+            assert_eq!(deferred.len(&key.into()), None);
+            assert_eq!(object.get(key).next(), expected_object.get(key).next())
+        }
+
+        let key = "items".into();
+        let items = expected_object
+            .get("items")
+            .next()
+            .unwrap()
+            .as_array()
+            .unwrap();
+        assert_eq!(
+            object.get("items").next(),
+            Some(&JsonValue::Array(Vec::new()))
+        );
+        assert_eq!(deferred.len(&key), Some(2));
+        assert_eq!(deferred.item(&key, 1).unwrap().unwrap(), items[1]);
+        assert_eq!(
+            deferred.value(&key).unwrap().unwrap(),
+            JsonValue::Array(items.to_vec())
+        )
+    }
+
+    #[test]
+    fn invalid_json_node() {
+        for input in [
+            r#"{"a": [{}, }"#,
+            r#"{"a": [{}]} {}"#,
+            r#"{"a": [{}], "a": 1}"#,
+            r#"[{"a": [{}]}]"#,
+        ] {
+            assert!(read_node::<Json>(input.as_bytes().to_vec(), 0).is_err())
+        }
+    }
+
+    #[test]
+    fn deferred_cbor_entries() {
+        let node = |i: i64| CborValue::Map(vec![(CborValue::from(100), CborValue::from(i))]);
+        let items = CborValue::Array(vec![node(1), node(2)]);
+        let document = CborValue::Tag(
+            0x0601,
+            Box::new(CborValue::Map(vec![
+                (CborValue::from(1), items.clone()),
+                (CborValue::from(101), items.clone()),
+                (
+                    CborValue::from(103),
+                    CborValue::Array(vec![node(1), 1.into()]),
+                ),
+            ])),
+        );
+        let mut bytes = Vec::new();
+        ciborium::into_writer(&document, &mut bytes).unwrap();
+
+        let (cbor, deferred) = Cbor::read_document(bytes).ok().unwrap();
+        let CborValue::Tag(0x0601, body) = cbor else {
+            panic!("expected CBOR-LD tag")
+        };
+        let entries = body.as_map().unwrap();
+        assert_eq!(entries[0].1, items);
+        assert_eq!(entries[1].1, CborValue::Array(Vec::new()));
+        assert_eq!(deferred.len(&1.into()), None);
+        assert_eq!(deferred.len(&103.into()), None);
+        assert_eq!(deferred.len(&101.into()), Some(2));
+        assert_eq!(deferred.item(&101.into(), 1).unwrap().unwrap(), node(2));
+        assert_eq!(deferred.value(&101.into()).unwrap().unwrap(), items);
+
+        // Documents with a tagged body are parsed as a whole.
+        let document = CborValue::Tag(0x0601, Box::new(CborValue::Tag(256, Box::new(items))));
+        let mut bytes = Vec::new();
+        ciborium::into_writer(&document, &mut bytes).unwrap();
+        assert!(Cbor::read_document(bytes).is_err())
+    }
+
+    #[test]
+    fn unannounced_entry() {
+        let mut writer = StreamWriter::<Cbor, _>::new(Vec::new());
+        let sink: &mut dyn NodeSink<CborValue, CborValue, std::io::Error> = &mut writer;
+        sink.begin(&[&CborValue::from(0)]).unwrap();
+        assert!(sink.entry(CborValue::from(2), CborValue::from(10)).is_err())
+    }
+
+    #[test]
+    fn missing_entries() {
+        let mut writer = StreamWriter::<Cbor, _>::new(Vec::new());
+        let keys = [CborValue::from(0), CborValue::from(2)];
+        let sink: &mut dyn NodeSink<CborValue, CborValue, std::io::Error> = &mut writer;
+        sink.begin(&keys.iter().collect::<Vec<_>>()).unwrap();
+        sink.entry(keys[1].clone(), CborValue::from(10)).unwrap();
+        assert!(sink.end().is_err())
+    }
+
+    #[test]
+    fn out_of_order_entries() {
+        let mut writer = StreamWriter::<Cbor, _>::new(Vec::new());
+        let keys = [CborValue::from(0), CborValue::from(2), CborValue::from(4)];
+        let sink: &mut dyn NodeSink<CborValue, CborValue, std::io::Error> = &mut writer;
+        sink.begin(&keys.iter().collect::<Vec<_>>()).unwrap();
+        sink.entry(keys[0].clone(), CborValue::from(10)).unwrap();
+        sink.begin_items(keys[2].clone(), 2).unwrap();
+        sink.item(CborValue::from(1)).unwrap();
+        sink.item(CborValue::from(2)).unwrap();
+        sink.end_items().unwrap();
+        sink.entry(keys[1].clone(), CborValue::from(11)).unwrap();
+        sink.end().unwrap();
+
+        let expected = CborValue::Map(vec![
+            (keys[0].clone(), CborValue::from(10)),
+            (keys[1].clone(), CborValue::from(11)),
+            (
+                keys[2].clone(),
+                CborValue::Array(vec![CborValue::from(1), CborValue::from(2)]),
+            ),
+        ]);
+        let mut expected_bytes = Vec::new();
+        ciborium::into_writer(&expected, &mut expected_bytes).unwrap();
+        assert_eq!(writer.into_inner(), expected_bytes)
+    }
+}
@@ -0,0 +1,216 @@
+use std::collections::HashMap;
+
+/// Common verifiable credential vocabulary.
+///
+/// The most common entries come first so they are referenced with a single
+/// byte. Entry positions are part of the CBOR-LD output and must never be
+/// changed: new entries can only be appended.
+pub const CREDENTIAL_VOCABULARY: [&str; 81] = [
+    "https://",
+    "did:example:",
+    "did:key:",
+    "did:web:",
+    "Verifiable Credential",
+    "Permanent Resident Card",
+    "Permanent Resident",
+    "Government of ",
+    "Department of ",
+    "University",
+    "Certificate",
+    "Credential",
+    "License",
+    "Driver",
+    "Male",
+    "Female",
+    "United States",
+    "Example",
+    "Bachelor of ",
+    "Master of ",
+    "Degree",
+    " of ",
+    "Science",
+    "Engineering",
+    "http://",
+    "www.",
+    ".com",
+    ".org",
+    ".gov",
+    ".edu",
+    "did:",
+    "urn:uuid:",
+    "data:image/png;base64,",
+    "data:image/jpeg;base64,",
+    "Identity",
+    "Card",
+    "Issuer",
+    "Citizen",
+    "Passport",
+    "Employee",
+    "Membership",
+    "Badge",
+    "Achievement",
+    "Course",
+    "Education",
+    "Institute",
+    "College",
+    "School",
+    "Technology",
+    "National",
+    "International",
+    "Association",
+    "Corporation",
+    "Company",
+    "Inc.",
+    "Ltd.",
+    "Authority",
+    "Agency",
+    "Ministry of ",
+    "Republic of ",
+    "State of ",
+    "County",
+    "City of ",
+    "Health",
+    "Vaccination",
+    "Certification",
+    "Permit",
+    "Registration",
+    "Number",
+    "Address",
+    "Street",
+    "Avenue",
+    "the ",
+    " and ",
+    "for ",
+    "Verification",
+    "Proof",
+    "Status",
+    "Revocation",
+    "Digital",
+    "Official",
+];
+
+/// String dictionary.
+///
+/// Shared by encoders and decoders to compress string literals that have no
+/// other codec, by substituting dictionary entries with their index.
+///
+/// Compressed strings are announced with the dictionary tag. No CBOR tag is
+/// registered for this purpose, so the tag is chosen by the application,
+/// which must make sure it is not otherwise used in its documents.
+#[derive(Debug, Clone)]
+pub struct StringDictionary {
+    tag: u64,
+
+    entries: Vec<String>,
+
+    /// Entry indexes grouped by first byte, longest entries first.
+    by_first_byte: HashMap<u8, Vec<u64>>,
+}
+
+impl StringDictionary {
+    /// Creates an empty dictionary whose compressed strings are announced
+    /// with the given tag.
+    pub fn new(tag: u64) -> Self {
+        Self {
+            tag,
+            entries: Vec::new(),
+            by_first_byte: HashMap::new(),
+        }
+    }
+
+    /// Creates a dictionary with the given tag and entries.
+    pub fn from_entries<'a>(tag: u64, entries: impl IntoIterator<Item = &'a str>) -> Self {
+        let mut result = Self::new(tag);
+
+        for entry in entries {
+            result.push(entry.to_owned());
+        }
+
+        result
+    }
+
+    /// Creates a dictionary with the given tag and the
+    /// [`CREDENTIAL_VOCABULARY`] entries.
+    pub fn credentials(tag: u64) -> Self {
+        Self::from_entries(tag, CREDENTIAL_VOCABULARY)
+    }
+
+    /// Tag announcing strings compressed with this dictionary.
+    pub fn tag(&self) -> u64 {
+        self.tag
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn get(&self, index: u64) -> Option<&str> {
+        usize::try_from(index)
+            .ok()
+            .and_then(|i| self.entries.get(i))
+            .map(String::as_str)
+    }
+
+    /// Returns an iterator over the entries, by index.
+    pub fn iter(&self) -> impl Iterator<Item = &str> {
+        self.entries.iter().map(String::as_str)
+    }
+
+    /// Appends a new entry to the dictionary, returning its index.
+    ///
+    /// Empty entries are ignored.
+    pub fn push(&mut self, entry: String) -> Option<u64> {
+        let first_byte = *entry.as_bytes().first()?;
+        let index = self.entries.len() as u64;
+        let len = entry.len();
+        self.entries.push(entry);
+
+        let candidates = self.by_first_byte.entry(first_byte).or_default();
+        let i = candidates.partition_point(|&j| self.entries[j as usize].len() >= len);
+        candidates.insert(i, index);
+
+        Some(index)
+    }
+
+    /// Finds the longest entry that is a prefix of `value`.
+    pub fn longest_prefix_of(&self, value: &str) -> Option<(u64, usize)> {
+        let candidates = self.by_first_byte.get(value.as_bytes().first()?)?;
+        candidates.iter().find_map(|&index| {
+            let entry = &self.entries[index as usize];
+            value
+                .starts_with(entry.as_str())
+                .then_some((index, entry.len()))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{StringDictionary, CREDENTIAL_VOCABULARY};
+    use std::collections::HashSet;
+
+    #[test]
+    fn unique_entries() {
+        let entries: HashSet<_> = CREDENTIAL_VOCABULARY.iter().collect();
+        assert_eq!(entries.len(), CREDENTIAL_VOCABULARY.len())
+    }
+
+    #[test]
+    fn longest_prefix() {
+        let dictionary = StringDictionary::credentials(0);
+        assert_eq!(
+            dictionary.longest_prefix_of("Permanent Resident Card."),
+            Some((5, 23))
+        );
+        assert_eq!(
+            dictionary.longest_prefix_of("Permanent Residents"),
+            Some((6, 18))
+        );
+        assert_eq!(dictionary.longest_prefix_of("Perm"), None);
+        assert_eq!(dictionary.longest_prefix_of(""), None);
+    }
+}
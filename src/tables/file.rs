@@ -0,0 +1,191 @@
+use std::collections::{BTreeMap, HashSet};
+
+use iref::{IriBuf, IriRefBuf};
+use json_ld::Type;
+use serde::{de::Error as _, Deserialize, Deserializer, Serialize, Serializer};
+
+use super::{ContextTable, StringDictionary, Tables, TypeTable};
+
+/// Serialized compression tables, see [`Tables`].
+#[derive(Serialize, Deserialize)]
+struct TablesFile {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    strings: Option<Vec<String>>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    strings_tag: Option<u64>,
+
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    context: BTreeMap<String, u64>,
+
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    types: BTreeMap<String, BTreeMap<String, u64>>,
+}
+
+/// Invalid compression tables file.
+#[derive(Debug, thiserror::Error)]
+pub enum InvalidTables {
+    #[error("invalid context IRI reference `{0}`")]
+    ContextIri(String),
+
+    #[error("duplicate context ID {0}")]
+    DuplicateContextId(u64),
+
+    #[error("invalid datatype `{0}`")]
+    Datatype(String),
+
+    #[error("duplicate ID {1} in the `{0}` type table")]
+    DuplicateTypeId(String, u64),
+
+    #[error("empty string dictionary entry")]
+    EmptyDictionaryEntry,
+
+    #[error("missing string dictionary tag")]
+    MissingDictionaryTag,
+}
+
+impl Serialize for Tables {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        TablesFile {
+            strings: self
+                .strings
+                .as_ref()
+                .map(|dictionary| dictionary.iter().map(ToOwned::to_owned).collect()),
+            strings_tag: self.strings.as_ref().map(StringDictionary::tag),
+            context: self
+                .context
+                .iter()
+                .map(|(iri_ref, id)| (iri_ref.as_str().to_owned(), id))
+                .collect(),
+            types: self
+                .types
+                .iter()
+                .map(|(type_, table)| {
+                    let table = table.iter().map(|(v, id)| (v.to_owned(), id)).collect();
+                    (type_.as_str().to_owned(), table)
+                })
+                .collect(),
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Tables {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        TablesFile::deserialize(deserializer)?
+            .try_into()
+            .map_err(D::Error::custom)
+    }
+}
+
+impl TryFrom<TablesFile> for Tables {
+    type Error = InvalidTables;
+
+    fn try_from(file: TablesFile) -> Result<Self, Self::Error> {
+        let mut context = ContextTable::default();
+        for (iri_ref, id) in file.context {
+            if context.get_iri_ref(id).is_some() {
+                return Err(InvalidTables::DuplicateContextId(id));
+            }
+
+            let iri_ref = IriRefBuf::new(iri_ref).map_err(|e| InvalidTables::ContextIri(e.0))?;
+            context.insert(iri_ref, id);
+        }
+
+        let mut types = std::collections::HashMap::new();
+        for (type_, values) in file.types {
+            let mut table = TypeTable::new();
+            let mut ids = HashSet::new();
+            for (value, id) in values {
+                if !ids.insert(id) {
+                    return Err(InvalidTables::DuplicateTypeId(type_, id));
+                }
+
+                table.insert(value, id);
+            }
+
+            types.insert(parse_type(type_)?, table);
+        }
+
+        // Empty entries would shift the index of the next ones.
+        if file.strings.iter().flatten().any(String::is_empty) {
+            return Err(InvalidTables::EmptyDictionaryEntry);
+        }
+
+        let strings = match file.strings {
+            Some(entries) => {
+                let tag = file
+                    .strings_tag
+                    .ok_or(InvalidTables::MissingDictionaryTag)?;
+                Some(StringDictionary::from_entries(
+                    tag,
+                    entries.iter().map(String::as_str),
+                ))
+            }
+            None => None,
+        };
+
+        Ok(Self {
+            context,
+            types,
+            strings,
+        })
+    }
+}
+
+fn parse_type(type_: String) -> Result<Type, InvalidTables> {
+    match type_.as_str() {
+        "@id" => Ok(Type::Id),
+        "@json" => Ok(Type::Json),
+        "@none" => Ok(Type::None),
+        "@vocab" => Ok(Type::Vocab),
+        _ => IriBuf::new(type_)
+            .map(Type::Iri)
+            .map_err(|e| InvalidTables::Datatype(e.0)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tables::{RegistryEntry, Tables};
+    use std::borrow::Cow;
+
+    #[test]
+    fn vc_barcodes_round_trip() {
+        let tables = RegistryEntry::VcBarcodes
+            .tables(Cow::Owned(Tables::default()))
+            .unwrap();
+        let text = toml::to_string(tables.as_ref()).unwrap();
+        let parsed: Tables = toml::from_str(&text).unwrap();
+
+        assert_eq!(parsed.context.len(), 3);
+        assert_eq!(toml::to_string(&parsed).unwrap(), text);
+    }
+
+    #[test]
+    fn duplicate_context_id() {
+        let text = r#"
+            [context]
+            "https://example.org/a" = 32768
+            "https://example.org/b" = 32768
+        "#;
+
+        assert!(toml::from_str::<Tables>(text).is_err())
+    }
+
+    #[test]
+    fn string_dictionary() {
+        let text = r#"
+            strings = ["https://", "did:example:"]
+            strings_tag = 40960
+        "#;
+
+        let tables: Tables = toml::from_str(text).unwrap();
+        let dictionary = tables.strings.unwrap();
+        assert_eq!(dictionary.tag(), 40960);
+        assert_eq!(dictionary.get(1), Some("did:example:"));
+
+        let text = r#"strings = ["https://"]"#;
+        assert!(toml::from_str::<Tables>(text).is_err())
+    }
+}
@@ -0,0 +1,192 @@
+use std::{collections::HashMap, hash::Hash, sync::Arc};
+
+use json_ld::Type;
+
+use super::{ContextTable, RegistryEntry, Tables, TypeTable};
+use crate::{
+    encode::{tag_cbor_ld, EncodePipeline},
+    stats::{cbor_len, Recorder},
+    CompressionMode, EncodeError, EncodeOptions, JsonValue,
+};
+
+/// Compression tables generation options.
+#[derive(Debug)]
+pub struct GenerateOptions {
+    /// Options used to encode the corpus.
+    ///
+    /// The tables of the compression mode are the baseline of the generated
+    /// tables: their IDs are kept, and the size savings are estimated against
+    /// them.
+    pub encode: EncodeOptions,
+
+    /// Minimum number of occurrences of a typed value in the corpus for it
+    /// to be added to its type table.
+    pub min_occurrences: usize,
+
+    /// Maximum number of distinct values of an enumerated datatype.
+    ///
+    /// Datatypes with more distinct values in the corpus, such as
+    /// identifiers, get no type table.
+    pub max_type_values: usize,
+}
+
+impl Default for GenerateOptions {
+    fn default() -> Self {
+        Self {
+            encode: EncodeOptions::default(),
+            min_occurrences: 2,
+            max_type_values: 256,
+        }
+    }
+}
+
+/// Generated compression tables, with an estimate of the size savings.
+#[derive(Debug, Clone)]
+pub struct GeneratedTables {
+    /// Proposed compression tables.
+    pub tables: Tables,
+
+    /// Number of documents in the corpus.
+    pub documents: usize,
+
+    /// Size of the corpus encoded with the baseline tables, in bytes.
+    pub size_before: usize,
+
+    /// Size of the corpus encoded with the proposed tables, in bytes.
+    pub size_after: usize,
+}
+
+impl GeneratedTables {
+    /// Number of bytes saved on the corpus by the proposed tables.
+    pub fn saved(&self) -> usize {
+        self.size_before.saturating_sub(self.size_after)
+    }
+}
+
+/// Proposes compression tables for the given corpus of JSON-LD documents.
+///
+/// Every context found in the corpus gets an ID, and every datatype without
+/// codec whose values are enumerated gets a type table listing the values
+/// occurring often, most frequent first. Documents encoded with the proposed
+/// tables use the [`RegistryEntry::Default`] compression mode, the tables
+/// being the default tables of the encoder and decoder. The corpus cannot be
+/// encoded in the uncompressed mode.
+pub async fn generate_tables(
+    corpus: &[JsonValue],
+    loader: impl json_ld::Loader,
+    options: GenerateOptions,
+) -> Result<GeneratedTables, EncodeError> {
+    let pipeline = EncodePipeline::from(options.encode);
+    let loader = pipeline.loader(loader);
+    let baseline = pipeline.tables()?;
+
+    let mut compressor = pipeline.encoder(&loader, baseline.clone());
+    compressor.state.recorder = Some(Recorder::default());
+
+    let mut size_before = 0;
+    for document in corpus {
+        compressor.state.reset_allocator();
+        let body = compressor.encode(document).await?;
+        size_before += cbor_len(&tag_cbor_ld(pipeline.compression_mode, body)?);
+    }
+
+    let recording = compressor.state.recorder.take().unwrap().into_inner();
+
+    let mut context = ContextTable::default();
+    let contexts = by_frequency(recording.contexts);
+    for iri_ref in &contexts {
+        if let Some(id) = baseline.context.get_id(iri_ref) {
+            context.insert(iri_ref.clone(), id)
+        }
+    }
+    for iri_ref in contexts {
+        if context.get_id(&iri_ref).is_none() {
+            let id = context.next_id();
+            context.insert(iri_ref, id)
+        }
+    }
+
+    let mut typed_values: HashMap<Type, Vec<String>> = HashMap::new();
+    for (type_, value) in recording.typed_values {
+        if matches!(type_, Type::Iri(_)) && pipeline.codecs.type_.get(&type_).is_none() {
+            typed_values.entry(type_).or_default().push(value)
+        }
+    }
+
+    let mut types = HashMap::new();
+    for (type_, values) in typed_values {
+        let counts = count(values);
+        if counts.len() > options.max_type_values {
+            continue;
+        }
+
+        let values = sorted_by_count(
+            counts
+                .into_iter()
+                .filter(|(_, n)| *n >= options.min_occurrences)
+                .collect(),
+        );
+
+        let base = baseline.types.get(&type_);
+        let mut table = TypeTable::new();
+        for value in &values {
+            if let Some(id) = base.and_then(|base| base.get_id(value)) {
+                table.insert(value.clone(), id)
+            }
+        }
+        for value in values {
+            if table.get_id(&value).is_none() {
+                let id = table.iter().map(|(_, id)| id + 1).max().unwrap_or(1);
+                table.insert(value, id)
+            }
+        }
+
+        if !table.is_empty() {
+            types.insert(type_, table);
+        }
+    }
+
+    let tables = Tables {
+        context,
+        types,
+        strings: baseline.strings.clone(),
+    };
+
+    let mut compressor = pipeline.encoder(&loader, Arc::new(tables.clone()));
+
+    let mut size_after = 0;
+    for document in corpus {
+        compressor.state.reset_allocator();
+        let body = compressor.encode(document).await?;
+        let mode = CompressionMode::Compressed(RegistryEntry::Default);
+        size_after += cbor_len(&tag_cbor_ld(mode, body)?);
+    }
+
+    Ok(GeneratedTables {
+        tables,
+        documents: corpus.len(),
+        size_before,
+        size_after,
+    })
+}
+
+fn count<T: Eq + Hash>(items: Vec<T>) -> HashMap<T, usize> {
+    let mut counts = HashMap::new();
+    for item in items {
+        *counts.entry(item).or_default() += 1
+    }
+
+    counts
+}
+
+/// Sorts the given items by decreasing count, then in increasing order.
+fn sorted_by_count<T: Ord>(counts: Vec<(T, usize)>) -> Vec<T> {
+    let mut counts = counts;
+    counts.sort_by(|(a, m), (b, n)| n.cmp(m).then_with(|| a.cmp(b)));
+    counts.into_iter().map(|(item, _)| item).collect()
+}
+
+/// Returns the distinct given items, most frequent first.
+fn by_frequency<T: Eq + Hash + Ord>(items: Vec<T>) -> Vec<T> {
+    sorted_by_count(count(items).into_iter().collect())
+}
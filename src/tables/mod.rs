@@ -2,17 +2,57 @@ use iref::{Iri, IriRef, IriRefBuf};
 use json_ld::Type;
 use std::collections::HashMap;
 
+mod dictionary;
+mod file;
+mod generator;
 mod registry;
+pub use dictionary::*;
+pub use file::*;
+pub use generator::*;
 pub use registry::*;
 
 use crate::{CborValue, DecodeError, JsonValue};
 
+/// Compression tables.
+///
+/// Compression tables are serialized as follows (here in TOML, the tables
+/// file format loaded by the command-line interface):
+///
+/// ```toml
+/// # String dictionary entries, by index (optional), and the tag announcing
+/// # dictionary-compressed strings (required with `strings`). Tables with a
+/// # dictionary must be bound to a registry entry (`--registry-entry`).
+/// strings = ["https://", "did:example:"]
+/// strings_tag = 40960
+///
+/// # Context IDs.
+/// [context]
+/// "https://www.w3.org/ns/credentials/v2" = 32768
+///
+/// # Type tables, by datatype IRI (or `@id`, `@vocab`, `@json`, `@none`).
+/// [types."https://w3id.org/security#cryptosuiteString"]
+/// ecdsa-rdfc-2019 = 1
+/// ecdsa-sd-2023 = 2
+/// ```
+///
+/// [`generate_tables`] proposes compression tables for a corpus of
+/// documents.
 #[derive(Debug, Default, Clone)]
 pub struct Tables {
     pub context: ContextTable,
     pub types: HashMap<Type, TypeTable>,
+
+    /// String dictionary.
+    ///
+    /// When set, string literals without codec are compressed using this
+    /// dictionary and back-references to previous strings of the document.
+    /// Such tables must be bound to an application [`Registry`] entry.
+    pub strings: Option<StringDictionary>,
 }
 
+/// First context ID of the application-specific range.
+pub const FIRST_CUSTOM_CONTEXT_ID: u64 = 0x8000;
+
 #[derive(Debug, Default, Clone)]
 pub struct ContextTable {
     forward: HashMap<IriRefBuf, u64>,
@@ -32,6 +72,29 @@ impl ContextTable {
         self.forward.insert(iri.clone(), i);
         self.backward.insert(i, iri);
     }
+
+    pub fn len(&self) -> usize {
+        self.forward.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.forward.is_empty()
+    }
+
+    /// Returns an iterator over the context IRI references and their IDs.
+    pub fn iter(&self) -> impl Iterator<Item = (&IriRef, u64)> {
+        self.forward.iter().map(|(iri, i)| (iri.as_iri_ref(), *i))
+    }
+
+    /// Smallest application-specific ID greater than every ID of the table.
+    pub fn next_id(&self) -> u64 {
+        self.backward
+            .keys()
+            .max()
+            .map_or(FIRST_CUSTOM_CONTEXT_ID, |max| {
+                (max + 1).max(FIRST_CUSTOM_CONTEXT_ID)
+            })
+    }
 }
 
 impl<'a> FromIterator<(&'a Iri, u64)> for ContextTable {
@@ -79,6 +142,8 @@ impl TypeTable {
                 let value = self.get_value(id).ok_or(DecodeError::InvalidValue)?;
                 Ok(JsonValue::String(value.into()))
             }
+            // Value absent from the table.
+            CborValue::Text(value) => Ok(JsonValue::String(value.as_str().into())),
             _ => Err(DecodeError::InvalidValue),
         }
     }
@@ -87,6 +152,19 @@ impl TypeTable {
         self.forward.insert(value.clone(), id);
         self.backward.insert(id, value);
     }
+
+    pub fn len(&self) -> usize {
+        self.forward.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.forward.is_empty()
+    }
+
+    /// Returns an iterator over the values and their IDs.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, u64)> {
+        self.forward.iter().map(|(value, id)| (value.as_str(), *id))
+    }
 }
 
 impl<'a> FromIterator<(&'a str, u64)> for TypeTable {
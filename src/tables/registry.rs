@@ -1,10 +1,10 @@
-use std::borrow::Cow;
+use std::{borrow::Cow, collections::HashMap, sync::Arc};
 
 use json_ld::Type;
 use lazy_static::lazy_static;
 use static_iref::iri;
 
-use crate::Tables;
+use crate::{CompressionMode, Tables};
 
 /// Compression tables registry entry.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
@@ -17,7 +17,8 @@ pub enum RegistryEntry {
     /// See: <https://w3c-ccg.github.io/vc-barcodes/>
     VcBarcodes,
 
-    /// Unknown compression table.
+    /// Compression table unknown to this library, possibly bound by the
+    /// application (see [`Registry`]).
     Unknown(u64),
 }
 
@@ -54,6 +55,68 @@ impl RegistryEntry {
 #[error("unknown compression table `{0}`")]
 pub struct UnknownCompressionTable(pub u64);
 
+/// Application compression tables registry.
+///
+/// Binds compression tables to registry entries unknown to this library.
+/// Tables with a string dictionary must be bound to such an entry: since the
+/// registry entry is part of the CBOR-LD tag, a decoder without the
+/// dictionary then fails with [`UnknownCompressionTable`] instead of
+/// misreading the dictionary-compressed strings.
+#[derive(Debug, Default, Clone)]
+pub struct Registry {
+    entries: HashMap<u64, Arc<Tables>>,
+}
+
+impl Registry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Binds the given tables to the given registry entry.
+    ///
+    /// Fails if the entry is reserved (uncompressed mode or known to this
+    /// library), or does not fit in the CBOR-LD tag.
+    pub fn insert(&mut self, id: u64, tables: Tables) -> Result<(), ReservedRegistryEntry> {
+        let mode = CompressionMode::from_id(id);
+        match mode {
+            CompressionMode::Compressed(RegistryEntry::Unknown(_)) if mode.tag().is_some() => {
+                self.entries.insert(id, Arc::new(tables));
+                Ok(())
+            }
+            _ => Err(ReservedRegistryEntry(id)),
+        }
+    }
+
+    pub fn get(&self, id: u64) -> Option<&Tables> {
+        self.entries.get(&id).map(Arc::as_ref)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Returns the compression tables of the given registry entry.
+    pub(crate) fn resolve(
+        &self,
+        entry: RegistryEntry,
+        default: &Arc<Tables>,
+    ) -> Result<Arc<Tables>, UnknownCompressionTable> {
+        match entry {
+            RegistryEntry::Default => Ok(default.clone()),
+            RegistryEntry::Unknown(id) => self
+                .entries
+                .get(&id)
+                .cloned()
+                .ok_or(UnknownCompressionTable(id)),
+            entry => Ok(Arc::new(entry.tables(Cow::Borrowed(default))?.into_owned())),
+        }
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("registry entry `{0}` cannot be bound to application tables")]
+pub struct ReservedRegistryEntry(pub u64);
+
 lazy_static! {
     static ref VC_BARCODES: Tables = {
         Tables {
@@ -77,6 +140,7 @@ lazy_static! {
             )]
             .into_iter()
             .collect(),
+            strings: None,
         }
     };
 }
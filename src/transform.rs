@@ -1,13 +1,14 @@
 use iref::{Iri, IriBuf, IriRef, IriRefBuf};
-use json_ld::{
-    context::TermDefinitionRef,
-    syntax::{is_keyword, Keyword},
-    Process,
-};
-use std::borrow::Cow;
+use json_ld::{context::TermDefinitionRef, syntax::Keyword, ContainerKind, Process};
+use std::{borrow::Cow, fmt, sync::Arc};
 
 use crate::{
+    cache::{ContextCache, ContextCacheKey, ProcessedContext, EMPTY_CONTEXT_FINGERPRINT},
+    codecs::StringCodec,
     keywords::{FIRST_CUSTOM_TERM_ID, KEYWORDS_MAP},
+    lenient::{Warning, WarningKind, Warnings},
+    stats::Recorder,
+    tables::ContextTable,
     CborObject, CborValue, Codecs, DecodeError, EncodeError, IdAllocator, JsonObject, JsonValue,
     Tables,
 };
@@ -160,19 +161,243 @@ pub struct MissingKeyTerm<T>(pub T);
 
 pub struct UndefinedTerm(pub String);
 
+/// Segment of the path from the document root to the value being
+/// transformed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PathSegment {
+    /// Object entry, with the term of its key once known.
+    Key(String, Option<String>),
+
+    /// Array item.
+    Index(usize),
+}
+
+/// Location of an error in the input document.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ErrorLocation {
+    /// Path to the faulty value: a JSON pointer (RFC 6901) when encoding, a
+    /// CBOR path (such as `$.100[0].104`) when decoding.
+    pub path: String,
+
+    /// Active term, if any.
+    pub term: Option<String>,
+}
+
+impl ErrorLocation {
+    /// Creates a location from a path, as a JSON pointer.
+    pub fn json_pointer(path: &[PathSegment]) -> Self {
+        Self {
+            path: json_pointer(path),
+            term: active_term(path),
+        }
+    }
+
+    /// Creates a location from a path, as a CBOR path whose keys are written
+    /// in diagnostic notation.
+    pub fn cbor_path(path: &[PathSegment]) -> Self {
+        let mut cbor_path = "$".to_owned();
+
+        for segment in path {
+            match segment {
+                PathSegment::Key(key, _) => {
+                    cbor_path.push('.');
+                    cbor_path.push_str(key)
+                }
+                PathSegment::Index(i) => cbor_path.push_str(&format!("[{i}]")),
+            }
+        }
+
+        Self {
+            path: cbor_path,
+            term: active_term(path),
+        }
+    }
+}
+
+/// Writes a path as a JSON pointer (RFC 6901).
+pub(crate) fn json_pointer(path: &[PathSegment]) -> String {
+    let mut pointer = String::new();
+
+    for segment in path {
+        pointer.push('/');
+        match segment {
+            PathSegment::Key(key, _) => {
+                pointer.push_str(&key.replace('~', "~0").replace('/', "~1"))
+            }
+            PathSegment::Index(i) => pointer.push_str(&i.to_string()),
+        }
+    }
+
+    pointer
+}
+
+/// Returns the innermost known term of the given path.
+fn active_term(path: &[PathSegment]) -> Option<String> {
+    path.iter().rev().find_map(|segment| match segment {
+        PathSegment::Key(_, term) => term.clone(),
+        PathSegment::Index(_) => None,
+    })
+}
+
+impl fmt::Display for ErrorLocation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.path.is_empty() {
+            write!(f, "at the document root")?
+        } else {
+            write!(f, "at `{}`", self.path)?
+        }
+
+        match &self.term {
+            Some(term) => write!(f, ", term `{term}`"),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Error to which the location where it occurred can be attached.
+pub trait LocatedError {
+    /// Attaches the location given by `path` to the error, unless it already
+    /// has one.
+    fn at(self, path: &[PathSegment]) -> Self;
+}
+
+/// Receiver of the output entries of a node object.
+///
+/// Entries are reported in processing order, which may differ from the
+/// output key order. Plural entries transformed item by item are reported
+/// using [`Self::begin_items`], [`Self::item`] and [`Self::end_items`].
+pub trait NodeSink<K, V, E> {
+    /// Starts the node, announcing the keys of all its entries.
+    fn begin(&mut self, keys: &[&K]) -> Result<(), E>;
+
+    fn entry(&mut self, key: K, value: V) -> Result<(), E>;
+
+    fn begin_items(&mut self, key: K, len: usize) -> Result<(), E>;
+
+    fn item(&mut self, value: V) -> Result<(), E>;
+
+    fn end_items(&mut self) -> Result<(), E>;
+
+    fn end(&mut self) -> Result<(), E>;
+}
+
+/// Entries of a node object read on demand.
+///
+/// A deferred entry holds an empty array in the node object given to the
+/// transformer. Its items are read one at a time when transformed, or as a
+/// whole when the entry cannot be transformed item by item. See the
+/// [`stream`](crate::stream) module.
+pub trait DeferredEntries<K, V, E> {
+    /// Number of items of the given entry, or `None` if it is not deferred.
+    fn len(&self, key: &K) -> Option<usize>;
+
+    /// Reads the item at the given index of the given entry, or returns
+    /// `None` if it is not deferred.
+    fn item(&self, key: &K, index: usize) -> Option<Result<V, E>>;
+
+    /// Reads the whole value of the given entry, or returns `None` if it is
+    /// not deferred.
+    fn value(&self, key: &K) -> Option<Result<V, E>>;
+}
+
+/// Node object without deferred entries.
+pub struct NoDeferredEntries;
+
+impl<K, V, E> DeferredEntries<K, V, E> for NoDeferredEntries {
+    fn len(&self, _key: &K) -> Option<usize> {
+        None
+    }
+
+    fn item(&self, _key: &K, _index: usize) -> Option<Result<V, E>> {
+        None
+    }
+
+    fn value(&self, _key: &K) -> Option<Result<V, E>> {
+        None
+    }
+}
+
+/// Node sink collecting the entries into an object, sorted by key.
+pub struct CollectNode<K, V> {
+    entries: Vec<(K, V)>,
+    items: Option<(K, Vec<V>)>,
+}
+
+impl<K, V> CollectNode<K, V> {
+    pub fn new() -> Self {
+        Self {
+            entries: Vec::new(),
+            items: None,
+        }
+    }
+
+    pub fn into_object<O>(mut self) -> O
+    where
+        K: PartialOrd,
+        O: TransformedObject<Key = K, Value = V>,
+    {
+        self.entries
+            .sort_unstable_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+        O::new(self.entries)
+    }
+}
+
+impl<K, V> Default for CollectNode<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K, V: TransformedValue, E> NodeSink<K, V, E> for CollectNode<K, V> {
+    fn begin(&mut self, keys: &[&K]) -> Result<(), E> {
+        self.entries.reserve(keys.len());
+        Ok(())
+    }
+
+    fn entry(&mut self, key: K, value: V) -> Result<(), E> {
+        self.entries.push((key, value));
+        Ok(())
+    }
+
+    fn begin_items(&mut self, key: K, len: usize) -> Result<(), E> {
+        self.items = Some((key, Vec::with_capacity(len)));
+        Ok(())
+    }
+
+    fn item(&mut self, value: V) -> Result<(), E> {
+        if let Some((_, items)) = &mut self.items {
+            items.push(value)
+        }
+
+        Ok(())
+    }
+
+    fn end_items(&mut self) -> Result<(), E> {
+        if let Some((key, items)) = self.items.take() {
+            self.entries.push((key, V::new_array(items)))
+        }
+
+        Ok(())
+    }
+
+    fn end(&mut self) -> Result<(), E> {
+        Ok(())
+    }
+}
+
 pub struct ExpectedObject;
 
 pub struct InvalidTypeKind;
 
 pub trait Transformer {
-    type Input: TransformedValue<Object = Self::InputObject>;
+    type Input: Clone + TransformedValue<Object = Self::InputObject>;
     type Output: TransformedValue<Object = Self::OutputObject>;
 
     type InputObject: TransformedObject<Key = Self::InputKey, Value = Self::Input>;
     type OutputObject: TransformedObject<Key = Self::OutputKey, Value = Self::Output>;
 
     type InputKey: ToOwned;
-    type OutputKey: PartialOrd;
+    type OutputKey: Clone + PartialOrd;
 
     type Loader: json_ld::Loader;
     type Error: From<json_ld::context_processing::Error>
@@ -180,7 +405,9 @@ pub trait Transformer {
         + From<DuplicateKey<Self::InputKey>>
         + From<MissingKeyTerm<<Self::InputKey as ToOwned>::Owned>>
         + From<UndefinedTerm>
-        + From<InvalidTypeKind>;
+        + From<InvalidTypeKind>
+        + LocatedError
+        + fmt::Display;
 
     fn context_iri_ref(&self, value: &Self::Input) -> Result<IriRefBuf, Self::Error>;
 
@@ -222,8 +449,77 @@ pub trait Transformer {
         value: &Self::Input,
     ) -> Result<Self::Output, Self::Error>;
 
+    /// Use the JSON codec to transform a JSON literal (`@json` typed) value.
+    fn transform_json(&self, value: &Self::Input) -> Result<Self::Output, Self::Error>;
+
+    /// Use the language codec to transform a language tag value.
+    fn transform_language(&self, value: &Self::Input) -> Result<Self::Output, Self::Error>;
+
+    /// Use the language codec to transform a language map key.
+    fn language_key(&self, key: &Self::InputKey) -> Result<Self::OutputKey, Self::Error>;
+
     fn state_and_loader_mut(&mut self) -> (&mut TransformerState, &mut Self::Loader);
 
+    /// Returns the path segment text of the given input key.
+    fn path_key(&self, key: &Self::InputKey) -> String;
+
+    /// Reports a lenient mode fallback at the current location.
+    fn warn(&self, kind: WarningKind);
+
+    /// Returns the output key of an entry kept uncompressed, its value left
+    /// as-is, if any.
+    ///
+    /// `defined` tells if the key term is defined by the active context.
+    fn uncompressed_key(&self, key: &Self::InputKey, defined: bool) -> Option<Self::OutputKey>;
+
+    /// Returns the definition and output of an inline context kept as-is,
+    /// if the given context entry is one.
+    fn inline_context(
+        &self,
+        value: &Self::Input,
+    ) -> Result<Option<(json_ld::syntax::Context, Self::Output)>, Self::Error>;
+
+    /// Starts expanding a node object, if the output is expanded.
+    fn begin_expanded_node(&mut self) {}
+
+    /// Expands a transformed entry of the current node object, or an item of
+    /// a plural entry, if the output is expanded.
+    ///
+    /// The value is `None` when a plural entry starts.
+    fn expand_entry(
+        &mut self,
+        _active_context: &json_ld::Context,
+        _term: &str,
+        _def: Option<TermDefinitionRef>,
+        _value: Option<&Self::Output>,
+    ) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    /// Ends expanding the current node object, if the output is expanded.
+    fn end_expanded_node(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    /// Enters a value of the document, before transforming it.
+    fn enter(&mut self, segment: PathSegment) {
+        self.state_and_loader_mut().0.path.push(segment)
+    }
+
+    /// Leaves the value entered last, locating the error of its
+    /// transformation, if any.
+    fn leave<T>(&mut self, result: Result<T, Self::Error>) -> Result<T, Self::Error> {
+        let path = &mut self.state_and_loader_mut().0.path;
+        let result = result.map_err(|e| e.at(path));
+        path.pop();
+        result
+    }
+
+    /// Attaches the current location to the given error.
+    fn locate(&mut self, error: Self::Error) -> Self::Error {
+        error.at(&self.state_and_loader_mut().0.path)
+    }
+
     #[allow(async_fn_in_trait)]
     async fn process_global_context<'c>(
         &mut self,
@@ -236,10 +532,12 @@ pub trait Transformer {
                 let mut active_context = Cow::Borrowed(active_context);
                 let mut cbor_entries = Vec::with_capacity(entries.len());
 
-                for entry in entries {
-                    let (cbor_value, new_active_context) = self
+                for (i, entry) in entries.iter().enumerate() {
+                    self.enter(PathSegment::Index(i));
+                    let result = self
                         .process_global_context_entry(&active_context, entry, propagate)
-                        .await?;
+                        .await;
+                    let (cbor_value, new_active_context) = self.leave(result)?;
 
                     active_context = Cow::Owned(new_active_context);
                     cbor_entries.push(cbor_value)
@@ -269,8 +567,18 @@ pub trait Transformer {
         //     .parse()
         //     .map_err(|_| EncodeError::InvalidContextEntry)?;
 
+        if let Some((context, value)) = self.inline_context(context_value)? {
+            let new_active_context = self
+                .process_context(active_context, &context, propagate)
+                .await?;
+            return Ok((value, new_active_context));
+        }
+
         let context_iri_ref = self.context_iri_ref(context_value)?;
         let id = self.context_id(context_value, &context_iri_ref);
+        self.state_and_loader_mut()
+            .0
+            .learn_context(&context_iri_ref);
         let context = json_ld::syntax::Context::iri_ref(context_iri_ref);
         let new_active_context = self
             .process_context(active_context, &context, propagate)
@@ -288,35 +596,57 @@ pub trait Transformer {
     ) -> Result<json_ld::Context, Self::Error> {
         let (state, loader) = self.state_and_loader_mut();
 
-        let result = context
-            .process_with(
-                &mut (),
-                active_context,
-                loader,
-                None,
-                json_ld::context_processing::Options {
-                    propagate,
-                    ..Default::default()
-                },
-            )
-            .await?
-            .into_processed();
-
-        let mut keys: Vec<_> = result
-            .definitions()
-            .iter()
-            .map(|d| d.term().as_str())
-            .collect();
-        keys.sort_unstable();
+        let cache_key = state
+            .context_cache
+            .as_ref()
+            .map(|_| ContextCacheKey::new(state.active_context_fingerprint, context, propagate));
 
-        // Allocate ids.
-        for key in keys {
-            if !is_keyword(key) {
-                state.allocator.allocate(key);
+        let cached = state
+            .context_cache
+            .as_ref()
+            .zip(cache_key.as_ref())
+            .and_then(|(cache, key)| cache.get(key));
+
+        let processed = match cached {
+            Some(processed) => processed,
+            None => {
+                let result = context
+                    .process_with(
+                        &mut (),
+                        active_context,
+                        loader,
+                        None,
+                        json_ld::context_processing::Options {
+                            propagate,
+                            ..Default::default()
+                        },
+                    )
+                    .await?
+                    .into_processed();
+
+                match state.context_cache.as_ref().zip(cache_key) {
+                    Some((cache, key)) => {
+                        let fingerprint = key.fingerprint();
+                        cache.insert(key, ProcessedContext::new(result, fingerprint))
+                    }
+                    None => Arc::new(ProcessedContext::new(
+                        result,
+                        state.active_context_fingerprint,
+                    )),
+                }
             }
+        };
+
+        // Allocate ids.
+        for term in &processed.terms {
+            state.allocator.allocate(term);
         }
 
-        Ok(result)
+        state.active_context_fingerprint = processed.fingerprint;
+        Ok(match Arc::try_unwrap(processed) {
+            Ok(processed) => processed.context,
+            Err(processed) => processed.context.clone(),
+        })
     }
 
     #[allow(async_fn_in_trait)]
@@ -330,7 +660,7 @@ pub trait Transformer {
                 .transform_node(active_context, object)
                 .await
                 .map(Self::Output::new_object),
-            None => Err(ExpectedObject.into()),
+            None => Err(self.locate(ExpectedObject.into())),
         }
     }
 
@@ -340,6 +670,28 @@ pub trait Transformer {
         active_context: &json_ld::Context,
         object: &Self::InputObject,
     ) -> Result<Self::OutputObject, Self::Error> {
+        let mut sink = CollectNode::new();
+        self.transform_node_into(active_context, object, &NoDeferredEntries, &mut sink)
+            .await?;
+        Ok(sink.into_object())
+    }
+
+    #[allow(async_fn_in_trait)]
+    /// Transforms a node object, reporting the output entries to the given
+    /// sink as soon as they are transformed.
+    ///
+    /// The values of the `deferred` entries are read when transformed.
+    async fn transform_node_into<D, S>(
+        &mut self,
+        active_context: &json_ld::Context,
+        object: &Self::InputObject,
+        deferred: &D,
+        sink: &mut S,
+    ) -> Result<(), Self::Error>
+    where
+        D: DeferredEntries<Self::InputKey, Self::Input, Self::Error>,
+        S: NodeSink<Self::OutputKey, Self::Output, Self::Error>,
+    {
         // // Otherwise element is a map.
         // // If `active_context` has a `previous_context`, the active context is not
         // // propagated.
@@ -359,37 +711,63 @@ pub trait Transformer {
         // }
 
         let mut active_context = Cow::Borrowed(active_context);
-        let mut result = Vec::new();
+        let mut context_entry = None;
+        let parent_fingerprint = self.state_and_loader_mut().0.active_context_fingerprint;
+        self.begin_expanded_node();
 
         // Embedded contexts.
-        if let Some(context_value) = object.get_context()? {
-            // Encode them.
-            let cbor_key = self.term_key("@context", context_value.is_array())?;
+        let context_entry_value = object.get_context().map_err(|e| self.locate(e.into()))?;
+        if let Some(context_value) = context_entry_value {
+            let key = object
+                .entries()
+                .find(|(_, value)| std::ptr::eq(*value, context_value))
+                .map(|(key, _)| self.path_key(key))
+                .unwrap_or_default();
+            self.enter(PathSegment::Key(key, Some("@context".to_owned())));
 
-            let (cbor_value, new_active_context) = self
-                .process_global_context(&active_context, context_value, true)
-                .await?;
+            // Encode them.
+            let result = match self.term_key("@context", context_value.is_array()) {
+                Ok(cbor_key) => self
+                    .process_global_context(&active_context, context_value, true)
+                    .await
+                    .map(|result| (cbor_key, result)),
+                Err(e) => Err(e),
+            };
+            let (cbor_key, (cbor_value, new_active_context)) = self.leave(result)?;
 
             if let Cow::Owned(new_active_context) = new_active_context {
                 active_context = Cow::Owned(new_active_context)
             }
 
-            result.push((cbor_key, cbor_value));
+            context_entry = Some((cbor_key, cbor_value));
         }
 
-        // Find types.
+        // Find types. In lenient mode, invalid types select no type-scoped
+        // context, and are left as-is when transforming the entry.
+        let lenient = self.state_and_loader_mut().0.lenient;
         let mut types = Vec::new();
         for (key, value) in object.entries() {
-            if let Some((term, plural)) = self.key_term(key, value)? {
-                if is_alias(&active_context, term, Keyword::Type) {
-                    for ty in value.force_as_array(plural) {
-                        let ty_term = self.value_term(&active_context, ty)?;
-                        types.push(ty_term.into_owned());
-                    }
-                }
-            }
+            let result = self.key_term(key, value).and_then(|term| match term {
+                Some((term, plural)) if is_alias(&active_context, term, Keyword::Type) => value
+                    .force_as_array(plural)
+                    .iter()
+                    .filter_map(|ty| match self.value_term(&active_context, ty) {
+                        Ok(ty) => Some(Ok(ty.into_owned())),
+                        Err(_) if lenient => None,
+                        Err(e) => Some(Err(e)),
+                    })
+                    .collect(),
+                _ => Ok(Vec::new()),
+            });
+
+            let segment = PathSegment::Key(self.path_key(key), None);
+            self.enter(segment);
+            types.extend(self.leave(result)?);
         }
 
+        // Value objects of type `@json` hold a JSON literal.
+        let json_literal = types.iter().any(|ty| ty == "@json");
+
         // Apply type-scoped contexts.
         types.sort_unstable();
         for ty in types {
@@ -406,83 +784,311 @@ pub trait Transformer {
         // Sort entries.
         let mut sorted_entries = Vec::new();
         for (key, value) in object.entries() {
-            let (key_term, plural) = self.required_key_term(key, value)?;
-            let key_term = key_term.to_owned();
+            let segment = self.path_key(key);
+            let result = self
+                .required_key_term(key, value)
+                .map(|(key_term, plural)| (key_term.to_owned(), plural));
+            self.enter(PathSegment::Key(segment.clone(), None));
+            let (key_term, plural) = self.leave(result)?;
 
             if key_term == "@context" {
                 continue;
             }
 
             let def = active_context.get(key_term.as_str());
-            let id = self.term_key(&key_term, value.is_array())?;
-            sorted_entries.push((key_term, plural, def, id, value));
+            let defined = def.is_some()
+                || [
+                    Keyword::Id,
+                    Keyword::Type,
+                    Keyword::Language,
+                    Keyword::Value,
+                    Keyword::Direction,
+                    Keyword::Index,
+                    Keyword::Graph,
+                ]
+                .into_iter()
+                .any(|keyword| key_term == keyword.into_str());
+
+            // Deferred entries are read item by item when possible, and as a
+            // whole otherwise.
+            let mut value = Cow::Borrowed(value);
+            let mut itemized = deferred
+                .len(key)
+                .filter(|_| plural && is_itemized(&key_term, def));
+            if itemized.is_none() {
+                if let Some(result) = deferred.value(key) {
+                    self.enter(PathSegment::Key(segment.clone(), Some(key_term.clone())));
+                    value = Cow::Owned(self.leave(result)?);
+                }
+            }
+
+            // In lenient mode, type entries holding invalid types are kept
+            // uncompressed, as their values could be mistaken for term IDs.
+            let invalid_type = if lenient && is_alias_with_def(&key_term, def, Keyword::Type) {
+                value
+                    .force_as_array(plural)
+                    .iter()
+                    .find_map(|ty| self.value_term(&active_context, ty).err())
+            } else {
+                None
+            };
+
+            let (id, uncompressed) =
+                match self.uncompressed_key(key, defined && invalid_type.is_none()) {
+                    Some(id) => {
+                        let warning = match invalid_type {
+                            Some(e) => WarningKind::UnhandledValue(e.to_string()),
+                            None => WarningKind::UncompressedTerm(key_term.clone()),
+                        };
+                        (id, Some(warning))
+                    }
+                    None => {
+                        let id = self.term_key(&key_term, value.is_array());
+                        self.enter(PathSegment::Key(segment.clone(), Some(key_term.clone())));
+                        (self.leave(id)?, None)
+                    }
+                };
+
+            if uncompressed.is_some() && itemized.take().is_some() {
+                if let Some(result) = deferred.value(key) {
+                    self.enter(PathSegment::Key(segment.clone(), Some(key_term.clone())));
+                    value = Cow::Owned(self.leave(result)?);
+                }
+            }
+
+            let itemized = itemized.map(|len| (key, len));
+            sorted_entries.push((
+                key_term,
+                plural,
+                def,
+                id,
+                value,
+                itemized,
+                segment,
+                uncompressed,
+            ));
         }
         sorted_entries.sort_by(|a, b| a.0.cmp(&b.0));
 
+        let keys: Vec<_> = context_entry
+            .iter()
+            .map(|(key, _)| key)
+            .chain(sorted_entries.iter().map(|e| &e.3))
+            .collect();
+        sink.begin(&keys)?;
+
+        if let Some((cbor_key, cbor_value)) = context_entry {
+            sink.entry(cbor_key, cbor_value)?;
+        }
+
         // Process entries.
-        for (key_term, plural, def, cbor_key, value) in sorted_entries {
-            if is_alias_with_def(&key_term, def, Keyword::Id) {
-                let cbor_value = self.transform_id(value)?;
-                result.push((cbor_key, cbor_value));
+        for (key_term, plural, def, cbor_key, value, itemized, segment, uncompressed) in
+            sorted_entries
+        {
+            let value: &Self::Input = &value;
+            self.enter(PathSegment::Key(segment, Some(key_term.clone())));
+            let result = async {
+                if let Some(warning) = uncompressed {
+                    self.warn(warning);
+                    let cbor_value = self.transform_json(value)?;
+                    self.expand_entry(&active_context, &key_term, def, Some(&cbor_value))?;
+                    return sink.entry(cbor_key, cbor_value);
+                }
 
-                continue;
-            }
+                if is_alias_with_def(&key_term, def, Keyword::Id) {
+                    let cbor_value = self.transform_id(value)?;
+                    self.expand_entry(&active_context, &key_term, def, Some(&cbor_value))?;
+                    return sink.entry(cbor_key, cbor_value);
+                }
 
-            if is_alias_with_def(&key_term, def, Keyword::Type) {
-                let cbor_value = if plural {
-                    let values = value.as_array().ok_or(InvalidTypeKind)?;
-                    let mut cbor_values = Vec::with_capacity(values.len());
+                if is_alias_with_def(&key_term, def, Keyword::Type) {
+                    let cbor_value = if plural {
+                        let values = value.as_array().ok_or(InvalidTypeKind)?;
+                        let mut cbor_values = Vec::with_capacity(values.len());
 
-                    for value in values {
-                        cbor_values.push(self.transform_vocab(&active_context, value)?);
-                    }
+                        for (i, value) in values.iter().enumerate() {
+                            self.enter(PathSegment::Index(i));
+                            let result = self.transform_vocab(&active_context, value);
+                            cbor_values.push(self.leave(result)?);
+                        }
 
-                    Self::Output::new_array(cbor_values)
-                } else {
-                    self.transform_vocab(&active_context, value)?
-                };
+                        Self::Output::new_array(cbor_values)
+                    } else {
+                        self.transform_vocab(&active_context, value)?
+                    };
 
-                result.push((cbor_key, cbor_value));
+                    self.expand_entry(&active_context, &key_term, def, Some(&cbor_value))?;
+                    return sink.entry(cbor_key, cbor_value);
+                }
 
-                continue;
-            }
+                if is_alias_with_def(&key_term, def, Keyword::Language) {
+                    let cbor_value = self.transform_language(value)?;
+                    self.expand_entry(&active_context, &key_term, def, Some(&cbor_value))?;
+                    return sink.entry(cbor_key, cbor_value);
+                }
 
-            let def = def.ok_or_else(|| UndefinedTerm(key_term.clone()))?;
+                if json_literal && is_alias_with_def(&key_term, def, Keyword::Value) {
+                    let cbor_value = self.transform_json(value)?;
+                    self.expand_entry(&active_context, &key_term, def, Some(&cbor_value))?;
+                    return sink.entry(cbor_key, cbor_value);
+                }
 
-            // Apply property-scoped context.
-            let mut property_context = Cow::Borrowed(active_context.as_ref());
-            if let Some(context) = def.context() {
-                property_context =
-                    Cow::Owned(self.process_context(&active_context, context, true).await?);
-            }
+                // Other value object entries are kept as is.
+                if is_alias_with_def(&key_term, def, Keyword::Value)
+                    || is_alias_with_def(&key_term, def, Keyword::Direction)
+                    || is_alias_with_def(&key_term, def, Keyword::Index)
+                {
+                    let cbor_value = self.transform_object(&active_context, value).await?;
+                    self.expand_entry(&active_context, &key_term, def, Some(&cbor_value))?;
+                    return sink.entry(cbor_key, cbor_value);
+                }
 
-            let values = value.force_as_array(plural);
-            let mut cbor_values = Vec::with_capacity(values.len());
+                // Graph entries hold node objects, like an untyped property
+                // without scoped context.
+                let (value_type, scoped_context) =
+                    if is_alias_with_def(&key_term, def, Keyword::Graph) {
+                        (None, None)
+                    } else {
+                        let def = def.ok_or_else(|| UndefinedTerm(key_term.clone()))?;
+
+                        if def.container().contains(ContainerKind::Language) {
+                            let cbor_value =
+                                self.transform_language_map(&active_context, value).await?;
+                            self.expand_entry(
+                                &active_context,
+                                &key_term,
+                                Some(def),
+                                Some(&cbor_value),
+                            )?;
+                            return sink.entry(cbor_key, cbor_value);
+                        }
 
-            let value_type = def.typ();
+                        // JSON literals are transformed as a whole, without
+                        // term substitution.
+                        if def.typ() == Some(&json_ld::Type::Json) {
+                            let cbor_value = self.transform_json(value)?;
+                            self.expand_entry(
+                                &active_context,
+                                &key_term,
+                                Some(def),
+                                Some(&cbor_value),
+                            )?;
+                            return sink.entry(cbor_key, cbor_value);
+                        }
 
-            for value in values {
-                let cbor_value =
-                    match self.transform_typed_value(&active_context, value, value_type)? {
-                        Some(cbor_value) => cbor_value,
-                        None => self.transform_object(&property_context, value).await?,
+                        (def.typ(), def.context())
                     };
 
-                cbor_values.push(cbor_value)
-            }
+                // Apply property-scoped context.
+                let fingerprint = self.state_and_loader_mut().0.active_context_fingerprint;
+                let mut property_context = Cow::Borrowed(active_context.as_ref());
+                let mut property_fingerprint = fingerprint;
+                if let Some(context) = scoped_context {
+                    property_context =
+                        Cow::Owned(self.process_context(&active_context, context, true).await?);
+                    property_fingerprint = self.state_and_loader_mut().0.active_context_fingerprint;
+                }
 
-            let cbor_value = if plural {
-                Self::Output::new_array(cbor_values)
-            } else {
-                cbor_values.into_iter().next().unwrap()
-            };
+                let values = value.force_as_array(plural);
+                let len = itemized.map_or(values.len(), |(_, len)| len);
+                let mut single_value = None;
+
+                if plural {
+                    self.expand_entry(&property_context, &key_term, def, None)?;
+                    sink.begin_items(cbor_key.clone(), len)?;
+                }
+
+                let mut values = values.iter();
+                for i in 0..len {
+                    if plural {
+                        self.enter(PathSegment::Index(i));
+                    }
+
+                    let result = async {
+                        // Deferred entries always hold their items.
+                        let value = match itemized {
+                            Some((key, _)) => {
+                                Cow::Owned(deferred.item(key, i).ok_or(ExpectedObject)??)
+                            }
+                            None => Cow::Borrowed(values.next().ok_or(ExpectedObject)?),
+                        };
+
+                        match self.transform_typed_value(&active_context, &value, value_type)? {
+                            Some(cbor_value) => Ok(cbor_value),
+                            None => {
+                                self.state_and_loader_mut().0.active_context_fingerprint =
+                                    property_fingerprint;
+                                self.transform_object(&property_context, &value).await
+                            }
+                        }
+                    }
+                    .await
+                    .and_then(|cbor_value| {
+                        self.expand_entry(&property_context, &key_term, def, Some(&cbor_value))?;
+                        Ok(cbor_value)
+                    });
+
+                    if plural {
+                        sink.item(self.leave(result)?)?
+                    } else {
+                        single_value = Some(result?)
+                    }
+                }
 
-            result.push((cbor_key, cbor_value));
+                self.state_and_loader_mut().0.active_context_fingerprint = fingerprint;
+
+                match single_value {
+                    Some(cbor_value) => sink.entry(cbor_key, cbor_value),
+                    None => sink.end_items(),
+                }
+            }
+            .await;
+            self.leave(result)?;
         }
 
-        result.sort_unstable_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+        self.state_and_loader_mut().0.active_context_fingerprint = parent_fingerprint;
+        self.end_expanded_node()?;
+        sink.end()
+    }
+
+    #[allow(async_fn_in_trait)]
+    async fn transform_language_map(
+        &mut self,
+        active_context: &json_ld::Context,
+        value: &Self::Input,
+    ) -> Result<Self::Output, Self::Error> {
+        let object = value.as_object().ok_or(ExpectedObject)?;
+        let mut result = Vec::new();
+
+        for (key, value) in object.entries() {
+            let segment = PathSegment::Key(self.path_key(key), None);
+            self.enter(segment);
+            let entry = async {
+                let cbor_key = self.language_key(key)?;
+
+                let cbor_value = match value.as_array() {
+                    Some(values) => {
+                        let mut cbor_values = Vec::with_capacity(values.len());
+
+                        for (i, value) in values.iter().enumerate() {
+                            self.enter(PathSegment::Index(i));
+                            let cbor_value = self.transform_object(active_context, value).await;
+                            cbor_values.push(self.leave(cbor_value)?);
+                        }
+
+                        Self::Output::new_array(cbor_values)
+                    }
+                    None => self.transform_object(active_context, value).await?,
+                };
+
+                Ok((cbor_key, cbor_value))
+            }
+            .await;
 
-        Ok(Self::OutputObject::new(result))
+            result.push(self.leave(entry)?);
+        }
+
+        Ok(Self::Output::new_object(Self::OutputObject::new(result)))
     }
 
     fn transform_typed_value(
@@ -517,27 +1123,148 @@ fn is_alias_with_def(key: &str, def: Option<TermDefinitionRef>, keyword: Keyword
         })
 }
 
+/// Checks if the entry with the given key is transformed item by item,
+/// along with its definition, if plural.
+fn is_itemized(key: &str, def: Option<TermDefinitionRef>) -> bool {
+    let keywords = [
+        Keyword::Id,
+        Keyword::Type,
+        Keyword::Language,
+        Keyword::Value,
+        Keyword::Direction,
+        Keyword::Index,
+    ];
+
+    if keywords
+        .into_iter()
+        .any(|keyword| is_alias_with_def(key, def, keyword))
+    {
+        return false;
+    }
+
+    is_alias_with_def(key, def, Keyword::Graph)
+        || def.is_some_and(|d| {
+            !d.container().contains(ContainerKind::Language)
+                && d.typ() != Some(&json_ld::Type::Json)
+        })
+}
+
 pub struct TransformerState {
     // pub context_map: IdMap,
     pub allocator: IdAllocator,
-    pub codecs: Codecs,
-    pub tables: Cow<'static, Tables>,
+    pub codecs: Arc<Codecs>,
+    pub tables: Arc<Tables>,
+
+    /// Processed context cache.
+    pub context_cache: Option<Arc<ContextCache>>,
+
+    /// Fingerprint of the active context, used as processed context cache
+    /// key.
+    pub active_context_fingerprint: u64,
+
+    /// String literals codec, used when the tables have a string dictionary.
+    pub strings: StringCodec,
+
+    /// Context IDs learned from the previous documents of a CBOR sequence,
+    /// when documents share their state.
+    pub sequence_contexts: Option<ContextTable>,
+
+    /// Path from the document root to the value being transformed, used to
+    /// locate errors.
+    pub path: Vec<PathSegment>,
+
+    /// Lenient mode: undefined terms are kept uncompressed and values that
+    /// cannot be transformed are left as-is.
+    ///
+    /// See the [`lenient`](crate::lenient) module.
+    pub lenient: bool,
+
+    /// Lenient mode warnings collector. Warnings are logged if none is given.
+    pub warnings: Option<Arc<Warnings>>,
+
+    /// Values recorded while encoding, to compute compression statistics or
+    /// generate compression tables.
+    pub(crate) recorder: Option<Recorder>,
 }
 
 impl TransformerState {
     pub fn new(
         // context_map: IdMap,
-        codecs: Codecs,
-        tables: Cow<'static, Tables>,
+        codecs: Arc<Codecs>,
+        tables: Arc<Tables>,
     ) -> Self {
         Self {
             // context_map,
             allocator: IdAllocator::new(Some(&KEYWORDS_MAP), FIRST_CUSTOM_TERM_ID),
             codecs,
             tables,
+            context_cache: None,
+            active_context_fingerprint: EMPTY_CONTEXT_FINGERPRINT,
+            strings: StringCodec::new(),
+            sequence_contexts: None,
+            path: Vec::new(),
+            lenient: false,
+            warnings: None,
+            recorder: None,
+        }
+    }
+
+    /// Shares term allocation and learned context IDs between the next
+    /// documents, as the documents of a CBOR sequence.
+    pub fn share_sequence_state(&mut self) {
+        self.sequence_contexts = Some(ContextTable::default());
+    }
+
+    /// Resets term allocation, so that the next document does not depend on
+    /// the previous ones.
+    pub fn reset_allocator(&mut self) {
+        self.allocator = IdAllocator::new(Some(&KEYWORDS_MAP), FIRST_CUSTOM_TERM_ID);
+    }
+
+    /// Returns the ID of the given context IRI reference, if any.
+    pub fn context_id(&self, iri_ref: &IriRef) -> Option<u64> {
+        self.tables.context.get_id(iri_ref).or_else(|| {
+            self.sequence_contexts
+                .as_ref()
+                .and_then(|learned| learned.get_id(iri_ref))
+        })
+    }
+
+    /// Returns the context IRI reference with the given ID, if any.
+    pub fn context_iri_ref(&self, id: u64) -> Option<&IriRef> {
+        self.tables.context.get_iri_ref(id).or_else(|| {
+            self.sequence_contexts
+                .as_ref()
+                .and_then(|learned| learned.get_iri_ref(id))
+        })
+    }
+
+    /// Assigns an ID to the given context IRI reference if it has none and
+    /// documents share their state.
+    pub fn learn_context(&mut self, iri_ref: &IriRef) {
+        if self.context_id(iri_ref).is_none() {
+            if let Some(learned) = &mut self.sequence_contexts {
+                let id = self.tables.context.next_id().max(learned.next_id());
+                learned.insert(iri_ref.to_owned(), id);
+            }
         }
     }
 
+    /// Reports a lenient mode fallback.
+    pub fn warn(&self, warning: Warning) {
+        match &self.warnings {
+            Some(warnings) => warnings.push(warning),
+            None => log::warn!("{warning}"),
+        }
+    }
+
+    /// Resets the per-document state, before transforming a new document.
+    pub fn reset(&mut self) {
+        self.strings.reset();
+        self.active_context_fingerprint = EMPTY_CONTEXT_FINGERPRINT;
+        self.path.clear();
+    }
+
     pub fn encode_vocab_term(
         &self,
         active_context: &json_ld::Context,
@@ -555,7 +1282,7 @@ impl TransformerState {
                             let prefix_value = prefix_def
                                 .value()
                                 .ok_or(EncodeError::InvalidTermDefinition)?;
-                            expanded_value = Cow::Owned(format!("{prefix_value}:{suffix}"))
+                            expanded_value = Cow::Owned(format!("{prefix_value}{suffix}"))
                         }
                     }
                 }
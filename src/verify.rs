@@ -0,0 +1,519 @@
+//! Round-trip verification.
+//!
+//! Comparing an input document with its decoded CBOR-LD encoding as JSON is
+//! too strict (entry order, compact IRIs) and too loose (it ignores what the
+//! terms mean). [`verify_round_trip`] instead expands both documents to RDF
+//! and compares the resulting datasets, reporting differences per quad.
+//!
+//! Blank node labels are assigned to both datasets canonically, so that
+//! isomorphic datasets compare equal: blank nodes are first told apart by
+//! iteratively hashing their neighborhood, then the remaining ties are broken
+//! by trying every choice and keeping the smallest resulting dataset.
+use std::{
+    collections::{hash_map::DefaultHasher, BTreeMap, BTreeSet, HashMap},
+    fmt,
+    hash::{Hash, Hasher},
+};
+
+use json_ld::{
+    rdf_types::{self, generator, RdfDisplay},
+    JsonLdProcessor, RemoteDocument,
+};
+
+use crate::{
+    cbor_into_bytes, decode_with, encode_with, rdf::RdfQuad, DecodeError, DecodeOptions,
+    EncodeError, EncodeOptions, JsonValue, RdfDataset,
+};
+
+#[derive(Debug, thiserror::Error)]
+pub enum VerifyError {
+    #[error("encoding failed: {0}")]
+    Encode(#[from] EncodeError),
+
+    #[error("decoding failed: {0}")]
+    Decode(#[from] DecodeError),
+
+    #[error("RDF conversion failed: {0}")]
+    ToRdf(#[from] json_ld::ToRdfError),
+}
+
+/// Result of a round-trip verification.
+#[derive(Debug)]
+pub struct RoundTripReport {
+    /// Size of the CBOR-LD encoding, in bytes.
+    pub encoded_len: usize,
+
+    /// Decoded document.
+    pub decoded: JsonValue,
+
+    /// Number of quads of the input document.
+    pub quad_count: usize,
+
+    /// Quads of the input document missing from the decoded document, in
+    /// N-Quads syntax.
+    pub missing: Vec<String>,
+
+    /// Quads of the decoded document absent from the input document, in
+    /// N-Quads syntax.
+    pub unexpected: Vec<String>,
+}
+
+impl RoundTripReport {
+    /// Checks that the decoded document has the same RDF meaning as the
+    /// input document.
+    pub fn is_equivalent(&self) -> bool {
+        self.missing.is_empty() && self.unexpected.is_empty()
+    }
+}
+
+impl fmt::Display for RoundTripReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for quad in &self.missing {
+            writeln!(f, "- {quad}")?
+        }
+
+        for quad in &self.unexpected {
+            writeln!(f, "+ {quad}")?
+        }
+
+        Ok(())
+    }
+}
+
+/// Encodes and decodes a JSON-LD document using the given JSON-LD context
+/// loader and the default options, and compares the RDF datasets of the
+/// input and decoded documents.
+pub async fn verify_round_trip(
+    json_ld_document: &JsonValue,
+    loader: impl json_ld::Loader,
+) -> Result<RoundTripReport, VerifyError> {
+    verify_round_trip_with(
+        json_ld_document,
+        loader,
+        Default::default(),
+        Default::default(),
+    )
+    .await
+}
+
+/// Encodes and decodes a JSON-LD document using the given JSON-LD context
+/// loader and options, and compares the RDF datasets of the input and
+/// decoded documents.
+pub async fn verify_round_trip_with(
+    json_ld_document: &JsonValue,
+    loader: impl json_ld::Loader,
+    encode_options: EncodeOptions,
+    decode_options: DecodeOptions,
+) -> Result<RoundTripReport, VerifyError> {
+    let encoded = encode_with(json_ld_document, &loader, encode_options).await?;
+    let decoded = decode_with(&encoded, &loader, decode_options).await?;
+
+    let expected = to_rdf(json_ld_document, &loader).await?;
+    let found = to_rdf(&decoded, &loader).await?;
+
+    Ok(RoundTripReport {
+        encoded_len: cbor_into_bytes(encoded).len(),
+        decoded,
+        quad_count: expected.len(),
+        missing: expected.difference(&found).cloned().collect(),
+        unexpected: found.difference(&expected).cloned().collect(),
+    })
+}
+
+/// Node of a quad, with blank nodes replaced by indexes.
+enum Node {
+    Fixed(String),
+    Blank(usize),
+}
+
+/// Blank node indexes, by label.
+#[derive(Default)]
+struct BlankNodes(HashMap<String, usize>);
+
+impl BlankNodes {
+    fn id(&mut self, id: &rdf_types::Id) -> Node {
+        match id {
+            rdf_types::Id::Blank(b) => {
+                let len = self.0.len();
+                Node::Blank(*self.0.entry(b.to_string()).or_insert(len))
+            }
+            rdf_types::Id::Iri(_) => Node::Fixed(id.rdf_display().to_string()),
+        }
+    }
+}
+
+/// Converts a JSON-LD document into an RDF dataset, as a set of canonically
+/// labeled N-Quads lines.
+async fn to_rdf(
+    json_ld_document: &JsonValue,
+    loader: &impl json_ld::Loader,
+) -> Result<BTreeSet<String>, json_ld::ToRdfError> {
+    let document = RemoteDocument::new(None, None, json_ld_document.clone());
+    let mut rdf = document.to_rdf(generator::Blank::new(), loader).await?;
+    Ok(canonical_quads(rdf.cloned_quads()))
+}
+
+/// Returns the N-Quads lines of an RDF dataset, with canonical blank node
+/// labels: isomorphic datasets give the same lines.
+pub fn canonical_nquads(dataset: &RdfDataset) -> BTreeSet<String> {
+    canonical_quads(dataset.0.iter().cloned())
+}
+
+fn canonical_quads(quads: impl Iterator<Item = RdfQuad>) -> BTreeSet<String> {
+    let mut blank_nodes = BlankNodes::default();
+    let quads: Vec<[Node; 4]> = quads
+        .map(|rdf_types::Quad(s, p, o, g)| {
+            [
+                blank_nodes.id(&s),
+                blank_nodes.id(&p),
+                match &o {
+                    rdf_types::Term::Id(id) => blank_nodes.id(id),
+                    rdf_types::Term::Literal(l) => Node::Fixed(l.rdf_display().to_string()),
+                },
+                match &g {
+                    Some(g) => blank_nodes.id(g),
+                    None => Node::Fixed(String::new()),
+                },
+            ]
+        })
+        .collect();
+
+    let labels = canonical_labels(&quads, blank_nodes.0.len());
+    render(&quads, &labels)
+}
+
+/// Renders quads as N-Quads lines, using the given blank node labels.
+fn render(quads: &[[Node; 4]], labels: &[usize]) -> BTreeSet<String> {
+    quads
+        .iter()
+        .map(|quad| {
+            let mut line = String::new();
+            for node in quad {
+                match node {
+                    Node::Fixed(s) if s.is_empty() => continue,
+                    Node::Fixed(s) => line.push_str(s),
+                    Node::Blank(b) => line.push_str(&format!("_:c14n{}", labels[*b])),
+                }
+
+                line.push(' ')
+            }
+
+            line.push('.');
+            line
+        })
+        .collect()
+}
+
+/// Assigns canonical labels to blank nodes.
+///
+/// Blank nodes are colored from the shape of their neighborhood. Blank nodes
+/// with the same color are individualized in turn, backtracking over every
+/// choice, and the labeling rendering the smallest dataset is kept. Choices
+/// equivalent to an explored one under an automorphism found on the way are
+/// skipped.
+fn canonical_labels(quads: &[[Node; 4]], count: usize) -> Vec<usize> {
+    let mut search = Search {
+        quads,
+        first: None,
+        best: None,
+        automorphisms: Vec::new(),
+    };
+
+    search.run(vec![0; count], &mut Vec::new());
+    search.best.map(|(_, labels)| labels).unwrap_or_default()
+}
+
+/// Canonical labeling search state.
+struct Search<'a> {
+    quads: &'a [[Node; 4]],
+
+    /// First labeling found, with its individualized blank nodes and its
+    /// colors in label order.
+    first: Option<(BTreeSet<String>, Vec<usize>, Vec<u64>)>,
+
+    /// Smallest rendering found so far, with its labels.
+    best: Option<(BTreeSet<String>, Vec<usize>)>,
+
+    /// Automorphisms found so far, as blank node permutations.
+    automorphisms: Vec<Vec<usize>>,
+}
+
+impl Search<'_> {
+    /// Searches the labelings below the given individualized blank nodes.
+    ///
+    /// Returns the depth to backtrack to when the rest of the search at the
+    /// current depth is equivalent to what was already explored.
+    fn run(&mut self, colors: Vec<u64>, individualized: &mut Vec<usize>) -> Option<usize> {
+        let colors = refine(self.quads, colors);
+
+        let Some(cell) = first_tie(&colors) else {
+            return self.leaf(&colors, individualized);
+        };
+
+        let depth = individualized.len();
+        let mut explored: Vec<usize> = Vec::new();
+        for b in cell {
+            if !explored.is_empty() {
+                let orbits = self.orbits(individualized, colors.len());
+                if explored.iter().any(|&e| orbits[e] == orbits[b]) {
+                    continue;
+                }
+            }
+
+            let mut new_colors = colors.clone();
+            let mut hasher = DefaultHasher::new();
+            (colors[b], "individualized").hash(&mut hasher);
+            new_colors[b] = hasher.finish();
+
+            individualized.push(b);
+            let backtrack = self.run(new_colors, individualized);
+            individualized.pop();
+            explored.push(b);
+
+            if let Some(target) = backtrack.filter(|&target| target < depth) {
+                return Some(target);
+            }
+        }
+
+        None
+    }
+
+    /// Labels blank nodes with distinct colors by color order, and keeps the
+    /// labeling if it is the smallest so far.
+    fn leaf(&mut self, colors: &[u64], individualized: &[usize]) -> Option<usize> {
+        let mut order: Vec<usize> = (0..colors.len()).collect();
+        order.sort_by_key(|&b| colors[b]);
+        let sorted_colors: Vec<u64> = order.iter().map(|&b| colors[b]).collect();
+
+        let mut labels = vec![0; colors.len()];
+        for (label, &b) in order.iter().enumerate() {
+            labels[b] = label
+        }
+
+        let rendered = render(self.quads, &labels);
+
+        let Some((first, first_individualized, first_colors)) = &self.first else {
+            self.first = Some((rendered.clone(), individualized.to_vec(), sorted_colors));
+            self.best = Some((rendered, labels));
+            return None;
+        };
+
+        // Same dataset and same coloring history as the first labeling: the
+        // whole subtree since the paths diverged is equivalent to the one
+        // explored first.
+        let backtrack = (*first == rendered && *first_colors == sorted_colors).then(|| {
+            first_individualized
+                .iter()
+                .zip(individualized)
+                .take_while(|(a, b)| a == b)
+                .count()
+        });
+
+        match &self.best {
+            Some((best, best_labels)) if *best == rendered => {
+                // Both labelings give the same dataset: mapping each blank
+                // node to the one with the same best label is an
+                // automorphism.
+                let mut by_label = vec![0; colors.len()];
+                for (b, &label) in best_labels.iter().enumerate() {
+                    by_label[label] = b
+                }
+
+                self.automorphisms
+                    .push(labels.iter().map(|&label| by_label[label]).collect())
+            }
+            Some((best, _)) if *best < rendered => (),
+            _ => self.best = Some((rendered, labels)),
+        }
+
+        backtrack
+    }
+
+    /// Returns the orbit representative of each blank node under the
+    /// automorphisms found so far fixing the individualized blank nodes.
+    fn orbits(&self, individualized: &[usize], count: usize) -> Vec<usize> {
+        fn find(parents: &mut [usize], mut b: usize) -> usize {
+            while parents[b] != b {
+                parents[b] = parents[parents[b]];
+                b = parents[b]
+            }
+
+            b
+        }
+
+        let mut parents: Vec<usize> = (0..count).collect();
+        for automorphism in &self.automorphisms {
+            if individualized.iter().all(|&b| automorphism[b] == b) {
+                for (b, &c) in automorphism.iter().enumerate() {
+                    let (b, c) = (find(&mut parents, b), find(&mut parents, c));
+                    parents[b.max(c)] = b.min(c)
+                }
+            }
+        }
+
+        (0..count).map(|b| find(&mut parents, b)).collect()
+    }
+}
+
+/// Returns the blank nodes of the smallest color shared by several blank
+/// nodes, if any.
+fn first_tie(colors: &[u64]) -> Option<Vec<usize>> {
+    let mut cells: BTreeMap<u64, Vec<usize>> = BTreeMap::new();
+    for (b, &color) in colors.iter().enumerate() {
+        cells.entry(color).or_default().push(b)
+    }
+
+    cells.into_values().find(|cell| cell.len() > 1)
+}
+
+/// Refines blank node colors from the shape of their neighborhood, until
+/// the number of colors is stable.
+fn refine(quads: &[[Node; 4]], mut colors: Vec<u64>) -> Vec<u64> {
+    let count = colors.len();
+    let mut classes = colors.iter().collect::<BTreeSet<_>>().len();
+
+    for _ in 0..=count {
+        let mut signatures = vec![Vec::new(); count];
+        for quad in quads {
+            for (position, node) in quad.iter().enumerate() {
+                if let Node::Blank(b) = node {
+                    let mut signature = position.to_string();
+                    for other in quad {
+                        signature.push(' ');
+                        match other {
+                            Node::Fixed(s) => signature.push_str(s),
+                            Node::Blank(c) if c == b => signature.push_str("_:self"),
+                            Node::Blank(c) => signature.push_str(&format!("_:{}", colors[*c])),
+                        }
+                    }
+
+                    signatures[*b].push(signature)
+                }
+            }
+        }
+
+        let new_colors: Vec<u64> = signatures
+            .into_iter()
+            .zip(&colors)
+            .map(|(mut signature, color)| {
+                signature.sort_unstable();
+                let mut hasher = DefaultHasher::new();
+                color.hash(&mut hasher);
+                signature.hash(&mut hasher);
+                hasher.finish()
+            })
+            .collect();
+
+        let new_classes = new_colors.iter().collect::<BTreeSet<_>>().len();
+        colors = new_colors;
+        if new_classes == classes {
+            break;
+        }
+
+        classes = new_classes
+    }
+
+    colors
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+
+    use super::{canonical_labels, render, Node};
+
+    fn fixed(s: &str) -> Node {
+        Node::Fixed(s.to_owned())
+    }
+
+    /// Canonical rendering of blank node edges `a <p> b`.
+    fn canonical(edges: &[(usize, usize)], count: usize) -> Vec<String> {
+        let quads: Vec<_> = edges
+            .iter()
+            .map(|&(a, b)| [Node::Blank(a), fixed("<p>"), Node::Blank(b), fixed("")])
+            .collect();
+        render(&quads, &canonical_labels(&quads, count))
+            .into_iter()
+            .collect()
+    }
+
+    #[test]
+    fn isomorphic_labels() {
+        let quads = |a: usize, b: usize| {
+            vec![
+                [Node::Blank(a), fixed("<p>"), fixed("\"x\""), fixed("")],
+                [Node::Blank(b), fixed("<p>"), Node::Blank(a), fixed("")],
+            ]
+        };
+
+        let labels = canonical_labels(&quads(0, 1), 2);
+        let swapped = canonical_labels(&quads(1, 0), 2);
+        assert_eq!(labels[0], swapped[1]);
+        assert_eq!(labels[1], swapped[0])
+    }
+
+    #[test]
+    fn symmetric_graphs() {
+        // Color refinement alone cannot tell the nodes of a cycle apart.
+        let cycle = |order: [usize; 6]| {
+            let edges: Vec<_> = (0..6).map(|i| (order[i], order[(i + 1) % 6])).collect();
+            canonical(&edges, 6)
+        };
+        assert_eq!(cycle([0, 1, 2, 3, 4, 5]), cycle([0, 2, 4, 1, 3, 5]));
+        assert_eq!(cycle([0, 1, 2, 3, 4, 5]), cycle([5, 3, 1, 4, 0, 2]));
+
+        // Nor a 6-cycle from two 3-cycles, which are not isomorphic to it.
+        let triangles = canonical(&[(0, 1), (1, 2), (2, 0), (3, 4), (4, 5), (5, 3)], 6);
+        assert_ne!(cycle([0, 1, 2, 3, 4, 5]), triangles)
+    }
+
+    #[test]
+    fn many_indistinguishable_blank_nodes() {
+        let quads = |count: usize, shift: usize| -> Vec<_> {
+            (0..count)
+                .map(|b| {
+                    [
+                        fixed("<s>"),
+                        fixed("<p>"),
+                        Node::Blank((b + shift) % count),
+                        fixed(""),
+                    ]
+                })
+                .chain(
+                    (0..count).map(|b| [Node::Blank(b), fixed("<q>"), fixed("\"x\""), fixed("")]),
+                )
+                .collect()
+        };
+
+        let a = quads(40, 0);
+        let b = quads(40, 7);
+        assert_eq!(
+            render(&a, &canonical_labels(&a, 40)),
+            render(&b, &canonical_labels(&b, 40))
+        )
+    }
+
+    proptest! {
+        #[test]
+        fn relabeling_invariance(
+            edges in prop::collection::vec((0..7usize, 0..2usize, 0..7usize), 0..14),
+            permutation in Just((0..7usize).collect::<Vec<_>>()).prop_shuffle()
+        ) {
+            let quads = |map: &dyn Fn(usize) -> usize| -> Vec<_> {
+                edges
+                    .iter()
+                    .map(|&(a, p, b)| {
+                        [Node::Blank(map(a)), fixed(["<p>", "<q>"][p]), Node::Blank(map(b)), fixed("")]
+                    })
+                    .collect()
+            };
+
+            let a = quads(&|b| b);
+            let b = quads(&|b| permutation[b]);
+            prop_assert_eq!(
+                render(&a, &canonical_labels(&a, 7)),
+                render(&b, &canonical_labels(&b, 7))
+            );
+        }
+    }
+}
@@ -1,9 +1,13 @@
-use std::str::FromStr;
+use std::{
+    str::FromStr,
+    sync::atomic::{AtomicUsize, Ordering},
+};
 
 use cbor_ld::{
-    decode_from_bytes, encode_to_bytes, encode_to_bytes_with, CompressionMode, EncodeOptions,
-    JsonValue,
+    decode_from_bytes, decode_from_bytes_with, encode_to_bytes, encode_to_bytes_with,
+    CompressionMode, DecodeOptions, EncodeOptions, JsonValue,
 };
+use iref::{Iri, IriBuf};
 use json_ld::{FsLoader, Print};
 use json_syntax::BorrowUnordered;
 use static_iref::iri;
@@ -47,10 +51,48 @@ pub fn create_context_loader() -> FsLoader {
         iri!("https://w3id.org/utopia").to_owned(),
         "tests/contexts/utopia",
     );
+    loader.mount(
+        iri!("https://example.org/temporal").to_owned(),
+        "tests/contexts/temporal",
+    );
+    loader.mount(
+        iri!("https://example.org/json-literal").to_owned(),
+        "tests/contexts/json-literal",
+    );
+    loader.mount(
+        iri!("https://example.org/multilingual").to_owned(),
+        "tests/contexts/multilingual",
+    );
+    loader.mount(
+        iri!("https://example.org/curie").to_owned(),
+        "tests/contexts/curie",
+    );
 
     loader
 }
 
+/// Context loader counting the number of loaded contexts.
+pub struct CountingLoader<L> {
+    pub loader: L,
+    pub count: AtomicUsize,
+}
+
+impl<L> CountingLoader<L> {
+    pub fn new(loader: L) -> Self {
+        Self {
+            loader,
+            count: AtomicUsize::new(0),
+        }
+    }
+}
+
+impl<L: json_ld::Loader> json_ld::Loader for CountingLoader<L> {
+    async fn load(&self, url: &Iri) -> json_ld::LoadingResult<IriBuf> {
+        self.count.fetch_add(1, Ordering::Relaxed);
+        self.loader.load(url).await
+    }
+}
+
 pub async fn compression_test(input: &str, expected_hex: &str) {
     let json = cbor_ld::JsonValue::from_str(input).unwrap();
     let expected_bytes = hex::decode(expected_hex).unwrap();
@@ -71,12 +113,20 @@ pub async fn compression_test_with(
     expected_hex: &str,
     compression_mode: CompressionMode,
 ) {
-    let json = cbor_ld::JsonValue::from_str(input).unwrap();
-    let expected_bytes = hex::decode(expected_hex).unwrap();
     let options = EncodeOptions {
         compression_mode,
         ..Default::default()
     };
+    compression_test_with_options(input, expected_hex, options).await
+}
+
+pub async fn compression_test_with_options(
+    input: &str,
+    expected_hex: &str,
+    options: EncodeOptions,
+) {
+    let json = cbor_ld::JsonValue::from_str(input).unwrap();
+    let expected_bytes = hex::decode(expected_hex).unwrap();
     let bytes = encode_to_bytes_with(&json, create_context_loader(), options)
         .await
         .unwrap();
@@ -90,9 +140,17 @@ pub async fn compression_test_with(
 }
 
 pub async fn decompression_test(input_hex: &str, expected: &str) {
+    decompression_test_with_options(input_hex, expected, DecodeOptions::default()).await
+}
+
+pub async fn decompression_test_with_options(
+    input_hex: &str,
+    expected: &str,
+    options: DecodeOptions,
+) {
     let bytes = hex::decode(input_hex).unwrap();
 
-    let json = decode_from_bytes(&bytes, create_context_loader())
+    let json = decode_from_bytes_with(&bytes, create_context_loader(), options)
         .await
         .unwrap();
 
@@ -104,6 +162,36 @@ pub async fn decompression_test(input_hex: &str, expected: &str) {
     assert_eq!(json.as_unordered(), expected_json.as_unordered())
 }
 
+pub async fn round_trip_test(input: &str) {
+    let json = cbor_ld::JsonValue::from_str(input).unwrap();
+    let bytes = encode_to_bytes(&json, create_context_loader())
+        .await
+        .unwrap();
+    let decoded = decode_from_bytes(&bytes, create_context_loader())
+        .await
+        .unwrap();
+
+    eprintln!("output   = {}", decoded.pretty_print());
+    eprintln!("expected = {}", json.pretty_print());
+
+    assert_eq!(decoded, json)
+}
+
+/// Checks that the input round-trips with the given options, and returns its
+/// encoded size.
+pub async fn round_trip_size_test(input: &str, options: EncodeOptions) -> usize {
+    let json = cbor_ld::JsonValue::from_str(input).unwrap();
+    let bytes = encode_to_bytes_with(&json, create_context_loader(), options)
+        .await
+        .unwrap();
+    let decoded = decode_from_bytes(&bytes, create_context_loader())
+        .await
+        .unwrap();
+
+    assert_eq!(decoded.as_unordered(), json.as_unordered());
+    bytes.len()
+}
+
 pub fn diff(value: &[u8], expected: &[u8]) {
     use yansi::Paint;
 
@@ -0,0 +1,135 @@
+//! Peak memory usage of the streaming API.
+//!
+//! Streaming builds neither the output tree nor the input tree: the input is
+//! only held as bytes, the items of the collection being parsed one at a
+//! time. Streaming must then use less memory than parsing the input alone.
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    io,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use json_ld::FsLoader;
+use static_iref::iri;
+
+struct PeakAlloc {
+    current: AtomicUsize,
+    peak: AtomicUsize,
+}
+
+unsafe impl GlobalAlloc for PeakAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc(layout);
+        if !ptr.is_null() {
+            let current = self.current.fetch_add(layout.size(), Ordering::Relaxed) + layout.size();
+            self.peak.fetch_max(current, Ordering::Relaxed);
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+        self.current.fetch_sub(layout.size(), Ordering::Relaxed);
+    }
+}
+
+#[global_allocator]
+static ALLOC: PeakAlloc = PeakAlloc {
+    current: AtomicUsize::new(0),
+    peak: AtomicUsize::new(0),
+};
+
+/// Runs `f`, returning its result and the peak memory it allocated on top of
+/// what was already allocated.
+async fn measure<T>(f: impl std::future::Future<Output = T>) -> (T, usize) {
+    let base = ALLOC.current.load(Ordering::Relaxed);
+    ALLOC.peak.store(base, Ordering::Relaxed);
+    let t = f.await;
+    (t, ALLOC.peak.load(Ordering::Relaxed) - base)
+}
+
+fn create_context_loader() -> FsLoader {
+    let mut loader = FsLoader::new();
+    loader.mount(
+        iri!("https://www.w3.org/ns/activitystreams").to_owned(),
+        "tests/contexts/activitystreams",
+    );
+    loader
+}
+
+/// ActivityStreams collection of `len` notes.
+fn collection(len: usize) -> String {
+    let items: Vec<_> = (0..len)
+        .map(|i| {
+            format!(
+                r#"{{
+                    "id": "https://example.org/notes/{i}",
+                    "type": "Note",
+                    "attributedTo": "https://example.org/users/{}",
+                    "content": "Note number {i}, with some more text to make it a bit longer than a few words.",
+                    "published": "2024-01-01T00:00:00Z"
+                }}"#,
+                i % 7
+            )
+        })
+        .collect();
+
+    format!(
+        r#"{{
+            "@context": "https://www.w3.org/ns/activitystreams",
+            "id": "https://example.org/outbox",
+            "type": "OrderedCollection",
+            "totalItems": {len},
+            "orderedItems": [{}]
+        }}"#,
+        items.join(",")
+    )
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn streaming_peak_memory() {
+    let input = collection(2000);
+
+    let (bytes, tree_encode_peak) = measure(async {
+        let json: cbor_ld::JsonValue = input.parse().unwrap();
+        cbor_ld::encode_to_bytes(&json, create_context_loader())
+            .await
+            .unwrap()
+    })
+    .await;
+
+    let ((), stream_encode_peak) = measure(async {
+        cbor_ld::encode_stream(input.as_bytes(), io::sink(), create_context_loader())
+            .await
+            .unwrap()
+    })
+    .await;
+
+    let (_, tree_decode_peak) = measure(async {
+        cbor_ld::decode_from_bytes(&bytes, create_context_loader())
+            .await
+            .unwrap()
+            .to_string()
+    })
+    .await;
+
+    let ((), stream_decode_peak) = measure(async {
+        cbor_ld::decode_stream(bytes.as_slice(), io::sink(), create_context_loader())
+            .await
+            .unwrap()
+    })
+    .await;
+
+    let (_, json_input_peak) =
+        measure(async { input.parse::<cbor_ld::JsonValue>().unwrap() }).await;
+
+    let (_, cbor_input_peak) = measure(async {
+        ciborium::from_reader::<cbor_ld::CborValue, _>(bytes.as_slice()).unwrap()
+    })
+    .await;
+
+    assert!(stream_encode_peak < tree_encode_peak);
+    assert!(stream_decode_peak < tree_decode_peak);
+    assert!(stream_encode_peak < json_input_peak);
+    assert!(stream_decode_peak < cbor_input_peak)
+}
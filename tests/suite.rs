@@ -1,6 +1,23 @@
 mod common;
-use cbor_ld::{tables::RegistryEntry, CompressionMode};
+use cbor_ld::{
+    cache::ContextCache,
+    codecs::{Codecs, LanguageCodec, TypeCodecs},
+    loader::ContextDigest,
+    tables::RegistryEntry,
+    tables::{Registry, StringDictionary, Tables},
+    CompressionMode, DecodeError, DecodeOptions, EncodeError, EncodeOptions,
+};
 pub use common::*;
+use iref::IriBuf;
+use json_ld::RemoteDocument;
+use json_syntax::BorrowUnordered;
+use static_iref::iri;
+use std::{
+    borrow::Cow,
+    collections::HashMap,
+    io,
+    sync::{atomic::Ordering, Arc},
+};
 
 #[tokio::test]
 async fn encode_note() {
@@ -56,6 +73,39 @@ async fn decode_truage() {
     .await
 }
 
+fn temporal_codecs() -> Codecs {
+    Codecs {
+        type_: TypeCodecs::temporal(),
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn encode_truage_temporal() {
+    compression_test_with_options(
+        include_str!("samples/truage-temporal.jsonld"),
+        include_str!("samples/truage-temporal.cbor.hex"),
+        EncodeOptions {
+            codecs: temporal_codecs(),
+            ..Default::default()
+        },
+    )
+    .await
+}
+
+#[tokio::test]
+async fn decode_truage_temporal() {
+    decompression_test_with_options(
+        include_str!("samples/truage-temporal.cbor.hex"),
+        include_str!("samples/truage-temporal.jsonld"),
+        DecodeOptions {
+            codecs: temporal_codecs(),
+            ..Default::default()
+        },
+    )
+    .await
+}
+
 #[tokio::test]
 async fn encode_uncompressible() {
     let json: cbor_ld::JsonValue = include_str!("samples/uncompressible.jsonld")
@@ -103,3 +153,1513 @@ async fn decode_vcb_mrz() {
     )
     .await
 }
+
+#[tokio::test]
+async fn encode_temporal() {
+    compression_test_with_options(
+        include_str!("samples/temporal.jsonld"),
+        include_str!("samples/temporal.cbor.hex"),
+        EncodeOptions {
+            codecs: temporal_codecs(),
+            ..Default::default()
+        },
+    )
+    .await
+}
+
+#[tokio::test]
+async fn decode_temporal() {
+    decompression_test_with_options(
+        include_str!("samples/temporal.cbor.hex"),
+        include_str!("samples/temporal.jsonld"),
+        DecodeOptions {
+            codecs: temporal_codecs(),
+            ..Default::default()
+        },
+    )
+    .await
+}
+
+#[tokio::test]
+async fn default_temporal() {
+    // Without the temporal codecs, values are stored as text.
+    let input = include_str!("samples/temporal.jsonld");
+    let default_size = round_trip_size_test(input, EncodeOptions::default()).await;
+    let expected_size = hex::decode(include_str!("samples/temporal.cbor.hex"))
+        .unwrap()
+        .len();
+    assert!(expected_size < default_size);
+
+    let bytes = hex::decode(include_str!("samples/temporal.cbor.hex")).unwrap();
+    assert!(cbor_ld::decode_from_bytes(&bytes, create_context_loader())
+        .await
+        .is_err())
+}
+
+#[tokio::test]
+async fn round_trip_json_literal() {
+    round_trip_test(include_str!("samples/json-literal.jsonld")).await
+}
+
+#[tokio::test]
+async fn round_trip_graph() {
+    round_trip_test(include_str!("samples/graph.jsonld")).await
+}
+
+#[tokio::test]
+async fn round_trip_multilingual() {
+    round_trip_test(include_str!("samples/multilingual.jsonld")).await
+}
+
+#[tokio::test]
+async fn common_language_tags() {
+    let input = include_str!("samples/multilingual.jsonld");
+    let json: cbor_ld::JsonValue = input.parse().unwrap();
+    let default_size = round_trip_size_test(input, EncodeOptions::default()).await;
+
+    let codecs = || Codecs {
+        language: LanguageCodec::common(),
+        ..Default::default()
+    };
+    let options = EncodeOptions {
+        codecs: codecs(),
+        ..Default::default()
+    };
+    let bytes = cbor_ld::encode_to_bytes_with(&json, create_context_loader(), options)
+        .await
+        .unwrap();
+    let options = DecodeOptions {
+        codecs: codecs(),
+        ..Default::default()
+    };
+    let decoded = cbor_ld::decode_from_bytes_with(&bytes, create_context_loader(), options)
+        .await
+        .unwrap();
+
+    assert_eq!(decoded, json);
+    assert!(bytes.len() < default_size)
+}
+
+#[tokio::test]
+async fn compact_iri_vocabulary_terms() {
+    let json: cbor_ld::JsonValue = include_str!("samples/curie.jsonld").parse().unwrap();
+    let bytes = cbor_ld::encode_to_bytes(&json, create_context_loader())
+        .await
+        .unwrap();
+    let decoded = cbor_ld::decode_from_bytes(&bytes, create_context_loader())
+        .await
+        .unwrap();
+
+    // Compact IRIs are expanded by concatenating the prefix IRI and the
+    // suffix.
+    let decoded = decoded.as_object().unwrap();
+    let types = decoded.get_unique("type").unwrap().unwrap();
+    assert_eq!(
+        types.as_array().unwrap()[0].as_str(),
+        Some("http://schema.org/Person")
+    );
+    let category = decoded.get_unique("category").unwrap().unwrap();
+    assert_eq!(category.as_str(), Some("http://schema.org/Organization"))
+}
+
+/// Application registry entry of the string dictionary tables.
+const DICTIONARY_ENTRY: u64 = 64;
+
+/// Tag recording the position of documents in shared state sequences.
+const SEQUENCE_TAG: u64 = 0xa001;
+
+/// Tag wrapping lossless side channels.
+const LOSSLESS_TAG: u64 = 0xa002;
+
+fn dictionary_registry() -> Registry {
+    let mut registry = Registry::new();
+    registry
+        .insert(
+            DICTIONARY_ENTRY,
+            Tables {
+                strings: Some(StringDictionary::credentials(0xa000)),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+    registry
+}
+
+#[tokio::test]
+async fn dictionary_compression() {
+    for input in [
+        include_str!("samples/prc.jsonld"),
+        include_str!("samples/cit.jsonld"),
+        include_str!("samples/note.jsonld"),
+        include_str!("samples/multilingual.jsonld"),
+    ] {
+        let json: cbor_ld::JsonValue = input.parse().unwrap();
+        let default_size = round_trip_size_test(input, EncodeOptions::default()).await;
+
+        let options = EncodeOptions {
+            compression_mode: CompressionMode::Compressed(RegistryEntry::Unknown(DICTIONARY_ENTRY)),
+            registry: dictionary_registry(),
+            ..Default::default()
+        };
+        let bytes = cbor_ld::encode_to_bytes_with(&json, create_context_loader(), options)
+            .await
+            .unwrap();
+        let options = DecodeOptions {
+            registry: dictionary_registry(),
+            ..Default::default()
+        };
+        let decoded = cbor_ld::decode_from_bytes_with(&bytes, create_context_loader(), options)
+            .await
+            .unwrap();
+
+        assert_eq!(decoded.as_unordered(), json.as_unordered());
+        assert!(bytes.len() <= default_size)
+    }
+}
+
+#[tokio::test]
+async fn dictionary_without_registry_entry() {
+    let json: cbor_ld::JsonValue = include_str!("samples/prc.jsonld").parse().unwrap();
+    let options = EncodeOptions {
+        compression_mode: CompressionMode::Compressed(RegistryEntry::Unknown(DICTIONARY_ENTRY)),
+        registry: dictionary_registry(),
+        ..Default::default()
+    };
+    let bytes = cbor_ld::encode_to_bytes_with(&json, create_context_loader(), options)
+        .await
+        .unwrap();
+
+    // Decoders without the dictionary do not know the registry entry.
+    let error = cbor_ld::decode_from_bytes(&bytes, create_context_loader())
+        .await
+        .unwrap_err();
+    assert!(matches!(
+        error.without_location(),
+        DecodeError::UnknownCompressionTable(DICTIONARY_ENTRY)
+    ));
+
+    // Dictionaries cannot be used with the default registry entry.
+    let tables = dictionary_registry().get(DICTIONARY_ENTRY).unwrap().clone();
+    let options = EncodeOptions {
+        default_table: Cow::Owned(tables.clone()),
+        ..Default::default()
+    };
+    let error = cbor_ld::encode_to_bytes_with(&json, create_context_loader(), options)
+        .await
+        .unwrap_err();
+    assert!(matches!(
+        error.without_location(),
+        EncodeError::UnregisteredStringDictionary
+    ));
+
+    let options = DecodeOptions {
+        default_tables: Cow::Owned(tables),
+        ..Default::default()
+    };
+    let bytes = cbor_ld::encode_to_bytes(&json, create_context_loader())
+        .await
+        .unwrap();
+    let error = cbor_ld::decode_from_bytes_with(&bytes, create_context_loader(), options)
+        .await
+        .unwrap_err();
+    assert!(matches!(
+        error.without_location(),
+        DecodeError::UnregisteredStringDictionary
+    ));
+
+    // Reserved registry entries cannot be bound.
+    for id in [0, 1, 100, 128] {
+        assert!(Registry::new().insert(id, Tables::default()).is_err())
+    }
+}
+
+#[tokio::test]
+async fn deduplication() {
+    for input in [
+        include_str!("samples/prc.jsonld"),
+        include_str!("samples/cit.jsonld"),
+        include_str!("samples/note.jsonld"),
+        include_str!("samples/truage.jsonld"),
+        include_str!("samples/vcb-aamva.jsonld"),
+        include_str!("samples/multilingual.jsonld"),
+        include_str!("samples/repeated.jsonld"),
+    ] {
+        let default_size = round_trip_size_test(input, EncodeOptions::default()).await;
+        let deduplicated_size = round_trip_size_test(
+            input,
+            EncodeOptions {
+                deduplicate: true,
+                ..Default::default()
+            },
+        )
+        .await;
+        assert!(deduplicated_size <= default_size)
+    }
+}
+
+#[test]
+fn encode_prc_sync() {
+    let json: cbor_ld::JsonValue = include_str!("samples/prc.jsonld").parse().unwrap();
+    let bytes = cbor_ld::encode_to_bytes_sync(&json, create_context_loader()).unwrap();
+    assert_eq!(
+        bytes,
+        hex::decode(include_str!("samples/prc.cbor.hex")).unwrap()
+    )
+}
+
+#[test]
+fn decode_prc_sync() {
+    let bytes = hex::decode(include_str!("samples/prc.cbor.hex")).unwrap();
+    let json = cbor_ld::decode_from_bytes_sync(&bytes, create_context_loader()).unwrap();
+    let expected: cbor_ld::JsonValue = include_str!("samples/prc.jsonld").parse().unwrap();
+    assert_eq!(json.as_unordered(), expected.as_unordered())
+}
+
+#[test]
+fn round_trip_preloaded_sync() {
+    let context: cbor_ld::JsonValue = include_str!("contexts/activitystreams").parse().unwrap();
+    let url = iri!("https://www.w3.org/ns/activitystreams").to_owned();
+    let store: HashMap<IriBuf, RemoteDocument> =
+        [(url.clone(), RemoteDocument::new(Some(url), None, context))]
+            .into_iter()
+            .collect();
+
+    let json: cbor_ld::JsonValue = include_str!("samples/note.jsonld").parse().unwrap();
+    let encoded = cbor_ld::encode_sync(&json, &store).unwrap();
+    let decoded = cbor_ld::decode_sync(&encoded, &store).unwrap();
+    assert_eq!(json.as_unordered(), decoded.as_unordered())
+}
+
+#[cfg(feature = "bundled-contexts")]
+#[test]
+fn encode_note_bundled() {
+    let json: cbor_ld::JsonValue = include_str!("samples/note.jsonld").parse().unwrap();
+    let bytes = cbor_ld::encode_to_bytes_sync(&json, cbor_ld::loader::BundledLoader).unwrap();
+    assert_eq!(
+        bytes,
+        hex::decode(include_str!("samples/note.cbor.hex")).unwrap()
+    );
+
+    let decoded = cbor_ld::decode_from_bytes_sync(&bytes, cbor_ld::loader::BundledLoader).unwrap();
+    assert_eq!(json.as_unordered(), decoded.as_unordered())
+}
+
+#[cfg(feature = "bundled-contexts")]
+#[test]
+fn round_trip_did_multikey_bundled() {
+    let json: cbor_ld::JsonValue = include_str!("samples/did-multikey.jsonld").parse().unwrap();
+    let bytes = cbor_ld::encode_to_bytes_sync(&json, cbor_ld::loader::BundledLoader).unwrap();
+    let decoded = cbor_ld::decode_from_bytes_sync(&bytes, cbor_ld::loader::BundledLoader).unwrap();
+    assert_eq!(json.as_unordered(), decoded.as_unordered())
+}
+
+#[tokio::test]
+async fn context_pinning() {
+    let json: cbor_ld::JsonValue = include_str!("samples/note.jsonld").parse().unwrap();
+    let context: cbor_ld::JsonValue = include_str!("contexts/activitystreams").parse().unwrap();
+    let url = iri!("https://www.w3.org/ns/activitystreams").to_owned();
+
+    let options = EncodeOptions {
+        context_pins: [(url.clone(), ContextDigest::of(&context))]
+            .into_iter()
+            .collect(),
+        ..Default::default()
+    };
+    assert!(
+        cbor_ld::encode_with(&json, create_context_loader(), options)
+            .await
+            .is_ok()
+    );
+
+    let options = EncodeOptions {
+        context_pins: [(url, ContextDigest([0; 32]))].into_iter().collect(),
+        ..Default::default()
+    };
+    let error = cbor_ld::encode_with(&json, create_context_loader(), options)
+        .await
+        .unwrap_err();
+    assert!(error.to_string().contains("context digest mismatch"))
+}
+
+#[tokio::test]
+async fn shared_context_cache() {
+    let cache = Arc::new(ContextCache::new());
+    let loader = CountingLoader::new(create_context_loader());
+    let inputs = [
+        include_str!("samples/prc.jsonld"),
+        include_str!("samples/truage.jsonld"),
+        include_str!("samples/repeated.jsonld"),
+    ];
+
+    for round in 0..2 {
+        let loads = loader.count.load(Ordering::Relaxed);
+
+        for input in inputs {
+            let json: cbor_ld::JsonValue = input.parse().unwrap();
+            let expected = cbor_ld::encode_to_bytes(&json, create_context_loader())
+                .await
+                .unwrap();
+
+            let options = EncodeOptions {
+                context_cache: Some(cache.clone()),
+                ..Default::default()
+            };
+            let bytes = cbor_ld::encode_to_bytes_with(&json, &loader, options)
+                .await
+                .unwrap();
+            assert_eq!(bytes, expected);
+
+            let options = DecodeOptions {
+                context_cache: Some(cache.clone()),
+                ..Default::default()
+            };
+            let decoded = cbor_ld::decode_from_bytes_with(&bytes, &loader, options)
+                .await
+                .unwrap();
+            assert_eq!(json.as_unordered(), decoded.as_unordered())
+        }
+
+        if round > 0 {
+            // Every context is served by the cache.
+            assert_eq!(loader.count.load(Ordering::Relaxed), loads)
+        }
+    }
+
+    assert!(!cache.is_empty())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn concurrent_session() {
+    let loader = CountingLoader::new(create_context_loader());
+    let session = Arc::new(cbor_ld::CborLd::new(loader));
+    let inputs = [
+        include_str!("samples/prc.jsonld"),
+        include_str!("samples/truage.jsonld"),
+        include_str!("samples/note.jsonld"),
+        include_str!("samples/repeated.jsonld"),
+    ];
+
+    let tasks: Vec<_> = (0..32)
+        .map(|i| {
+            let session = session.clone();
+            let json: cbor_ld::JsonValue = inputs[i % inputs.len()].parse().unwrap();
+            tokio::spawn(async move {
+                let expected = cbor_ld::encode_to_bytes(&json, create_context_loader())
+                    .await
+                    .unwrap();
+                let bytes = session.encode_to_bytes(&json).await.unwrap();
+                assert_eq!(bytes, expected);
+
+                let decoded = session.decode_from_bytes(&bytes).await.unwrap();
+                assert_eq!(json.as_unordered(), decoded.as_unordered())
+            })
+        })
+        .collect();
+
+    for task in tasks {
+        task.await.unwrap()
+    }
+
+    assert!(!session.context_cache().unwrap().is_empty())
+}
+
+#[tokio::test]
+async fn lenient_session() {
+    use cbor_ld::lenient::Warnings;
+
+    let mut json: cbor_ld::JsonValue = include_str!("samples/uncompressible.jsonld")
+        .parse()
+        .unwrap();
+    json.as_object_mut()
+        .unwrap()
+        .insert("id".into(), "not an IRI".into());
+
+    let strict = cbor_ld::CborLd::new(create_context_loader());
+    assert!(strict.encode(&json).await.is_err());
+
+    let warnings = Arc::new(Warnings::new());
+    let session = cbor_ld::CborLd::new(create_context_loader())
+        .with_lenient(true)
+        .with_warnings(warnings.clone());
+
+    let expected = cbor_ld::encode_with(
+        &json,
+        create_context_loader(),
+        EncodeOptions {
+            lenient: true,
+            ..Default::default()
+        },
+    )
+    .await
+    .unwrap();
+    let cbor = session.encode(&json).await.unwrap();
+    assert_eq!(cbor, expected);
+    assert!(!warnings.take().is_empty());
+
+    assert!(strict.decode(&cbor).await.is_err());
+    let decoded = session.decode(&cbor).await.unwrap();
+    assert_eq!(decoded.as_unordered(), json.as_unordered());
+    assert!(!warnings.take().is_empty())
+}
+
+#[tokio::test]
+async fn session_uncompressed() {
+    let json: cbor_ld::JsonValue = include_str!("samples/note.jsonld").parse().unwrap();
+    let session = cbor_ld::CborLd::new(create_context_loader())
+        .with_compression_mode(CompressionMode::Uncompressed);
+
+    assert!(matches!(
+        session.encode(&json).await,
+        Err(EncodeError::UnsupportedCompressionMode(0))
+    ))
+}
+
+#[tokio::test]
+async fn streaming() {
+    for input in [
+        include_str!("samples/prc.jsonld"),
+        include_str!("samples/cit.jsonld"),
+        include_str!("samples/note.jsonld"),
+        include_str!("samples/truage.jsonld"),
+        include_str!("samples/multilingual.jsonld"),
+        include_str!("samples/repeated.jsonld"),
+        include_str!("samples/graph.jsonld"),
+        include_str!("samples/json-literal.jsonld"),
+    ] {
+        let json: cbor_ld::JsonValue = input.parse().unwrap();
+        let expected = cbor_ld::encode_to_bytes(&json, create_context_loader())
+            .await
+            .unwrap();
+
+        let mut bytes = Vec::new();
+        cbor_ld::encode_stream(input.as_bytes(), &mut bytes, create_context_loader())
+            .await
+            .unwrap();
+        assert_eq!(bytes, expected);
+
+        let mut output = Vec::new();
+        cbor_ld::decode_stream(bytes.as_slice(), &mut output, create_context_loader())
+            .await
+            .unwrap();
+        let decoded: cbor_ld::JsonValue = std::str::from_utf8(&output).unwrap().parse().unwrap();
+        assert_eq!(json.as_unordered(), decoded.as_unordered())
+    }
+}
+
+#[tokio::test]
+async fn streaming_deferred_entries() {
+    // Root arrays of node objects that cannot be transformed item by item:
+    // a JSON literal, and an undefined term kept uncompressed.
+    let input = r#"{
+        "@context": "https://example.org/json-literal/v1",
+        "settings": [{"type": "not a JSON-LD type"}, {"ratio": 1.50}],
+        "undefined term": [{"nested": "value"}, {"other": [1, 2]}],
+        "type": "Configuration"
+    }"#;
+    let json: cbor_ld::JsonValue = input.parse().unwrap();
+    let options = || EncodeOptions {
+        lenient: true,
+        ..Default::default()
+    };
+    let expected = cbor_ld::encode_to_bytes_with(&json, create_context_loader(), options())
+        .await
+        .unwrap();
+
+    let mut bytes = Vec::new();
+    cbor_ld::encode_stream_with(
+        input.as_bytes(),
+        &mut bytes,
+        create_context_loader(),
+        options(),
+    )
+    .await
+    .unwrap();
+    assert_eq!(bytes, expected);
+
+    let mut output = Vec::new();
+    cbor_ld::decode_stream(bytes.as_slice(), &mut output, create_context_loader())
+        .await
+        .unwrap();
+    let decoded: cbor_ld::JsonValue = std::str::from_utf8(&output).unwrap().parse().unwrap();
+    assert_eq!(json.as_unordered(), decoded.as_unordered());
+
+    // Invalid input is reported as when parsed as a whole.
+    let error = cbor_ld::encode_stream(&b"{\"a\": [{}, }"[..], io::sink(), create_context_loader())
+        .await
+        .unwrap_err();
+    assert!(matches!(error, EncodeError::InvalidJson(_)))
+}
+
+#[tokio::test]
+async fn cbor_sequence() {
+    let documents: Vec<cbor_ld::JsonValue> = [
+        include_str!("samples/prc.jsonld"),
+        include_str!("samples/cit.jsonld"),
+        include_str!("samples/prc.jsonld"),
+        include_str!("samples/note.jsonld"),
+    ]
+    .into_iter()
+    .map(|input| input.parse().unwrap())
+    .collect();
+
+    // Independent documents.
+    let mut expected = Vec::new();
+    for json in &documents {
+        expected.extend(
+            cbor_ld::encode_to_bytes(json, create_context_loader())
+                .await
+                .unwrap(),
+        )
+    }
+    let bytes = cbor_ld::encode_sequence_to_bytes(&documents, create_context_loader())
+        .await
+        .unwrap();
+    assert_eq!(bytes, expected);
+
+    let decoded = cbor_ld::decode_sequence_from_bytes(&bytes, create_context_loader())
+        .await
+        .unwrap();
+    assert_eq!(decoded.len(), documents.len());
+    for (json, decoded) in documents.iter().zip(&decoded) {
+        assert_eq!(json.as_unordered(), decoded.as_unordered())
+    }
+
+    // Shared state.
+    let options = EncodeOptions {
+        shared_sequence_tag: Some(SEQUENCE_TAG),
+        ..Default::default()
+    };
+    let shared_bytes =
+        cbor_ld::encode_sequence_to_bytes_with(&documents, create_context_loader(), options)
+            .await
+            .unwrap();
+    assert!(shared_bytes.len() < bytes.len());
+
+    let options = DecodeOptions {
+        shared_sequence_tag: Some(SEQUENCE_TAG),
+        ..Default::default()
+    };
+    let decoded =
+        cbor_ld::decode_sequence_from_bytes_with(&shared_bytes, create_context_loader(), options)
+            .await
+            .unwrap();
+    assert_eq!(decoded.len(), documents.len());
+    for (json, decoded) in documents.iter().zip(&decoded) {
+        assert_eq!(json.as_unordered(), decoded.as_unordered())
+    }
+
+    // State sharing is signalled in the documents.
+    let shared_options = || DecodeOptions {
+        shared_sequence_tag: Some(SEQUENCE_TAG),
+        ..Default::default()
+    };
+    let error = cbor_ld::decode_sequence_from_bytes(&shared_bytes, create_context_loader())
+        .await
+        .unwrap_err();
+    assert!(matches!(
+        error.without_location(),
+        DecodeError::ExpectedNodeObject
+    ));
+
+    let other_options = DecodeOptions {
+        shared_sequence_tag: Some(SEQUENCE_TAG + 1),
+        ..Default::default()
+    };
+    let error = cbor_ld::decode_sequence_from_bytes_with(
+        &shared_bytes,
+        create_context_loader(),
+        other_options,
+    )
+    .await
+    .unwrap_err();
+    assert!(matches!(error, DecodeError::SequenceStateMismatch));
+
+    let error =
+        cbor_ld::decode_sequence_from_bytes_with(&bytes, create_context_loader(), shared_options())
+            .await
+            .unwrap_err();
+    assert!(matches!(error, DecodeError::SequenceStateMismatch));
+
+    let mut shared = cbor_ld::cbor_sequence_from_bytes(&shared_bytes).unwrap();
+    shared.swap(1, 2);
+    let error = cbor_ld::decode_sequence_with(&shared, create_context_loader(), shared_options())
+        .await
+        .unwrap_err();
+    assert!(matches!(error, DecodeError::SequenceStateMismatch));
+
+    let error = cbor_ld::decode_with(
+        &shared[0],
+        create_context_loader(),
+        DecodeOptions {
+            shared_sequence_tag: Some(SEQUENCE_TAG),
+            ..Default::default()
+        },
+    )
+    .await
+    .unwrap_err();
+    assert!(matches!(error, DecodeError::SequenceStateMismatch))
+}
+
+#[derive(Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+struct Note {
+    #[serde(rename = "@context")]
+    context: String,
+
+    #[serde(rename = "type")]
+    type_: String,
+
+    summary: String,
+    content: String,
+}
+
+#[tokio::test]
+async fn serde_round_trip() {
+    let note = Note {
+        context: "https://www.w3.org/ns/activitystreams".to_owned(),
+        type_: "Note".to_owned(),
+        summary: "A note".to_owned(),
+        content: "This is an example note.".to_owned(),
+    };
+
+    let bytes = cbor_ld::to_vec(&note, create_context_loader())
+        .await
+        .unwrap();
+    assert_eq!(
+        bytes,
+        hex::decode(include_str!("samples/note.cbor.hex")).unwrap()
+    );
+
+    let decoded: Note = cbor_ld::from_slice(&bytes, create_context_loader())
+        .await
+        .unwrap();
+    assert_eq!(decoded, note)
+}
+
+#[cfg(feature = "serde_json")]
+#[tokio::test]
+async fn serde_json_round_trip() {
+    for (input, expected_hex) in [
+        (
+            include_str!("samples/prc.jsonld"),
+            Some(include_str!("samples/prc.cbor.hex")),
+        ),
+        (include_str!("samples/temporal.jsonld"), None),
+        (include_str!("samples/json-literal.jsonld"), None),
+        (include_str!("samples/multilingual.jsonld"), None),
+    ] {
+        let json: serde_json::Value = serde_json::from_str(input).unwrap();
+        let cbor = cbor_ld::encode_serde_json(&json, create_context_loader())
+            .await
+            .unwrap();
+
+        if let Some(expected_hex) = expected_hex {
+            let bytes = cbor_ld::cbor_into_bytes(cbor.clone());
+            assert_eq!(bytes, hex::decode(expected_hex.trim()).unwrap())
+        }
+
+        let decoded = cbor_ld::decode_serde_json(&cbor, create_context_loader())
+            .await
+            .unwrap();
+        assert_eq!(decoded, json)
+    }
+}
+
+#[tokio::test]
+async fn lossless_round_trip() {
+    for input in [
+        include_str!("samples/curie.jsonld"),
+        include_str!("samples/prc.jsonld"),
+        include_str!("samples/cit.jsonld"),
+        include_str!("samples/json-literal.jsonld"),
+        include_str!("samples/multilingual.jsonld"),
+    ] {
+        let json: cbor_ld::JsonValue = input.parse().unwrap();
+
+        let options = EncodeOptions {
+            lossless_tag: Some(LOSSLESS_TAG),
+            ..Default::default()
+        };
+        let bytes = cbor_ld::encode_to_bytes_with(&json, create_context_loader(), options)
+            .await
+            .unwrap();
+
+        let options = DecodeOptions {
+            lossless_tag: Some(LOSSLESS_TAG),
+            ..Default::default()
+        };
+        let decoded = cbor_ld::decode_from_bytes_with(&bytes, create_context_loader(), options)
+            .await
+            .unwrap();
+        assert_eq!(decoded, json);
+        assert_eq!(decoded.to_string(), json.to_string())
+    }
+
+    // The side channel tag is chosen by the application.
+    let json: cbor_ld::JsonValue = include_str!("samples/curie.jsonld").parse().unwrap();
+    let options = EncodeOptions {
+        lossless_tag: Some(LOSSLESS_TAG),
+        ..Default::default()
+    };
+    let bytes = cbor_ld::encode_to_bytes_with(&json, create_context_loader(), options)
+        .await
+        .unwrap();
+    let error = cbor_ld::decode_from_bytes(&bytes, create_context_loader())
+        .await
+        .unwrap_err();
+    assert!(matches!(
+        error.without_location(),
+        DecodeError::ExpectedNodeObject
+    ));
+
+    // Without the side channel, entry order and compact IRIs are lost.
+    let json: cbor_ld::JsonValue = include_str!("samples/curie.jsonld").parse().unwrap();
+    let bytes = cbor_ld::encode_to_bytes(&json, create_context_loader())
+        .await
+        .unwrap();
+    let decoded = cbor_ld::decode_from_bytes(&bytes, create_context_loader())
+        .await
+        .unwrap();
+    assert_ne!(decoded.to_string(), json.to_string());
+    assert!(decoded.to_string().contains("http://schema.org/Person"))
+}
+
+#[tokio::test]
+async fn lossless_sequence() {
+    let documents: Vec<cbor_ld::JsonValue> = [
+        include_str!("samples/curie.jsonld"),
+        include_str!("samples/note.jsonld"),
+        include_str!("samples/curie.jsonld"),
+    ]
+    .into_iter()
+    .map(|input| input.parse().unwrap())
+    .collect();
+
+    for shared_sequence_tag in [None, Some(SEQUENCE_TAG)] {
+        let bytes = cbor_ld::encode_sequence_to_bytes_with(
+            &documents,
+            create_context_loader(),
+            EncodeOptions {
+                lossless_tag: Some(LOSSLESS_TAG),
+                deduplicate: true,
+                shared_sequence_tag,
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+
+        let decoded = cbor_ld::decode_sequence_from_bytes_with(
+            &bytes,
+            create_context_loader(),
+            DecodeOptions {
+                shared_sequence_tag,
+                lossless_tag: Some(LOSSLESS_TAG),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+        assert_eq!(decoded, documents)
+    }
+}
+
+#[tokio::test]
+async fn verify_round_trip() {
+    for input in [
+        include_str!("samples/note.jsonld"),
+        include_str!("samples/prc.jsonld"),
+        include_str!("samples/cit.jsonld"),
+        include_str!("samples/curie.jsonld"),
+        include_str!("samples/json-literal.jsonld"),
+        include_str!("samples/multilingual.jsonld"),
+        include_str!("samples/temporal.jsonld"),
+    ] {
+        let json: cbor_ld::JsonValue = input.parse().unwrap();
+        let report = cbor_ld::verify::verify_round_trip(&json, create_context_loader())
+            .await
+            .unwrap();
+
+        assert!(report.quad_count > 0);
+        assert!(report.is_equivalent(), "{report}")
+    }
+}
+
+#[tokio::test]
+async fn decode_expanded_and_rdf() {
+    use json_ld::{syntax::IntoJsonWithContext, JsonLdProcessor};
+
+    for input in [
+        include_str!("samples/cit.jsonld"),
+        include_str!("samples/curie.jsonld"),
+        include_str!("samples/graph.jsonld"),
+        include_str!("samples/json-literal.jsonld"),
+        include_str!("samples/multilingual.jsonld"),
+        include_str!("samples/note.jsonld"),
+        include_str!("samples/prc.jsonld"),
+        include_str!("samples/repeated.jsonld"),
+        include_str!("samples/temporal.jsonld"),
+        include_str!("samples/truage.jsonld"),
+        include_str!("samples/truage-temporal.jsonld"),
+        include_str!("samples/vcb-aamva.jsonld"),
+        include_str!("samples/vcb-mrz.jsonld"),
+    ] {
+        let json: cbor_ld::JsonValue = input.parse().unwrap();
+        let cbor = cbor_ld::encode(&json, create_context_loader())
+            .await
+            .unwrap();
+
+        let mut expected = cbor_ld::JsonValue::Array(
+            RemoteDocument::new(None, None, json.clone())
+                .expand(&create_context_loader())
+                .await
+                .unwrap()
+                .into_iter()
+                .map(|object| object.into_json_with(&()))
+                .collect(),
+        );
+        expected.canonicalize();
+
+        let expanded = cbor_ld::decode_expanded(&cbor, create_context_loader())
+            .await
+            .unwrap();
+        assert_eq!(expanded, expected);
+
+        let dataset = cbor_ld::decode_to_rdf(&cbor, create_context_loader())
+            .await
+            .unwrap();
+        // Quads follow the order of the decoded entries, and so do blank
+        // node labels.
+        let unlabeled = |quad: String| {
+            let terms = quad
+                .split(' ')
+                .map(|t| if t.starts_with("_:") { "_:" } else { t });
+            terms.collect::<Vec<_>>().join(" ")
+        };
+        let mut quads: Vec<_> = dataset.0.iter().map(|q| unlabeled(q.to_string())).collect();
+        quads.sort_unstable();
+        let mut expected_quads: Vec<_> = RemoteDocument::new(None, None, json)
+            .to_rdf(
+                json_ld::rdf_types::generator::Blank::new(),
+                &create_context_loader(),
+            )
+            .await
+            .unwrap()
+            .cloned_quads()
+            .map(|quad| unlabeled(quad.to_string()))
+            .collect();
+        expected_quads.sort_unstable();
+        assert_eq!(quads, expected_quads);
+        assert!(dataset.to_string().lines().all(|line| line.ends_with(" .")))
+    }
+}
+
+#[tokio::test]
+async fn decode_expanded_lenient() {
+    use json_ld::{syntax::IntoJsonWithContext, JsonLdProcessor};
+
+    let mut json: cbor_ld::JsonValue = include_str!("samples/uncompressible.jsonld")
+        .parse()
+        .unwrap();
+    json.as_object_mut().unwrap().insert(
+        "https://example.com/vocabs#undefined".into(),
+        r#"{ "summary": "Nested", "https://example.com/vocabs#size": [1, "a"] }"#
+            .parse()
+            .unwrap(),
+    );
+
+    let options = || EncodeOptions {
+        lenient: true,
+        warnings: Some(Arc::new(cbor_ld::lenient::Warnings::new())),
+        ..Default::default()
+    };
+    let cbor = cbor_ld::encode_with(&json, create_context_loader(), options())
+        .await
+        .unwrap();
+
+    let mut expected = cbor_ld::JsonValue::Array(
+        RemoteDocument::new(None, None, json)
+            .expand(&create_context_loader())
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|object| object.into_json_with(&()))
+            .collect(),
+    );
+    expected.canonicalize();
+
+    let options = DecodeOptions {
+        lenient: true,
+        warnings: options().warnings,
+        ..Default::default()
+    };
+    let expanded = cbor_ld::decode_expanded_with(&cbor, create_context_loader(), options)
+        .await
+        .unwrap();
+    assert_eq!(expanded, expected)
+}
+
+#[tokio::test]
+async fn decode_expanded_without_second_pass() {
+    let json: cbor_ld::JsonValue = include_str!("samples/prc.jsonld").parse().unwrap();
+    let cbor = cbor_ld::encode(&json, create_context_loader())
+        .await
+        .unwrap();
+
+    // Once the contexts are processed and cached, expanding the document
+    // needs no loader: a second expansion pass would load them again.
+    let cache = Arc::new(ContextCache::new());
+    let options = || cbor_ld::DecodeOptions {
+        context_cache: Some(cache.clone()),
+        ..Default::default()
+    };
+    let decoded = cbor_ld::decode_with(&cbor, create_context_loader(), options())
+        .await
+        .unwrap();
+    assert!(
+        cbor_ld::decode_with(&cbor, json_ld::NoLoader, Default::default())
+            .await
+            .is_err()
+    );
+
+    let expanded = cbor_ld::decode_expanded_with(&cbor, json_ld::NoLoader, options())
+        .await
+        .unwrap();
+    let expected = cbor_ld::decode_expanded(&cbor, create_context_loader())
+        .await
+        .unwrap();
+    assert_eq!(expanded, expected);
+
+    let dataset = cbor_ld::decode_to_rdf_with(&cbor, json_ld::NoLoader, options())
+        .await
+        .unwrap();
+    let expected = cbor_ld::decode_to_rdf(&cbor, create_context_loader())
+        .await
+        .unwrap();
+    assert_eq!(dataset, expected);
+
+    // Decoding itself reuses the cached contexts the same way.
+    let cached = cbor_ld::decode_with(&cbor, json_ld::NoLoader, options())
+        .await
+        .unwrap();
+    assert_eq!(cached, decoded);
+}
+
+#[tokio::test]
+async fn encode_expanded_and_rdf() {
+    for (input, contexts) in [
+        (
+            include_str!("samples/prc.jsonld"),
+            &[
+                iri!("https://www.w3.org/2018/credentials/v1"),
+                iri!("https://w3id.org/citizenship/v1"),
+            ][..],
+        ),
+        (
+            include_str!("samples/curie.jsonld"),
+            &[iri!("https://example.org/curie/v1")][..],
+        ),
+    ] {
+        let contexts: Vec<IriBuf> = contexts.iter().map(|iri| (*iri).to_owned()).collect();
+        let json: cbor_ld::JsonValue = input.parse().unwrap();
+        let expected = cbor_ld::encode(&json, create_context_loader())
+            .await
+            .unwrap();
+
+        let expanded = cbor_ld::decode_expanded(&expected, create_context_loader())
+            .await
+            .unwrap();
+        let cbor = cbor_ld::encode_expanded(&expanded, &contexts, create_context_loader())
+            .await
+            .unwrap();
+        assert_eq!(cbor, expected);
+
+        let nquads = cbor_ld::decode_to_rdf(&expected, create_context_loader())
+            .await
+            .unwrap()
+            .to_string();
+        let dataset: cbor_ld::RdfDataset = nquads.parse().unwrap();
+        let cbor = cbor_ld::encode_rdf(&dataset, &contexts, create_context_loader())
+            .await
+            .unwrap();
+        assert_eq!(cbor, expected)
+    }
+}
+
+#[tokio::test]
+async fn encode_rdf_multiple_roots() {
+    let json: cbor_ld::JsonValue = include_str!("samples/graph.jsonld").parse().unwrap();
+    let contexts = [iri!("https://www.w3.org/ns/activitystreams").to_owned()];
+    let expected = cbor_ld::decode_to_rdf(
+        &cbor_ld::encode(&json, create_context_loader())
+            .await
+            .unwrap(),
+        create_context_loader(),
+    )
+    .await
+    .unwrap();
+
+    let cbor = cbor_ld::encode_rdf(&expected, &contexts, create_context_loader())
+        .await
+        .unwrap();
+    let decoded = cbor_ld::decode_to_rdf(&cbor, create_context_loader())
+        .await
+        .unwrap();
+
+    let sorted = |dataset: &cbor_ld::RdfDataset| {
+        let mut lines: Vec<_> = dataset.to_string().lines().map(str::to_owned).collect();
+        lines.sort();
+        lines
+    };
+    assert_eq!(sorted(&decoded), sorted(&expected))
+}
+
+#[tokio::test]
+async fn rdf_to_expanded_json() {
+    use json_ld::{rdf_types::generator, JsonLdProcessor};
+
+    for input in [
+        include_str!("samples/prc.jsonld"),
+        include_str!("samples/cit.jsonld"),
+        include_str!("samples/note.jsonld"),
+        include_str!("samples/curie.jsonld"),
+        include_str!("samples/graph.jsonld"),
+        include_str!("samples/json-literal.jsonld"),
+        include_str!("samples/multilingual.jsonld"),
+    ] {
+        let json: cbor_ld::JsonValue = input.parse().unwrap();
+        let cbor = cbor_ld::encode(&json, create_context_loader())
+            .await
+            .unwrap();
+        let dataset = cbor_ld::decode_to_rdf(&cbor, create_context_loader())
+            .await
+            .unwrap();
+
+        // Converting the expanded document back to RDF with the JSON-LD
+        // processor gives the same dataset.
+        let document = RemoteDocument::new(None, None, dataset.to_expanded_json());
+        let mut rdf = document
+            .to_rdf(generator::Blank::new(), &create_context_loader())
+            .await
+            .unwrap();
+        let round_trip = cbor_ld::RdfDataset(rdf.cloned_quads().collect());
+
+        assert_eq!(
+            cbor_ld::verify::canonical_nquads(&round_trip),
+            cbor_ld::verify::canonical_nquads(&dataset)
+        )
+    }
+}
+
+#[tokio::test]
+async fn error_locations() {
+    let mut json: cbor_ld::JsonValue = include_str!("samples/prc.jsonld").parse().unwrap();
+    json.as_object_mut()
+        .unwrap()
+        .get_unique_mut("credentialSubject")
+        .unwrap()
+        .unwrap()
+        .as_object_mut()
+        .unwrap()
+        .insert("undefined/term".into(), "value".into());
+
+    let error = cbor_ld::encode(&json, create_context_loader())
+        .await
+        .unwrap_err();
+    let location = error.location().unwrap();
+    assert_eq!(location.path, "/credentialSubject/undefined~1term");
+    assert_eq!(location.term.as_deref(), Some("undefined/term"));
+    assert!(matches!(
+        error.without_location(),
+        cbor_ld::EncodeError::MissingIdFor(_)
+    ));
+
+    // Add an undefined compressed term to the first nested node (`proof`).
+    let json: cbor_ld::JsonValue = include_str!("samples/prc.jsonld").parse().unwrap();
+    let mut cbor = cbor_ld::encode(&json, create_context_loader())
+        .await
+        .unwrap();
+    let cbor_ld::CborValue::Tag(_, body) = &mut cbor else {
+        panic!("expected CBOR-LD tag")
+    };
+    let (node_key, node) = body
+        .as_map_mut()
+        .unwrap()
+        .iter_mut()
+        .find(|(_, value)| value.is_map())
+        .unwrap();
+    let node_key = i128::from(node_key.as_integer().unwrap());
+    node.as_map_mut()
+        .unwrap()
+        .push((9999.into(), cbor_ld::CborValue::Null));
+
+    let error = cbor_ld::decode(&cbor, create_context_loader())
+        .await
+        .unwrap_err();
+    let location = error.location().unwrap();
+    assert_eq!(location.path, format!("$.{node_key}.9999"));
+    assert_eq!(location.term.as_deref(), Some("proof"));
+    assert_eq!(
+        error.to_string(),
+        format!(
+            "undefined compressed term: Integer(Integer(9999)) \
+            (at `$.{node_key}.9999`, term `proof`)"
+        )
+    )
+}
+
+#[tokio::test]
+async fn lenient_round_trip() {
+    use cbor_ld::lenient::{WarningKind, Warnings};
+
+    let mut json: cbor_ld::JsonValue = include_str!("samples/uncompressible.jsonld")
+        .parse()
+        .unwrap();
+    let object = json.as_object_mut().unwrap();
+    object.insert("id".into(), "not an IRI".into());
+    object.insert(
+        "undefined".into(),
+        r#"{ "nested": [1, "a"] }"#.parse().unwrap(),
+    );
+
+    let warnings = Arc::new(Warnings::new());
+    let take_warnings = || -> Vec<_> {
+        warnings
+            .take()
+            .into_iter()
+            .map(|w| (w.kind, w.location.path))
+            .collect()
+    };
+
+    let cbor = cbor_ld::encode_with(
+        &json,
+        create_context_loader(),
+        EncodeOptions {
+            lenient: true,
+            warnings: Some(warnings.clone()),
+            ..Default::default()
+        },
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        take_warnings(),
+        [
+            (WarningKind::InlineContext, "/@context/1".to_owned()),
+            (
+                WarningKind::UnhandledValue("invalid node ID `not an IRI`".to_owned()),
+                "/id".to_owned()
+            ),
+            (
+                WarningKind::UncompressedTerm("undefined".to_owned()),
+                "/undefined".to_owned()
+            )
+        ]
+    );
+
+    // Text keys are always accepted, but invalid values need the lenient
+    // mode.
+    assert!(cbor_ld::decode(&cbor, create_context_loader())
+        .await
+        .is_err());
+
+    let decoded = cbor_ld::decode_with(
+        &cbor,
+        create_context_loader(),
+        DecodeOptions {
+            lenient: true,
+            warnings: Some(warnings.clone()),
+            ..Default::default()
+        },
+    )
+    .await
+    .unwrap();
+    assert_eq!(decoded.as_unordered(), json.as_unordered());
+    assert_eq!(
+        take_warnings()
+            .into_iter()
+            .map(|(kind, _)| kind)
+            .collect::<Vec<_>>(),
+        [
+            WarningKind::InlineContext,
+            WarningKind::UnhandledValue("`iri` codec error: invalid IRI".to_owned()),
+            WarningKind::UncompressedTerm("undefined".to_owned())
+        ]
+    );
+
+    // Inline contexts are always accepted, but only reported in lenient
+    // mode.
+    let json: cbor_ld::JsonValue = include_str!("samples/uncompressible.jsonld")
+        .parse()
+        .unwrap();
+    let cbor = cbor_ld::encode_with(
+        &json,
+        create_context_loader(),
+        EncodeOptions {
+            lenient: true,
+            ..Default::default()
+        },
+    )
+    .await
+    .unwrap();
+    let decoded = cbor_ld::decode_with(
+        &cbor,
+        create_context_loader(),
+        DecodeOptions {
+            warnings: Some(warnings.clone()),
+            ..Default::default()
+        },
+    )
+    .await
+    .unwrap();
+    assert_eq!(decoded.as_unordered(), json.as_unordered());
+    assert_eq!(take_warnings(), [])
+}
+
+#[tokio::test]
+async fn compression_stats() {
+    use cbor_ld::stats::Handler;
+
+    let json: cbor_ld::JsonValue = include_str!("samples/prc.jsonld").parse().unwrap();
+    let stats = cbor_ld::stats::analyze(&json, create_context_loader())
+        .await
+        .unwrap();
+
+    let bytes = cbor_ld::encode_to_bytes(&json, create_context_loader())
+        .await
+        .unwrap();
+    assert_eq!(stats.size, bytes.len());
+
+    // Tag (3 bytes) and map header (1 byte).
+    let top_level_size: usize = stats.top_level().map(|e| e.size).sum();
+    assert_eq!(top_level_size + 4, stats.size);
+
+    let entry = |path: &str| stats.entries.iter().find(|e| e.path == path).unwrap();
+    assert_eq!(entry("/@context/0").handler, Some(Handler::Context));
+    assert_eq!(entry("/type/0").handler, Some(Handler::Vocab));
+    assert_eq!(
+        entry("/credentialSubject/id").handler,
+        Some(Handler::Iri(None))
+    );
+    assert_eq!(
+        entry("/issuanceDate").handler,
+        Some(Handler::TypeCodec(
+            "http://www.w3.org/2001/XMLSchema#dateTime".to_owned()
+        ))
+    );
+    assert_eq!(entry("/credentialSubject/givenName").depth, 2);
+    assert!(entry("/credentialSubject").handler.is_none());
+
+    assert!(stats
+        .uncompressed_strings()
+        .any(|e| e.path == "/credentialSubject/givenName"
+            && e.uncompressed.as_deref() == Some("JOHN")));
+    assert!(!stats
+        .uncompressed_strings()
+        .any(|e| e.path == "/issuanceDate"));
+    assert_eq!(stats.gzip_size.is_some(), cfg!(feature = "stats"));
+
+    let uncompressed = cbor_ld::stats::analyze_with(
+        &json,
+        create_context_loader(),
+        EncodeOptions {
+            compression_mode: CompressionMode::Uncompressed,
+            ..Default::default()
+        },
+    )
+    .await;
+    assert!(matches!(
+        uncompressed,
+        Err(EncodeError::UnsupportedCompressionMode(0))
+    ));
+}
+
+#[tokio::test]
+async fn generate_tables() {
+    use cbor_ld::tables::{GenerateOptions, Tables};
+    use std::borrow::Cow;
+
+    let corpus: Vec<cbor_ld::JsonValue> = [
+        include_str!("samples/vcb-aamva.jsonld"),
+        include_str!("samples/vcb-mrz.jsonld"),
+        include_str!("samples/prc.jsonld"),
+    ]
+    .into_iter()
+    .map(|input| input.parse().unwrap())
+    .collect();
+
+    let generated = cbor_ld::tables::generate_tables(
+        &corpus,
+        create_context_loader(),
+        GenerateOptions::default(),
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(generated.documents, 3);
+    assert!(generated.size_after < generated.size_before);
+
+    let uncompressed = cbor_ld::tables::generate_tables(
+        &corpus,
+        create_context_loader(),
+        GenerateOptions {
+            encode: EncodeOptions {
+                compression_mode: CompressionMode::Uncompressed,
+                ..Default::default()
+            },
+            ..Default::default()
+        },
+    )
+    .await;
+    assert!(matches!(
+        uncompressed,
+        Err(EncodeError::UnsupportedCompressionMode(0))
+    ));
+
+    let tables = &generated.tables;
+    for iri in [
+        iri!("https://www.w3.org/ns/credentials/v2"),
+        iri!("https://w3id.org/vc-barcodes/v1"),
+        iri!("https://w3id.org/utopia/v2"),
+        iri!("https://w3id.org/citizenship/v1"),
+    ] {
+        assert!(tables.context.get_id(iri.as_iri_ref()).is_some(), "{iri}")
+    }
+
+    // Occurs in the two barcodes.
+    let cryptosuites = &tables.types
+        [&json_ld::Type::Iri(iri!("https://w3id.org/security#cryptosuiteString").to_owned())];
+    assert_eq!(cryptosuites.get_id("ecdsa-xi-2023"), Some(1));
+
+    // Round trip through the tables file.
+    let tables: Tables = toml::from_str(&toml::to_string(tables).unwrap()).unwrap();
+    let tables = Cow::<'static, Tables>::Owned(tables);
+
+    let mut size = 0;
+    for json in &corpus {
+        let bytes = cbor_ld::encode_to_bytes_with(
+            json,
+            create_context_loader(),
+            EncodeOptions {
+                default_table: tables.clone(),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+        size += bytes.len();
+
+        let decoded = cbor_ld::decode_from_bytes_with(
+            &bytes,
+            create_context_loader(),
+            DecodeOptions {
+                default_tables: tables.clone(),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+        assert_eq!(decoded.as_unordered(), json.as_unordered())
+    }
+
+    assert_eq!(size, generated.size_after)
+}
+
+#[tokio::test]
+async fn diagnostic_annotations() {
+    use cbor_ld::diagnostic::{annotations, Edn};
+
+    let bytes = hex::decode(include_str!("samples/vcb-aamva.cbor.hex").trim()).unwrap();
+    let edn = Edn::from_bytes(&bytes).unwrap();
+    let cbor: cbor_ld::CborValue = ciborium::from_reader(bytes.as_slice()).unwrap();
+
+    let annotations = annotations(&cbor, create_context_loader(), DecodeOptions::default())
+        .await
+        .unwrap();
+    let text = edn.annotated(&annotations).to_string();
+    assert!(text.contains("1: [ # @context\n    32768, # https://www.w3.org/ns/credentials/v2\n"));
+    assert!(text.contains("186: { # credentialSubject\n"));
+    assert!(text.contains(
+        "214: 4, # cryptosuite, `https://w3id.org/security#cryptosuiteString` type table\n"
+    ));
+
+    let text = edn.annotated(&annotations).with_offsets().to_string();
+    assert!(text.starts_with(&format!("1636({{ # at 0, {} bytes\n", bytes.len())));
+    assert!(text.contains(
+        "157: [ # at 15, 7 bytes: type\n    118, # at 18, 2 bytes: VerifiableCredential\n"
+    ));
+
+    assert_eq!(text.parse::<Edn>().unwrap(), edn);
+    assert_eq!(edn.to_string().parse::<Edn>().unwrap().to_bytes(), bytes);
+}
+
+#[tokio::test]
+async fn diagnostic_iri_codec_annotations() {
+    use cbor_ld::diagnostic::{annotations, Edn};
+
+    let bytes = hex::decode(include_str!("samples/prc.cbor.hex").trim()).unwrap();
+    let edn = Edn::from_bytes(&bytes).unwrap();
+    let cbor: cbor_ld::CborValue = ciborium::from_reader(bytes.as_slice()).unwrap();
+
+    let annotations = annotations(&cbor, create_context_loader(), DecodeOptions::default())
+        .await
+        .unwrap();
+    let text = edn.annotated(&annotations).to_string();
+
+    assert!(text.contains("112: [ # id, `https` IRI codec\n"));
+    assert!(text.contains("128: [ # image, `data` IRI codec\n"));
+    assert!(text.contains("198: 204, # proofPurpose, `@vocab` codec, assertionMethod\n"));
+}
+
+#[tokio::test]
+async fn lenient_invalid_type() {
+    use cbor_ld::lenient::{WarningKind, Warnings};
+
+    let mut json: cbor_ld::JsonValue = include_str!("samples/note.jsonld").parse().unwrap();
+    json.as_object_mut()
+        .unwrap()
+        .insert("type".into(), 110.into());
+
+    let error = cbor_ld::encode(&json, create_context_loader())
+        .await
+        .unwrap_err();
+    assert!(matches!(
+        error.without_location(),
+        EncodeError::InvalidVocabTermKind
+    ));
+
+    let warnings = Arc::new(Warnings::new());
+    let cbor = cbor_ld::encode_with(
+        &json,
+        create_context_loader(),
+        EncodeOptions {
+            lenient: true,
+            warnings: Some(warnings.clone()),
+            ..Default::default()
+        },
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        warnings
+            .take()
+            .into_iter()
+            .map(|w| (w.kind, w.location.path))
+            .collect::<Vec<_>>(),
+        [(
+            WarningKind::UnhandledValue("invalid vocabulary term".to_owned()),
+            "/type".to_owned()
+        )]
+    );
+
+    let decoded = cbor_ld::decode_with(
+        &cbor,
+        create_context_loader(),
+        DecodeOptions {
+            lenient: true,
+            ..Default::default()
+        },
+    )
+    .await
+    .unwrap();
+    assert_eq!(decoded.as_unordered(), json.as_unordered())
+}